@@ -0,0 +1,75 @@
+//! Attribution of changed files to workspace members, e.g. `cargo bench --bench attribution`;
+//!
+//! Compares the prefix tree used by `cargo cvm` against checking every member for every
+//! changed file, on a synthetic workspace of 4000 members.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::path::PathBuf;
+
+// Unit tests of the module are compiled out of the bench, leaving their imports unused;
+#[allow(unused_imports)]
+#[path = "../src/path_trie.rs"]
+mod path_trie;
+
+use path_trie::PathTrie;
+
+/// Returns the src directories of `members` members and `deltas` changed files spread across
+/// them, with some files outside of any member;
+fn workspace(members: usize, deltas: usize) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let dirs = (0..members)
+        .map(|n| PathBuf::from(format!("crates/group-{}/crate-{}/src", n % 40, n)))
+        .collect::<Vec<PathBuf>>();
+    let files = (0..deltas)
+        .map(|n| match n % 10 {
+            0 => PathBuf::from(format!("docs/page-{}.md", n)),
+            _ => dirs[(n * 7919) % members].join(format!("module/file-{}.rs", n)),
+        })
+        .collect();
+
+    (dirs, files)
+}
+
+fn attribution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("attribution");
+    // Checking every member is slow on large workspaces;
+    group.sample_size(10);
+
+    for members in [100, 1000, 4000] {
+        let (dirs, files) = workspace(members, 2_000);
+
+        group.bench_with_input(BenchmarkId::new("per-member", members), &members, |b, _| {
+            b.iter(|| {
+                files
+                    .iter()
+                    .filter(|file| {
+                        let file = file.display().to_string();
+                        dirs.iter()
+                            .any(|dir| file.contains(&*dir.display().to_string()))
+                    })
+                    .count()
+            })
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("prefix-tree", members),
+            &members,
+            |b, _| {
+                b.iter(|| {
+                    let mut trie = PathTrie::default();
+                    for dir in dirs.iter() {
+                        trie.insert(dir, dir.as_path());
+                    }
+                    files
+                        .iter()
+                        .filter(|file| trie.longest_prefix(black_box(file)).is_some())
+                        .count()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, attribution);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+# Meldungen von cargo cvm auf Deutsch; fehlende Meldungen erscheinen auf
+# Englisch, siehe en.ftl;
+
+## Diagnosen
+
+warning = Warnung: { $message }
+error = Fehler: { $message }
+strict-warning = { $message } (Warnungen sind mit --strict Fehler)
+no-manifest = `cargo cvm` muss in einem Verzeichnis mit einer `Cargo.toml`-Datei ausgeführt werden.
+    Datei existiert nicht: { $path }
+no-src-dir = src-Verzeichnis existiert nicht: { $path }
+outdated-found = Veraltete Version gefunden, Prozess wird erfolglos beendet
+jump-found = Versionssprung über mehrere Versionen gefunden, Prozess wird erfolglos beendet
+unversioned-found = Crate ohne Version gefunden, Prozess wird erfolglos beendet
+below-floor-found = Version unter ihrer Mindestversion gefunden, Prozess wird erfolglos beendet
+mismatch-found = Von der gemeinsamen Version abweichende Version gefunden, Prozess wird erfolglos beendet
+metadata-found = Rückschritt in den Metadaten gefunden, Prozess wird erfolglos beendet
+incomplete-found = Neue Crate mit unvollständigen Metadaten gefunden, Prozess wird erfolglos beendet
+changelog-found = Erhöhte Version ohne Changelog-Eintrag gefunden, Prozess wird erfolglos beendet
+path-dep-found = Pfadabhängigkeit ohne Version gefunden, Prozess wird erfolglos beendet
+release-train-found = Verstoß gegen den Release-Train gefunden, Prozess wird erfolglos beendet
+dirty-refused = das Arbeitsverzeichnis hat nicht committete Änderungen: { $files }; committen oder stashen Sie sie vor dem Anheben der Versionen, oder übergeben Sie --allow-dirty
+dirty = das Arbeitsverzeichnis hat nicht committete Änderungen: { $files }
+no-member = Kein Workspace-Mitglied heißt { $name } oder liegt unter diesem Pfad
+changelog-missing = Kein Changelog-Eintrag für { $entries }; fügen Sie die Einträge zuerst hinzu, keine Version wurde angehoben
+
+## Hooks
+
+hook-not-run = Der { $hook }-Hook { $script } konnte nicht ausgeführt werden: { $error }
+hook-failed = { $hook }-Hook für { $crate } { $version } fehlgeschlagen: { $script }
+pre-fix-failed = Der pre-fix-Hook ist für { $crates } fehlgeschlagen; keine Version wurde angehoben
+post-fix-failed = Der post-fix-Hook ist für { $crates } fehlgeschlagen; die Anhebungen werden nicht committet, `cargo cvm undo` macht sie rückgängig
+push-checking = cvm: prüfe { $ref }
+push-skipping = cvm: überspringe { $ref }, der Zielbranch existiert noch nicht
+
+## Bericht
+
+outdated-heading = Veraltet ({ $count }):
+tolerated-by = toleriert durch { $file }
+metadata-heading = Rückschritte in den Metadaten ({ $count }):
+incomplete-heading = Neue Crates mit unvollständigen Metadaten ({ $count }):
+changelog-heading = Fehlende Changelog-Einträge ({ $count }):
+below-floor-heading = Unter der Mindestversion ({ $count }):
+mismatched-heading = Nicht auf der gemeinsamen Version ({ $count }):
+path-deps-heading = Pfadabhängigkeiten ohne Version ({ $count }):
+added-heading = Hinzugefügt ({ $count }):
+removed-heading = Entfernt ({ $count }):
+sparse-heading = Außerhalb des Sparse-Checkouts ({ $count }):
+frozen-heading = Eingefroren ({ $count }):
+unversioned-heading = Ohne Version ({ $count }):
+up-to-date-heading = Aktuell ({ $count }):
+up-to-date-count = Aktuell: { $count } Crate(s); mit --show-ok auflisten
+target-up-to-date = { $target }: aktuell
+target-outdated = { $target }: { $count } veraltete Crate(s)
+
+## Veröffentlichungsbereitschaft
+
+due-heading = Zur Veröffentlichung fällig ({ $count }):
+nothing-due = Keine Crate ist zur Veröffentlichung fällig.
+untagged = Ohne Release-Tags ({ $count }): { $crates }
@@ -0,0 +1,64 @@
+# Messages of cargo cvm, in English, the default locale;
+#
+# Each message is an id followed by `=` and its text, in the syntax of Fluent
+# (https://projectfluent.org): `{ $name }` is replaced by a variable, and
+# indented lines continue the message on a new line. Other locales translate
+# these ids; messages missing from them are shown in English.
+
+## Diagnostics
+
+warning = warning: { $message }
+error = error: { $message }
+strict-warning = { $message } (warnings are errors with --strict)
+no-manifest = `cargo cvm` must be run in a directory containing a `Cargo.toml` file.
+    File does not exist at: { $path }
+no-src-dir = src directory does not exist at { $path }
+outdated-found = Found outdated version, exiting process unsuccessfully
+jump-found = Found version bump skipping versions, exiting process unsuccessfully
+unversioned-found = Found crate without a version, exiting process unsuccessfully
+below-floor-found = Found version below its floor, exiting process unsuccessfully
+mismatch-found = Found version differing from the unified version, exiting process unsuccessfully
+metadata-found = Found metadata regression, exiting process unsuccessfully
+incomplete-found = Found new crate with incomplete metadata, exiting process unsuccessfully
+changelog-found = Found bumped crate without a changelog entry, exiting process unsuccessfully
+path-dep-found = Found path dependency without a version, exiting process unsuccessfully
+release-train-found = Found release train violation, exiting process unsuccessfully
+dirty-refused = the working directory has uncommitted changes: { $files }; commit or stash them before bumping versions, or pass --allow-dirty
+dirty = the working directory has uncommitted changes: { $files }
+no-member = No workspace member is named { $name } or is at that path
+changelog-missing = No changelog entry for { $entries }; add the entries first, no version was bumped
+
+## Hooks
+
+hook-not-run = Failed to run the { $hook } hook { $script }: { $error }
+hook-failed = { $hook } hook failed for { $crate } { $version }: { $script }
+pre-fix-failed = The pre-fix hook failed for { $crates }; no version was bumped
+post-fix-failed = The post-fix hook failed for { $crates }; the bumps are not committed, run `cargo cvm undo` to revert them
+push-checking = cvm: checking { $ref }
+push-skipping = cvm: skipping { $ref }, the target branch does not exist yet
+
+## Report
+
+outdated-heading = Outdated ({ $count }):
+tolerated-by = tolerated by { $file }
+metadata-heading = Metadata regressions ({ $count }):
+incomplete-heading = New crates with incomplete metadata ({ $count }):
+changelog-heading = Missing changelog entries ({ $count }):
+below-floor-heading = Below floor ({ $count }):
+mismatched-heading = Not at the unified version ({ $count }):
+path-deps-heading = Path dependencies without a version ({ $count }):
+added-heading = Added ({ $count }):
+removed-heading = Removed ({ $count }):
+sparse-heading = Outside sparse checkout ({ $count }):
+frozen-heading = Frozen ({ $count }):
+unversioned-heading = Unversioned ({ $count }):
+up-to-date-heading = Up to date ({ $count }):
+up-to-date-count = Up to date: { $count } crate(s); use --show-ok to list them
+target-up-to-date = { $target }: up to date
+target-outdated = { $target }: { $count } outdated crate(s)
+
+## Release readiness
+
+due-heading = Due for release ({ $count }):
+nothing-due = No crate is due for release.
+untagged = Without release tags ({ $count }): { $crates }
@@ -0,0 +1,129 @@
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory of the generated files inside cargo's target directory;
+pub const ARTIFACT_DIR: &str = "cvm";
+
+/// Name of the file listing the paths git ignores, in the workspace root;
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// Returns the directory of the files CVM generates, e.g. plans and the registry index cache:
+/// `$CVM_HOME` if set, else `artifact-dir` of `.cvm.toml`, else `cvm` in cargo's target
+/// directory, i.e. `$CARGO_TARGET_DIR` or `target`; relative paths are relative to `root`;
+pub fn artifact_dir(root: &Path, configured: Option<&Path>) -> PathBuf {
+    resolve(root, configured, |var| {
+        std::env::var_os(var).filter(|value| !value.is_empty())
+    })
+}
+
+/// `artifact_dir`, reading the environment variables with `var`;
+fn resolve(
+    root: &Path,
+    configured: Option<&Path>,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> PathBuf {
+    if let Some(home) = var("CVM_HOME") {
+        return root.join(home);
+    }
+    if let Some(dir) = configured {
+        return root.join(dir);
+    }
+
+    let target = var("CARGO_TARGET_DIR")
+        .or_else(|| var("CARGO_BUILD_TARGET_DIR"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"));
+    root.join(target).join(ARTIFACT_DIR)
+}
+
+/// Returns the `.gitignore` lines ignoring the artifact directory `dir`, or None if it is not
+/// inside `root`, where there is nothing to ignore;
+pub fn gitignore_fragment(root: &Path, dir: &Path) -> Option<String> {
+    let relative = dir.strip_prefix(root).ok()?;
+    let components = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Option<Vec<String>>>()
+        .filter(|components| !components.is_empty())?;
+
+    Some(format!(
+        "# Files generated by cargo cvm, e.g. plans and caches;\n/{}/\n",
+        components.join("/")
+    ))
+}
+
+/// Returns `gitignore` with the fragment appended, or None if it already contains its pattern;
+pub fn with_fragment(gitignore: &str, fragment: &str) -> Option<String> {
+    let pattern = fragment.lines().last().unwrap_or_default();
+    if gitignore.lines().any(|line| line.trim() == pattern) {
+        return None;
+    }
+
+    let mut contents = gitignore.to_string();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(fragment);
+    Some(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gitignore_fragment, resolve, with_fragment};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_artifact_dir() {
+        let root = Path::new("/work/repo");
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |var: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == var)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+
+        assert_eq!(
+            resolve(root, None, env(&[])),
+            PathBuf::from("/work/repo/target/cvm")
+        );
+        assert_eq!(
+            resolve(root, None, env(&[("CARGO_TARGET_DIR", "/tmp/target")])),
+            PathBuf::from("/tmp/target/cvm")
+        );
+        assert_eq!(
+            resolve(root, Some(Path::new(".cache/cvm")), env(&[])),
+            PathBuf::from("/work/repo/.cache/cvm")
+        );
+        // `CVM_HOME` wins over the configuration;
+        assert_eq!(
+            resolve(
+                root,
+                Some(Path::new(".cache/cvm")),
+                env(&[("CVM_HOME", "/var/cache/cvm")])
+            ),
+            PathBuf::from("/var/cache/cvm")
+        );
+    }
+
+    #[test]
+    fn test_gitignore_fragment() {
+        let root = Path::new("/work/repo");
+        let fragment = gitignore_fragment(root, &root.join("target/cvm")).unwrap();
+        assert!(fragment.ends_with("\n/target/cvm/\n"));
+        assert_eq!(gitignore_fragment(root, Path::new("/var/cache/cvm")), None);
+
+        assert_eq!(
+            with_fragment("/target", &fragment),
+            Some(format!("/target\n{}", fragment))
+        );
+        assert_eq!(
+            with_fragment(&format!("/target\n{}", fragment), &fragment),
+            None
+        );
+    }
+}
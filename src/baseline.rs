@@ -0,0 +1,108 @@
+use crate::manager::{OutdatedCrate, ReleaseTrainViolation};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+/// Name of the file recording the findings tolerated by `--check`, read from the workspace root;
+pub const BASELINE_FILE: &str = ".cvm-baseline.json";
+
+/// Findings recorded by `cargo cvm baseline`, which `--check` does not fail on until they
+/// are fixed;
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub entries: Vec<BaselineEntry>,
+}
+
+/// A tolerated finding; it no longer applies once the version in the manifest changes;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Name of the check, i.e. `outdated` or `release-train`;
+    pub check: String,
+    pub cargo_toml: PathBuf,
+    pub version: String,
+}
+
+impl BaselineEntry {
+    pub fn outdated(krate: &OutdatedCrate) -> Self {
+        Self {
+            check: String::from("outdated"),
+            cargo_toml: krate.cargo_toml.clone(),
+            version: krate.version.to_string(),
+        }
+    }
+
+    pub fn violation(violation: &ReleaseTrainViolation) -> Self {
+        Self {
+            check: String::from("release-train"),
+            cargo_toml: violation.cargo_toml.clone(),
+            version: violation.new_version.to_string(),
+        }
+    }
+}
+
+impl Baseline {
+    /// Load the baseline from `dir`, tolerating nothing if no file exists;
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let path = dir.join(BASELINE_FILE);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&read_to_string(&path)?, &path)
+    }
+
+    /// Parse the baseline, e.g. as read from a git tree; `path` is for diagnostics;
+    pub fn parse(contents: &str, path: &Path) -> Result<Self, Error> {
+        serde_json::from_str(contents)
+            .map_err(|e| Error::msg(format!("Invalid baseline {:?}: {}", path, e)))
+    }
+
+    /// Save the baseline to `dir`, sorted so that updates produce small diffs;
+    pub fn save(&mut self, dir: &Path) -> Result<(), Error> {
+        self.entries.sort();
+        self.entries.dedup();
+        write(
+            dir.join(BASELINE_FILE),
+            serde_json::to_string_pretty(self)? + "\n",
+        )?;
+        Ok(())
+    }
+
+    pub fn contains(&self, entry: &BaselineEntry) -> bool {
+        self.entries.contains(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Baseline, BaselineEntry};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_baseline_format() -> Result<(), Box<dyn std::error::Error>> {
+        let baseline: Baseline = serde_json::from_str(
+            r#"{
+  "entries": [
+    {
+      "check": "outdated",
+      "cargo_toml": "crates/foo/Cargo.toml",
+      "version": "0.1.0"
+    }
+  ]
+}"#,
+        )?;
+
+        let mut entry = BaselineEntry {
+            check: String::from("outdated"),
+            cargo_toml: PathBuf::from("crates/foo/Cargo.toml"),
+            version: String::from("0.1.0"),
+        };
+        assert!(baseline.contains(&entry));
+
+        entry.version = String::from("0.2.0");
+        assert!(!baseline.contains(&entry));
+        Ok(())
+    }
+}
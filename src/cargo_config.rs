@@ -0,0 +1,195 @@
+use anyhow::Error;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Network settings of cargo's own configuration, i.e. the `[http]` and `[net]` tables of
+/// `.cargo/config.toml`, so that fetches behave like `cargo` in the same environment;
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CargoNetConfig {
+    /// `http.proxy`;
+    pub proxy: Option<String>,
+    /// `http.cainfo`, resolved against the directory containing `.cargo`;
+    pub cainfo: Option<PathBuf>,
+    /// `http.ssl-version`, or the minimum of its range, e.g. `tlsv1.2`;
+    pub ssl_version: Option<String>,
+    /// `http.check-revoke`;
+    pub check_revoke: Option<bool>,
+    /// `http.timeout` in seconds;
+    pub timeout: Option<u64>,
+    /// `net.retry`;
+    pub retry: Option<u32>,
+    /// `net.git-fetch-with-cli`;
+    pub git_fetch_with_cli: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    http: HttpTable,
+    net: NetTable,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+struct HttpTable {
+    proxy: Option<String>,
+    cainfo: Option<PathBuf>,
+    ssl_version: Option<SslVersion>,
+    check_revoke: Option<bool>,
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+struct NetTable {
+    retry: Option<u32>,
+    git_fetch_with_cli: Option<bool>,
+}
+
+/// `http.ssl-version` is either a version or a `{ min, max }` range;
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SslVersion {
+    Exact(String),
+    Range {
+        min: Option<String>,
+        max: Option<String>,
+    },
+}
+
+impl SslVersion {
+    fn minimum(self) -> Option<String> {
+        match self {
+            SslVersion::Exact(version) => Some(version),
+            SslVersion::Range { min, max } => min.or(max),
+        }
+    }
+}
+
+impl CargoNetConfig {
+    /// Load the settings as cargo would when run in `dir`: `CARGO_HTTP_*` and `CARGO_NET_*`
+    /// environment variables first, then `.cargo/config.toml` (or `.cargo/config`) in `dir` and
+    /// each of its ancestors, nearest first, then the one in `$CARGO_HOME`;
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let mut config = Self::from_env();
+
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+        let mut cargo_dirs = dir
+            .ancestors()
+            .map(|ancestor| ancestor.join(".cargo"))
+            .collect::<Vec<PathBuf>>();
+        if let Some(cargo_home) = cargo_home {
+            if !cargo_dirs.contains(&cargo_home) {
+                cargo_dirs.push(cargo_home);
+            }
+        }
+
+        for cargo_dir in cargo_dirs.iter() {
+            let path = match ["config.toml", "config"]
+                .iter()
+                .map(|name| cargo_dir.join(name))
+                .find(|path| path.is_file())
+            {
+                Some(path) => path,
+                None => continue,
+            };
+            let contents = read_to_string(&path)
+                .map_err(|e| Error::msg(format!("Failed to read {:?}: {}", path.display(), e)))?;
+            // Relative paths are relative to the directory containing `.cargo`;
+            let base = cargo_dir.parent().unwrap_or(cargo_dir);
+            config.merge(Self::parse(&contents, base).map_err(|e| {
+                Error::msg(format!(
+                    "Invalid cargo configuration {:?}: {}",
+                    path.display(),
+                    e
+                ))
+            })?);
+        }
+
+        Ok(config)
+    }
+
+    /// Parse a cargo configuration file, resolving relative paths against `base`;
+    pub fn parse(contents: &str, base: &Path) -> Result<Self, Error> {
+        let file: ConfigFile = toml::from_str(contents)?;
+
+        Ok(Self {
+            proxy: file.http.proxy.filter(|proxy| !proxy.is_empty()),
+            cainfo: file.http.cainfo.map(|cainfo| base.join(cainfo)),
+            ssl_version: file.http.ssl_version.and_then(SslVersion::minimum),
+            check_revoke: file.http.check_revoke,
+            timeout: file.http.timeout,
+            retry: file.net.retry,
+            git_fetch_with_cli: file.net.git_fetch_with_cli,
+        })
+    }
+
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        Self {
+            proxy: var("CARGO_HTTP_PROXY"),
+            cainfo: var("CARGO_HTTP_CAINFO").map(PathBuf::from),
+            ssl_version: var("CARGO_HTTP_SSL_VERSION"),
+            check_revoke: var("CARGO_HTTP_CHECK_REVOKE").and_then(|value| value.parse().ok()),
+            timeout: var("CARGO_HTTP_TIMEOUT").and_then(|value| value.parse().ok()),
+            retry: var("CARGO_NET_RETRY").and_then(|value| value.parse().ok()),
+            git_fetch_with_cli: var("CARGO_NET_GIT_FETCH_WITH_CLI")
+                .and_then(|value| value.parse().ok()),
+        }
+    }
+
+    /// Fill the settings not set yet from `other`, which takes lower precedence;
+    fn merge(&mut self, other: Self) {
+        self.proxy = self.proxy.take().or(other.proxy);
+        self.cainfo = self.cainfo.take().or(other.cainfo);
+        self.ssl_version = self.ssl_version.take().or(other.ssl_version);
+        self.check_revoke = self.check_revoke.or(other.check_revoke);
+        self.timeout = self.timeout.or(other.timeout);
+        self.retry = self.retry.or(other.retry);
+        self.git_fetch_with_cli = self.git_fetch_with_cli.or(other.git_fetch_with_cli);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CargoNetConfig;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_parse_cargo_config() -> Result<(), anyhow::Error> {
+        let mut config = CargoNetConfig::parse(
+            r#"
+            [http]
+            proxy = "http://proxy.example.com:3128"
+            cainfo = "certs/ca.pem"
+            ssl-version = { min = "tlsv1.2", max = "tlsv1.3" }
+
+            [net]
+            git-fetch-with-cli = true
+            "#,
+            Path::new("/work"),
+        )?;
+        config.merge(CargoNetConfig::parse(
+            "[http]\nproxy = \"http://other:3128\"\ntimeout = 30\n",
+            Path::new("/home/user"),
+        )?);
+
+        assert_eq!(
+            config,
+            CargoNetConfig {
+                proxy: Some(String::from("http://proxy.example.com:3128")),
+                cainfo: Some(PathBuf::from("/work/certs/ca.pem")),
+                ssl_version: Some(String::from("tlsv1.2")),
+                check_revoke: None,
+                timeout: Some(30),
+                retry: None,
+                git_fetch_with_cli: Some(true),
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use crate::baseline::BaselineEntry;
+use crate::manager::package_version_line;
+use crate::report::Report;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// A failed check of a crate, reported at the package version of its manifest;
+struct Finding<'a> {
+    /// Name of the check, e.g. `outdated`;
+    check: &'static str,
+    cargo_toml: &'a Path,
+    /// What the finding is about within the manifest, e.g. the target branch or the field,
+    /// which identifies it in later pipelines while its message changes;
+    key: String,
+    message: String,
+}
+
+/// Returns the findings of the report that are not tolerated by the baseline, in the order they
+/// were reported;
+fn findings(report: &Report) -> Vec<Finding<'_>> {
+    let outdated = report
+        .outdated
+        .iter()
+        .filter(|krate| !report.tolerated.contains(&BaselineEntry::outdated(krate)))
+        .filter(|krate| report.waiver(krate).is_none())
+        .map(|krate| Finding {
+            check: "outdated",
+            cargo_toml: &krate.cargo_toml,
+            key: krate.target.clone(),
+            message: krate.to_string(),
+        });
+    let violations = report
+        .violations
+        .iter()
+        .filter(|violation| {
+            !report
+                .tolerated
+                .contains(&BaselineEntry::violation(violation))
+        })
+        .map(|violation| Finding {
+            check: "release-train",
+            cargo_toml: &violation.cargo_toml,
+            key: violation.target.clone(),
+            message: violation.to_string(),
+        });
+    let jumps = report.jumps.iter().map(|jump| Finding {
+        check: "version-jump",
+        cargo_toml: &jump.cargo_toml,
+        key: jump.target.clone(),
+        message: jump.to_string(),
+    });
+
+    let unversioned = report
+        .unversioned
+        .iter()
+        .filter(|krate| krate.required)
+        .map(|krate| Finding {
+            check: "missing-version",
+            cargo_toml: &krate.cargo_toml,
+            key: String::new(),
+            message: krate.to_string(),
+        });
+
+    let path_deps = report
+        .path_deps
+        .iter()
+        .filter(|dep| !dep.fixed)
+        .map(|dep| Finding {
+            check: "path-dependency",
+            cargo_toml: &dep.cargo_toml,
+            key: format!("{} {}", dep.table, dep.dependency),
+            message: dep.to_string(),
+        });
+
+    let metadata = report.metadata.iter().map(|regression| Finding {
+        check: "metadata",
+        cargo_toml: &regression.cargo_toml,
+        key: format!("{} {}", regression.field, regression.target),
+        message: regression.to_string(),
+    });
+
+    let incomplete = report.incomplete.iter().map(|incomplete| Finding {
+        check: "new-crate-metadata",
+        cargo_toml: &incomplete.cargo_toml,
+        key: String::new(),
+        message: incomplete.to_string(),
+    });
+
+    let changelog = report.changelog.iter().map(|missing| Finding {
+        check: "changelog",
+        cargo_toml: &missing.cargo_toml,
+        key: missing.target.clone(),
+        message: missing.to_string(),
+    });
+
+    let below_floor = report.below_floor.iter().map(|below| Finding {
+        check: "floor",
+        cargo_toml: &below.cargo_toml,
+        key: String::new(),
+        message: below.to_string(),
+    });
+
+    let mismatched = report.mismatched.iter().map(|mismatch| Finding {
+        check: "unified",
+        cargo_toml: &mismatch.cargo_toml,
+        key: String::new(),
+        message: mismatch.to_string(),
+    });
+
+    outdated
+        .chain(violations)
+        .chain(jumps)
+        .chain(unversioned)
+        .chain(path_deps)
+        .chain(metadata)
+        .chain(incomplete)
+        .chain(changelog)
+        .chain(below_floor)
+        .chain(mismatched)
+        .collect()
+}
+
+/// Returns the one-based line of the package version in the manifest `cargo_toml`, relative to
+/// the repository root `root`, or the first line if it cannot be read;
+fn version_line(root: &Path, cargo_toml: &Path) -> usize {
+    read_to_string(root.join(cargo_toml))
+        .ok()
+        .and_then(|manifest| package_version_line(&manifest))
+        .map_or(1, |line| line + 1)
+}
+
+/// Returns the fingerprint of the finding, which the same finding keeps in later pipelines
+/// although its message changes, e.g. the suggested version or the crate name shown;
+fn fingerprint(finding: &Finding<'_>) -> String {
+    let id = format!(
+        "{}:{}:{}",
+        finding.check,
+        finding.cargo_toml.display(),
+        finding.key
+    );
+    format!("{:x}", Sha256::digest(id.as_bytes()))
+}
+
+/// Render the report as a GitLab Code Quality report, which merge requests display next to
+/// the changed lines; manifest paths are relative to the repository root `root`;
+pub fn code_quality(report: &Report, root: &Path) -> String {
+    let issues = findings(report)
+        .iter()
+        .map(|finding| {
+            json!({
+                "description": finding.message.lines().next().unwrap_or_default(),
+                "check_name": format!("cvm-{}", finding.check),
+                "fingerprint": fingerprint(finding),
+                "severity": "major",
+                "location": {
+                    "path": finding.cargo_toml.display().to_string(),
+                    "lines": { "begin": version_line(root, finding.cargo_toml) },
+                },
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    serde_json::to_string_pretty(&issues).unwrap_or_default()
+}
+
+/// Render the report as JUnit XML with a test case per crate, which Jenkins and GitLab display
+/// as test results; `manifests` are the manifests of every checked crate, relative to the
+/// repository root `root` as those of the findings, so that up-to-date crates are reported as
+/// passing;
+pub fn junit(report: &Report, root: &Path, manifests: &[PathBuf]) -> String {
+    let findings = findings(report);
+    let failures = manifests
+        .iter()
+        .filter(|manifest| {
+            findings
+                .iter()
+                .any(|finding| finding.cargo_toml == manifest.as_path())
+        })
+        .count();
+    let skipped = manifests
+        .iter()
+        .filter(|manifest| skip_reason(report, manifest).is_some())
+        .count();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuite name=\"cargo-cvm\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        manifests.len(),
+        failures,
+        skipped
+    );
+
+    for manifest in manifests.iter() {
+        let name = escape(&manifest.display().to_string());
+        let _ = write!(
+            out,
+            "  <testcase classname=\"cvm\" name=\"{}\" file=\"{}\" line=\"{}\"",
+            name,
+            name,
+            version_line(root, manifest)
+        );
+
+        let failed = findings
+            .iter()
+            .filter(|finding| finding.cargo_toml == manifest.as_path())
+            .collect::<Vec<&Finding>>();
+
+        if let Some(reason) = skip_reason(report, manifest) {
+            let _ = write!(
+                out,
+                ">\n    <skipped message=\"{}\"/>\n  </testcase>\n",
+                reason
+            );
+        } else if failed.is_empty() {
+            out.push_str("/>\n");
+        } else {
+            out.push_str(">\n");
+            for finding in failed {
+                let _ = writeln!(
+                    out,
+                    "    <failure type=\"{}\" message=\"{}\">{}</failure>",
+                    finding.check,
+                    escape(finding.message.lines().next().unwrap_or_default()),
+                    escape(&finding.message)
+                );
+            }
+            out.push_str("  </testcase>\n");
+        }
+    }
+
+    out.push_str("</testsuite>\n");
+    out
+}
+
+/// Returns why the crate of `manifest` was skipped, if it was;
+fn skip_reason(report: &Report, manifest: &Path) -> Option<&'static str> {
+    if report
+        .frozen
+        .iter()
+        .any(|krate| krate.cargo_toml == manifest)
+    {
+        Some("version is frozen")
+    } else if report
+        .unversioned
+        .iter()
+        .any(|krate| krate.cargo_toml == manifest && !krate.required)
+    {
+        Some("version is omitted")
+    } else {
+        None
+    }
+}
+
+/// Escape text or an attribute value;
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_junit() {
+        let junit = super::junit(
+            &Report::default(),
+            Path::new("/work/repo"),
+            &[PathBuf::from("crates/foo/Cargo.toml")],
+        );
+
+        assert!(junit
+            .contains("<testsuite name=\"cargo-cvm\" tests=\"1\" failures=\"0\" skipped=\"0\">"));
+        assert!(junit.contains("<testcase classname=\"cvm\" name=\"crates/foo/Cargo.toml\" file=\"crates/foo/Cargo.toml\" line=\"1\"/>"));
+        assert_eq!(
+            super::code_quality(&Report::default(), Path::new("/work/repo")),
+            "[]"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let finding = |key: &str, message: &str| super::Finding {
+            check: "outdated",
+            cargo_toml: Path::new("crates/foo/Cargo.toml"),
+            key: key.to_string(),
+            message: message.to_string(),
+        };
+
+        assert_eq!(
+            super::fingerprint(&finding("origin/main", "version 0.1.0 of foo")),
+            super::fingerprint(&finding("origin/main", "version 0.2.0 of foo"))
+        );
+        assert_ne!(
+            super::fingerprint(&finding("origin/main", "version 0.1.0 of foo")),
+            super::fingerprint(&finding("origin/release", "version 0.1.0 of foo"))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::git::CommitInfo;
+use crate::manager::SemVer;
+use serde::Deserialize;
+
+/// Commits left out of the bump inference, set in the `[commits]` table of `.cvm.toml`;
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct CommitFilter {
+    /// Skip merge commits, whose changes are already covered by the merged commits;
+    pub ignore_merges: bool,
+    /// Names or emails of authors whose commits are skipped, e.g. `dependabot[bot]`;
+    pub ignore_authors: Vec<String>,
+}
+
+impl CommitFilter {
+    /// Returns true if the commit must not affect the inferred bump level;
+    pub fn is_ignored(&self, commit: &CommitInfo) -> bool {
+        (self.ignore_merges && commit.parent_count > 1)
+            || self.is_ignored_author(Some(&commit.author), Some(&commit.email))
+    }
+
+    /// Returns true if the name or the email matches an ignored author, ignoring case;
+    fn is_ignored_author(&self, name: Option<&str>, email: Option<&str>) -> bool {
+        self.ignore_authors.iter().any(|ignored| {
+            [name, email]
+                .iter()
+                .flatten()
+                .any(|value| value.eq_ignore_ascii_case(ignored))
+        })
+    }
+}
+
+/// Infer the bump level required by a commit message following the conventional commits
+/// specification, e.g. `feat(parser): add option` requires a minor bump;
+///
+/// Returns `None` for commits that do not follow the specification or do not affect the
+/// public API, e.g. `docs:` or `chore:`.
+pub fn infer_semver(message: &str) -> Option<SemVer> {
+    let header = message.lines().next()?;
+    let colon = header.find(':')?;
+    let mut kind = header[..colon].trim();
+
+    let breaking = kind.ends_with('!')
+        || message.lines().any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+    kind = kind.trim_end_matches('!');
+
+    // Strip the scope, e.g. `feat(parser)`;
+    if let Some(open) = kind.find('(') {
+        if !kind.ends_with(')') {
+            return None;
+        }
+        kind = &kind[..open];
+    }
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    if breaking {
+        return Some(SemVer::Major);
+    }
+
+    match kind.to_lowercase().as_str() {
+        "feat" => Some(SemVer::Minor),
+        "fix" | "perf" => Some(SemVer::Patch),
+        _ => None,
+    }
+}
+
+/// Returns the scopes of a conventional commit message, e.g. `parser` and `lexer` for
+/// `feat(parser, lexer): add option`;
+pub fn scopes(message: &str) -> Vec<&str> {
+    let header = message.lines().next().unwrap_or_default();
+    let kind = match header.find(':') {
+        Some(colon) => header[..colon].trim().trim_end_matches('!'),
+        None => return Vec::new(),
+    };
+
+    match (kind.find('('), kind.strip_suffix(')')) {
+        (Some(open), Some(kind)) => kind[open + 1..]
+            .split(',')
+            .map(str::trim)
+            .filter(|scope| !scope.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Marker in a commit message that waives the outdated check for every crate;
+pub const SKIP_MARKER: &str = "[skip cvm]";
+
+/// Trailer of a commit message that waives the outdated check for one crate, e.g.
+/// `Cvm-Waive: parser only comments changed`;
+pub const WAIVE_TRAILER: &str = "Cvm-Waive";
+
+/// Returns the waivers of a commit message as the waived crate, none for all of them, and the
+/// reason; `[skip cvm]` waives every crate for the reason in the summary line;
+pub fn waivers(message: &str) -> Vec<(Option<String>, String)> {
+    let mut waivers = Vec::new();
+
+    if message.contains(SKIP_MARKER) {
+        let summary = message.lines().next().unwrap_or_default();
+        waivers.push((None, summary.replace(SKIP_MARKER, "").trim().to_string()));
+    }
+
+    for line in message.lines() {
+        let value = match line.split_once(':') {
+            Some((key, value)) if key.trim().eq_ignore_ascii_case(WAIVE_TRAILER) => value.trim(),
+            _ => continue,
+        };
+        let mut parts = value.splitn(2, char::is_whitespace);
+        if let Some(krate) = parts.next().filter(|krate| !krate.is_empty()) {
+            let reason = parts.next().unwrap_or_default().trim().to_string();
+            waivers.push((Some(krate.to_string()), reason));
+        }
+    }
+
+    waivers
+}
+
+/// Short description of the commits that required a bump level;
+pub fn describe(semver: SemVer) -> &'static str {
+    match semver {
+        SemVer::Major => "breaking change commits detected",
+        SemVer::Minor => "feat commits detected",
+        SemVer::Patch => "fix commits detected",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer_semver, scopes, waivers, CommitFilter};
+    use crate::manager::SemVer;
+
+    #[test]
+    fn test_infer_semver() {
+        assert_eq!(infer_semver("feat: add option"), Some(SemVer::Minor));
+        assert_eq!(
+            infer_semver("feat(parser): add option"),
+            Some(SemVer::Minor)
+        );
+        assert_eq!(infer_semver("fix: off by one"), Some(SemVer::Patch));
+        assert_eq!(infer_semver("refactor!: drop API"), Some(SemVer::Major));
+        assert_eq!(
+            infer_semver("fix: rename\n\nBREAKING CHANGE: renamed `foo`"),
+            Some(SemVer::Major)
+        );
+        assert_eq!(infer_semver("docs: typo"), None);
+        assert_eq!(infer_semver("Merge branch 'master'"), None);
+        assert_eq!(infer_semver("update readme: typo"), None);
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!(scopes("feat(parser): add option"), vec!["parser"]);
+        assert_eq!(
+            scopes("fix(parser, lexer)!: drop API"),
+            vec!["parser", "lexer"]
+        );
+        assert!(scopes("feat: add option").is_empty());
+        assert!(scopes("feat(): add option").is_empty());
+        assert!(scopes("Merge branch 'master'").is_empty());
+    }
+
+    #[test]
+    fn test_waivers() {
+        assert_eq!(
+            waivers("docs: fix typo [skip cvm]"),
+            vec![(None, String::from("docs: fix typo"))]
+        );
+        assert_eq!(
+            waivers("fix: comments\n\nCvm-Waive: parser only comments changed\ncvm-waive: lexer\n"),
+            vec![
+                (
+                    Some(String::from("parser")),
+                    String::from("only comments changed")
+                ),
+                (Some(String::from("lexer")), String::new()),
+            ]
+        );
+        assert!(waivers("feat: add option\n\nCvm-Waive:\n").is_empty());
+    }
+
+    #[test]
+    fn test_ignored_author() {
+        let filter = CommitFilter {
+            ignore_merges: false,
+            ignore_authors: vec![
+                String::from("dependabot[bot]"),
+                String::from("bot@renovateapp.com"),
+            ],
+        };
+
+        assert!(filter.is_ignored_author(Some("Dependabot[bot]"), None));
+        assert!(filter.is_ignored_author(Some("Renovate Bot"), Some("bot@renovateapp.com")));
+        assert!(!filter.is_ignored_author(Some("Jane Doe"), Some("jane@example.com")));
+        assert!(!filter.is_ignored_author(None, None));
+    }
+}
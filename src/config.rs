@@ -0,0 +1,463 @@
+use crate::commits::CommitFilter;
+use crate::fetch::HostKeyPolicy;
+use crate::manager::SemVer;
+use anyhow::Error;
+use globset::{Glob, GlobMatcher};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Name of the configuration file read from the workspace root;
+pub const CONFIG_FILE: &str = ".cvm.toml";
+
+/// Name of the file listing gitignore-style patterns for paths whose changes never
+/// require a version bump;
+pub const IGNORE_FILE: &str = ".cvmignore";
+
+/// Settings read from `.cvm.toml` in the workspace root;
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Config {
+    /// Branch compared against if `--branch` is not given;
+    pub branch: Option<String>,
+    /// Remote of the target branch if `--remote` is not given;
+    pub remote: Option<String>,
+    /// How to treat packages nested inside a member that are not workspace members themselves;
+    pub nested_packages: NestedPackages,
+    /// Minimum bump level required by changes to files matching each glob pattern,
+    /// relative to the crate directory, e.g. `"src/api/**" = "major"`;
+    pub severity: BTreeMap<String, SemVer>,
+    /// CA bundle trusted for HTTPS remotes, in addition to the system certificates;
+    pub ca_bundle: Option<PathBuf>,
+    /// How SSH host keys of the remote are verified;
+    pub ssh_host_key_policy: Option<HostKeyPolicy>,
+    /// Trusted SSH host key fingerprints, e.g.
+    /// `SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s`;
+    pub ssh_host_fingerprints: Vec<String>,
+    /// Highest bump level allowed for changes landing on target branches matching each glob
+    /// pattern, e.g. `"release/*" = "patch"`;
+    pub release_train: BTreeMap<String, SemVer>,
+    /// Commits left out of the bump inferred from conventional commit messages;
+    pub commits: CommitFilter,
+    /// Workspace member directory of each conventional commit scope, e.g.
+    /// `parser = "crates/parser"`;
+    pub scopes: BTreeMap<String, PathBuf>,
+    /// Fail on members that omit `package.version`, instead of skipping them as unpublishable;
+    pub require_version: bool,
+    /// Accept versions with extra components, e.g. `1.2.3.4`, when comparing them; such
+    /// versions are still not bumped;
+    pub lenient_versions: bool,
+    /// Lowest version computed for each crate, e.g. `foo = ">=2.0.0"` after a forced republish;
+    pub floors: BTreeMap<String, String>,
+    /// Version all members as one product, sharing the version of the root package, or of
+    /// `unified-crate` if set;
+    pub unified: bool,
+    /// Package whose version governs all members with `unified`, e.g. in a virtual workspace;
+    pub unified_crate: Option<String>,
+    /// Package fields whose removal or change compared to the target branch fails checks, e.g.
+    /// `["license", "repository"]`;
+    pub metadata_fields: Vec<String>,
+    /// Package fields a crate added on the current branch must set before its first release
+    /// passes checks, e.g. `["description", "license", "repository", "categories"]`;
+    pub new_crate_fields: Vec<String>,
+    /// Run `cargo package` for each bumped crate before the bumps are committed, if set;
+    pub preflight: Option<Preflight>,
+    /// Files whose changes are not code changes, e.g. test fixtures and images;
+    pub assets: Assets,
+    /// Directory of the generated files, e.g. plans and caches, relative to the workspace root;
+    /// `cvm` in cargo's target directory if not set;
+    pub artifact_dir: Option<PathBuf>,
+    /// Commands run for each bumped crate, e.g. to require a changelog entry;
+    pub hooks: Hooks,
+    /// Changelog entries required of bumped crates;
+    pub changelog: Changelog,
+    /// Keys not recognized above, reported as warnings;
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NestedPackages {
+    /// Check nested packages the same as workspace members;
+    Check,
+    /// Print a warning listing the nested packages that are not checked;
+    #[default]
+    Warn,
+    /// Silently skip nested packages;
+    Ignore,
+}
+
+/// Files whose changes do not count as code changes, set in the `[assets]` table of `.cvm.toml`;
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Assets {
+    /// Extensions of asset files, e.g. `["png", "bin"]`;
+    pub extensions: Vec<String>,
+    /// Whether files with binary contents are assets whatever their extension;
+    pub binary: bool,
+    /// What asset changes require;
+    pub changes: AssetChanges,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetChanges {
+    /// Asset changes never require a bump;
+    #[default]
+    Ignore,
+    /// Asset changes require a patch bump, whatever the `[severity]` of their paths;
+    Patch,
+}
+
+impl Assets {
+    /// Returns true if the file is an asset by its extension, ignoring case;
+    pub fn has_asset_extension(&self, path: &Path) -> bool {
+        let extension = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension,
+            None => return false,
+        };
+
+        self.extensions.iter().any(|asset| {
+            asset
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    }
+}
+
+/// Commands run for each bumped crate, set in the `[hooks]` table of `.cvm.toml`; `{crate}`,
+/// `{old_version}`, `{new_version}`, and `{manifest}` are replaced in each command;
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Hooks {
+    /// Run before the bumps are written; a failure fails the run without bumping any crate;
+    pub pre_fix: Option<String>,
+    /// Run after the bumps are written, before they are committed; a failure keeps the bumps
+    /// for `cargo cvm undo`;
+    pub post_fix: Option<String>,
+}
+
+impl Hooks {
+    /// Returns the command with each `{name}` of `vars` replaced by its value, quoted for the
+    /// shell if needed, e.g. a manifest path with spaces;
+    pub fn substitute(command: &str, vars: &[(&str, &str)]) -> String {
+        vars.iter()
+            .fold(command.to_string(), |command, (name, value)| {
+                command.replace(&format!("{{{}}}", name), &shell_quote(name, value))
+            })
+    }
+
+    /// Returns the environment variable that hooks read the variable `name` from, e.g.
+    /// `CVM_NEW_VERSION`;
+    pub fn variable(name: &str) -> String {
+        format!("CVM_{}", name.to_uppercase())
+    }
+}
+
+/// Quote the value of the variable `name` as a single word of the shell running the hooks, i.e.
+/// `sh`, or `cmd` on Windows;
+fn shell_quote(name: &str, value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.,:/+=@".contains(c);
+    if !value.is_empty() && value.chars().all(plain) {
+        value.to_string()
+    } else if cfg!(windows) {
+        // `cmd` expands `%` even in quotes, so the value is read from its variable instead,
+        // which is expanded only once;
+        format!("\"%{}%\"", Hooks::variable(name))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Changelog entries required of bumped crates, set in the `[changelog]` table of `.cvm.toml`;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Changelog {
+    /// Whether checks and fixes fail on bumped crates without an entry for their new version;
+    pub require: bool,
+    /// Changelog of each crate, relative to the crate directory;
+    pub file: PathBuf,
+    /// Prefixes of the heading of an entry, with `{version}` replaced by the version, e.g.
+    /// `"## [{version}]"`; if empty, any Markdown heading naming the version is an entry;
+    pub headings: Vec<String>,
+}
+
+impl Default for Changelog {
+    fn default() -> Self {
+        Self {
+            require: false,
+            file: PathBuf::from("CHANGELOG.md"),
+            headings: Vec::new(),
+        }
+    }
+}
+
+impl Changelog {
+    /// Returns true if the changelog has a heading for `version`, e.g. `## [1.2.0] - 2024-05-01`
+    /// or `# v1.2.0`, but not `## 1.2.0-rc.1` for `1.2.0`;
+    pub fn has_entry(&self, contents: &str, version: &str) -> bool {
+        let is_version_char = |c: char| c.is_ascii_alphanumeric() || ".-+".contains(c);
+
+        contents.lines().map(str::trim).any(|line| {
+            if self.headings.is_empty() {
+                return line.starts_with('#')
+                    && line.split_whitespace().any(|word| {
+                        let word = word.trim_matches(|c: char| "[]()#:".contains(c));
+                        word.strip_prefix('v').unwrap_or(word) == version
+                    });
+            }
+
+            self.headings.iter().any(|heading| {
+                let prefix = heading.replace("{version}", version);
+                match line.strip_prefix(&prefix) {
+                    // A pattern ending in the version must not match a longer version;
+                    Some(rest) if heading.ends_with("{version}") => {
+                        !rest.starts_with(is_version_char)
+                    }
+                    Some(_) => true,
+                    None => false,
+                }
+            })
+        })
+    }
+}
+
+/// Returns true if the contents are binary, i.e. a NUL byte appears in their first 8000
+/// bytes, as git decides;
+pub fn is_binary(contents: &[u8]) -> bool {
+    contents.iter().take(8000).any(|byte| *byte == 0)
+}
+
+/// How bumped crates are packaged before the bumps are committed;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preflight {
+    /// `cargo package --no-verify`, which checks the manifest and the packaged files;
+    NoVerify,
+    /// `cargo package`, which also builds the packaged crate;
+    Verify,
+}
+
+impl TryFrom<&str> for Preflight {
+    type Error = Error;
+    fn try_from(value: &str) -> Result<Self, Error> {
+        match value {
+            "no-verify" => Ok(Preflight::NoVerify),
+            "verify" => Ok(Preflight::Verify),
+            _ => Err(Error::msg(format!("Invalid preflight: {:?}", value))),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from `dir`, falling back to the defaults if no file exists;
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let path = dir.join(CONFIG_FILE);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&read_to_string(&path)?, &path)
+    }
+
+    /// Parse the configuration, e.g. as read from a git tree; `path` is for diagnostics;
+    pub fn parse(contents: &str, path: &Path) -> Result<Self, Error> {
+        toml::from_str(contents)
+            .map_err(|e| Error::msg(format!("Invalid configuration in {:?}: {}", path, e)))
+    }
+
+    /// Compile the `[severity]` patterns, keeping each pattern for reporting;
+    pub fn severity_rules(&self) -> Result<Vec<(String, GlobMatcher, SemVer)>, Error> {
+        compile_rules(&self.severity, "severity")
+    }
+
+    /// Compile the `[release-train]` branch patterns, keeping each pattern for reporting;
+    pub fn release_train_rules(&self) -> Result<Vec<(String, GlobMatcher, SemVer)>, Error> {
+        compile_rules(&self.release_train, "release-train")
+    }
+}
+
+fn compile_rules(
+    rules: &BTreeMap<String, SemVer>,
+    table: &str,
+) -> Result<Vec<(String, GlobMatcher, SemVer)>, Error> {
+    rules
+        .iter()
+        .map(|(pattern, semver)| {
+            let glob = Glob::new(pattern).map_err(|e| {
+                Error::msg(format!("Invalid {} pattern {:?}: {}", table, pattern, e))
+            })?;
+            Ok((pattern.clone(), glob.compile_matcher(), *semver))
+        })
+        .collect()
+}
+
+/// Load the `.cvmignore` patterns from `dir`, matching nothing if the file does not exist;
+pub fn load_ignore(dir: &Path) -> Result<Gitignore, Error> {
+    let path = dir.join(IGNORE_FILE);
+
+    if !path.exists() {
+        return Ok(Gitignore::empty());
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&path) {
+        return Err(Error::msg(format!("Invalid pattern in {:?}: {}", path, e)));
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Parse `.cvmignore` patterns relative to `dir`, e.g. as read from a git tree;
+pub fn parse_ignore(dir: &Path, contents: &str) -> Result<Gitignore, Error> {
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in contents.lines() {
+        builder
+            .add_line(None, line)
+            .map_err(|e| Error::msg(format!("Invalid pattern in {}: {}", IGNORE_FILE, e)))?;
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_binary, AssetChanges, Changelog, Config, Hooks, NestedPackages};
+    use crate::manager::SemVer;
+    use crate::testing::TempDir;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = toml::from_str("")?;
+        assert_eq!(config.nested_packages, NestedPackages::Warn);
+
+        let config: Config = toml::from_str("nested-packages = \"check\"")?;
+        assert_eq!(config.nested_packages, NestedPackages::Check);
+
+        let config: Config = toml::from_str("[severity]\n\"src/api/**\" = \"major\"")?;
+        let rules = config.severity_rules()?;
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].1.is_match("src/api/v1/mod.rs"));
+        assert_eq!(rules[0].2, SemVer::Major);
+
+        let config: Config = toml::from_str("[release-train]\n\"release/*\" = \"patch\"")?;
+        let rules = config.release_train_rules()?;
+        assert!(rules[0].1.is_match("release/1.x"));
+        assert!(!rules[0].1.is_match("main"));
+        assert_eq!(rules[0].2, SemVer::Patch);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assets() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config =
+            toml::from_str("[assets]\nextensions = [\".png\", \"bin\"]\nchanges = \"patch\"")?;
+        assert!(config
+            .assets
+            .has_asset_extension(Path::new("tests/fixtures/logo.PNG")));
+        assert!(config
+            .assets
+            .has_asset_extension(Path::new("tests/data.bin")));
+        assert!(!config.assets.has_asset_extension(Path::new("src/lib.rs")));
+        assert!(!config.assets.has_asset_extension(Path::new("LICENSE")));
+        assert_eq!(config.assets.changes, AssetChanges::Patch);
+        assert_eq!(Config::default().assets.changes, AssetChanges::Ignore);
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_binary("fn main() {}\n".as_bytes()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hooks() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = toml::from_str(
+            "[hooks]\npre-fix = \"./scripts/check-changelog.sh {crate} {new_version}\"",
+        )?;
+        assert_eq!(config.hooks.post_fix, None);
+        let command = Hooks::substitute(
+            config.hooks.pre_fix.as_deref().unwrap_or_default(),
+            &[("crate", "foo"), ("new_version", "0.2.0-rc.1")],
+        );
+        assert_eq!(command, "./scripts/check-changelog.sh foo 0.2.0-rc.1");
+
+        #[cfg(unix)]
+        assert_eq!(
+            Hooks::substitute("cat {manifest}", &[("manifest", "my crate's/Cargo.toml")]),
+            "cat 'my crate'\\''s/Cargo.toml'"
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            Hooks::substitute("cat {manifest}", &[("manifest", "a;$(b)/Cargo.toml")]),
+            "cat 'a;$(b)/Cargo.toml'"
+        );
+        #[cfg(windows)]
+        assert_eq!(
+            Hooks::substitute("type {manifest}", &[("manifest", "100% & b\\Cargo.toml")]),
+            "type \"%CVM_MANIFEST%\""
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_changelog() -> Result<(), Box<dyn std::error::Error>> {
+        let changelog = "# Changelog\n\n## [0.2.0-rc.1]\n\n## [v0.1.0] - 2024-05-01\n- 0.3.0\n";
+        let config = Changelog::default();
+        assert!(config.has_entry(changelog, "0.1.0"));
+        assert!(config.has_entry(changelog, "0.2.0-rc.1"));
+        assert!(!config.has_entry(changelog, "0.2.0"));
+        assert!(!config.has_entry(changelog, "0.3.0"));
+
+        let config: Config =
+            toml::from_str("[changelog]\nrequire = true\nheadings = [\"## [v{version}]\"]")?;
+        assert_eq!(config.changelog.file, Path::new("CHANGELOG.md"));
+        assert!(config.changelog.has_entry(changelog, "0.1.0"));
+        assert!(!config.changelog.has_entry(changelog, "0.2.0-rc.1"));
+
+        let config = Changelog {
+            headings: vec![String::from("## {version}")],
+            ..Changelog::default()
+        };
+        assert!(config.has_entry("## 1.2.0 (2024-05-01)", "1.2.0"));
+        assert!(!config.has_entry("## 1.2.0-rc.1", "1.2.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_patterns() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new("ignore")?;
+        let dir = tmp.path();
+        std::fs::write(dir.join(super::IGNORE_FILE), "docs/\n*.snap\n!keep.snap\n")?;
+
+        let ignore = super::load_ignore(dir)?;
+        let matched = |path: &str| {
+            ignore
+                .matched_path_or_any_parents(dir.join(path), false)
+                .is_ignore()
+        };
+
+        assert!(matched("crates/foo/docs/guide.md"));
+        assert!(matched("crates/foo/src/snapshots/a.snap"));
+        assert!(!matched("crates/foo/src/snapshots/keep.snap"));
+        assert!(!matched("crates/foo/src/lib.rs"));
+
+        // Patterns read from a git tree match the same paths;
+        let parsed = super::parse_ignore(dir, "docs/\n*.snap\n!keep.snap\n")?;
+        assert!(parsed
+            .matched_path_or_any_parents(dir.join("crates/foo/docs/guide.md"), false)
+            .is_ignore());
+        assert!(!parsed
+            .matched_path_or_any_parents(dir.join("crates/foo/src/snapshots/keep.snap"), false)
+            .is_ignore());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use std::cmp::Ordering;
+use toml_edit::{value, DocumentMut, InlineTable, Item, Table, TableLike, Value};
+
+/// Dependency tables checked for external dependencies;
+pub const TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Version requirement of an external dependency declared in a manifest;
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    /// Name of the package, which differs from `key` for renamed dependencies;
+    pub package: String,
+    /// Dependency table declaring it, e.g. `dev-dependencies`;
+    pub table: &'static str,
+    pub key: String,
+    pub version: String,
+}
+
+/// Returns the version requirements of the dependencies fetched from a registry, skipping
+/// path, git, and workspace-inherited dependencies;
+pub fn requirements(doc: &DocumentMut) -> Vec<Requirement> {
+    let mut requirements = Vec::new();
+
+    for table in TABLES.iter() {
+        if let Some(deps) = doc.get(table).and_then(Item::as_table_like) {
+            collect(deps, table, &mut requirements);
+        }
+    }
+
+    requirements
+}
+
+/// A dependency declared in a manifest, of any source;
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// Name of the package, which differs from the key for renamed dependencies;
+    pub package: String,
+    /// Dependency table declaring it, e.g. `dev-dependencies`;
+    pub table: &'static str,
+    /// Whether it requires a version, directly or inherited from the workspace; `cargo publish`
+    /// drops dev-dependencies without one;
+    pub versioned: bool,
+}
+
+/// Returns every dependency declared in the manifest, including path, git, and
+/// workspace-inherited dependencies;
+pub fn dependencies(doc: &DocumentMut) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+
+    for table in TABLES.iter() {
+        if let Some(deps) = doc.get(table).and_then(Item::as_table_like) {
+            for (key, item) in deps.iter() {
+                let package = item
+                    .get("package")
+                    .and_then(Item::as_str)
+                    .unwrap_or(key)
+                    .to_string();
+                let versioned = item.is_str()
+                    || item.get("version").is_some()
+                    || item.get("workspace").and_then(Item::as_bool) == Some(true);
+                dependencies.push(Dependency {
+                    package,
+                    table,
+                    versioned,
+                });
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Returns the path dependencies that `cargo publish` would reject for lacking a version, as
+/// requirements without a version; dev-dependencies are skipped, since it drops those;
+pub fn unversioned_path_dependencies(doc: &DocumentMut) -> Vec<(Requirement, String)> {
+    let mut unversioned = Vec::new();
+
+    for table in TABLES.iter().filter(|table| **table != "dev-dependencies") {
+        if let Some(deps) = doc.get(table).and_then(Item::as_table_like) {
+            for (key, item) in deps.iter() {
+                let path = match item.get("path").and_then(Item::as_str) {
+                    Some(path) if item.get("version").is_none() => path,
+                    _ => continue,
+                };
+                let requirement = Requirement {
+                    package: item
+                        .get("package")
+                        .and_then(Item::as_str)
+                        .unwrap_or(key)
+                        .to_string(),
+                    table,
+                    key: key.to_string(),
+                    version: String::new(),
+                };
+                unversioned.push((requirement, path.to_string()));
+            }
+        }
+    }
+
+    unversioned
+}
+
+/// Returns the version requirement of a package in the root `[workspace.dependencies]`;
+pub fn workspace_version(doc: &DocumentMut, package: &str) -> Option<String> {
+    let deps = doc.get("workspace")?.get("dependencies")?.as_table_like()?;
+
+    let mut requirements = Vec::new();
+    collect(deps, "workspace.dependencies", &mut requirements);
+
+    requirements
+        .into_iter()
+        .find(|requirement| requirement.package == package)
+        .map(|requirement| requirement.version)
+}
+
+fn collect(deps: &dyn TableLike, table: &'static str, requirements: &mut Vec<Requirement>) {
+    for (key, item) in deps.iter() {
+        let (version, package) = if let Some(version) = item.as_str() {
+            (version, key)
+        } else if let Some(dep) = item.as_table_like() {
+            if dep.contains_key("path") || dep.contains_key("git") || dep.contains_key("workspace")
+            {
+                continue;
+            }
+            match dep.get("version").and_then(Item::as_str) {
+                Some(version) => (
+                    version,
+                    dep.get("package").and_then(Item::as_str).unwrap_or(key),
+                ),
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+
+        requirements.push(Requirement {
+            package: package.to_string(),
+            table,
+            key: key.to_string(),
+            version: version.to_string(),
+        });
+    }
+}
+
+/// Set the version requirement of a dependency, preserving its other keys, e.g. features;
+pub fn set_version(doc: &mut DocumentMut, requirement: &Requirement, version: &str) {
+    let item = match doc
+        .get_mut(requirement.table)
+        .and_then(Item::as_table_like_mut)
+        .and_then(|deps| deps.get_mut(&requirement.key))
+    {
+        Some(item) => item,
+        None => return,
+    };
+
+    if item.is_str() {
+        *item = value(version);
+    } else if let Some(dep) = item.as_inline_table_mut() {
+        // A new last key takes over the space before the closing brace;
+        if !dep.contains_key("version") {
+            if let Some((_, last)) = dep.iter_mut().last() {
+                last.decor_mut().set_suffix("");
+            }
+        }
+        dep.insert("version", Value::from(version));
+    } else if let Some(dep) = item.as_table_like_mut() {
+        dep.insert("version", value(version));
+    }
+}
+
+/// Returns true if the dependency disables default features;
+pub fn disables_default_features(doc: &DocumentMut, requirement: &Requirement) -> bool {
+    doc.get(requirement.table)
+        .and_then(|deps| deps.get(&requirement.key))
+        .and_then(|dep| dep.get("default-features"))
+        .and_then(Item::as_bool)
+        == Some(false)
+}
+
+/// Replace a dependency with a `workspace = true` reference, keeping member-specific keys such
+/// as `features` and `optional`;
+pub fn inherit(doc: &mut DocumentMut, requirement: &Requirement) {
+    let item = match doc
+        .get_mut(requirement.table)
+        .and_then(Item::as_table_like_mut)
+        .and_then(|deps| deps.get_mut(&requirement.key))
+    {
+        Some(item) => item,
+        None => return,
+    };
+
+    // Keys that move to `[workspace.dependencies]`;
+    let hoisted = ["version", "default-features", "registry"];
+
+    if let Some(table) = item.as_table_mut() {
+        for key in hoisted.iter() {
+            table.remove(key);
+        }
+        table.insert("workspace", value(true));
+    } else {
+        let mut inline = InlineTable::new();
+        inline.insert("workspace", Value::from(true));
+
+        if let Some(dep) = item.as_inline_table() {
+            for (key, value) in dep.iter() {
+                if !hoisted.contains(&key) {
+                    inline.insert(key, value.clone());
+                }
+            }
+        }
+
+        *item = value(inline);
+    }
+}
+
+/// Add a dependency to the root `[workspace.dependencies]`;
+pub fn add_workspace_dependency(
+    doc: &mut DocumentMut,
+    package: &str,
+    version: &str,
+    default_features: bool,
+) {
+    let deps = doc
+        .entry("workspace")
+        .or_insert_with(|| {
+            let mut workspace = Table::new();
+            workspace.set_implicit(true);
+            Item::Table(workspace)
+        })
+        .as_table_mut()
+        .map(|workspace| {
+            workspace
+                .entry("dependencies")
+                .or_insert_with(|| Item::Table(Table::new()))
+        })
+        .and_then(Item::as_table_like_mut);
+
+    if let Some(deps) = deps {
+        if default_features {
+            deps.insert(package, value(version));
+        } else {
+            let mut dep = InlineTable::new();
+            dep.insert("version", Value::from(version));
+            dep.insert("default-features", Value::from(false));
+            deps.insert(package, value(dep));
+        }
+    }
+}
+
+/// Compare version requirements by the version they name, ignoring operators, e.g. `^1.0.100`
+/// is lower than `1.0.114`;
+pub fn compare(a: &str, b: &str) -> Ordering {
+    components(a).cmp(&components(b))
+}
+
+fn components(requirement: &str) -> Vec<u64> {
+    requirement
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_requirements() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = "[dependencies]\nserde = { version = \"1.0.100\", features = [\"derive\"] }\nlog = \"0.4\"\nbar = { path = \"../bar\", version = \"0.1\" }\n\n[dev-dependencies.anyhow]\nversion = \"1.0\"\n"
+            .parse::<toml_edit::DocumentMut>()?;
+
+        let requirements = super::requirements(&doc);
+        let names = requirements
+            .iter()
+            .map(|requirement| requirement.package.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["serde", "log", "anyhow"]);
+
+        super::set_version(&mut doc, &requirements[0], "1.0.114");
+        super::set_version(&mut doc, &requirements[1], "0.4.11");
+        assert!(doc.to_string().contains(
+            "serde = { version = \"1.0.114\", features = [\"derive\"] }\nlog = \"0.4.11\""
+        ));
+
+        super::inherit(&mut doc, &requirements[0]);
+        super::inherit(&mut doc, &requirements[2]);
+        let manifest = doc.to_string();
+        assert!(manifest.contains("serde = { workspace = true, features = [\"derive\"] }"));
+        assert!(manifest.contains("[dev-dependencies.anyhow]\nworkspace = true\n"));
+
+        super::add_workspace_dependency(&mut doc, "serde", "1.0.114", true);
+        assert!(doc
+            .to_string()
+            .ends_with("[workspace.dependencies]\nserde = \"1.0.114\"\n"));
+
+        assert_eq!(super::compare("^1.0.100", "1.0.114"), Ordering::Less);
+        assert_eq!(super::compare("0.4", "0.4.0"), Ordering::Less);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unversioned_path_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = "[dependencies]\nfoo = { path = \"../foo\" }\nbar = { path = \"../bar\", version = \"0.1\" }\n\n[dev-dependencies]\nbaz = { path = \"../baz\" }\n"
+            .parse::<toml_edit::DocumentMut>()?;
+        let unversioned = super::unversioned_path_dependencies(&doc);
+        assert_eq!(unversioned.len(), 1);
+        assert_eq!(unversioned[0].1, "../foo");
+        super::set_version(&mut doc, &unversioned[0].0, "0.3.1");
+        assert!(doc
+            .to_string()
+            .contains("foo = { path = \"../foo\", version = \"0.3.1\" }"));
+        Ok(())
+    }
+}
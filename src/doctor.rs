@@ -0,0 +1,156 @@
+use crate::config::Config;
+use cargo_toml::Manifest;
+use git2::Repository;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Outcome of a single prerequisite check of `cargo cvm doctor`;
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub detail: String,
+    /// How to fix the failed check, None if it passed;
+    pub hint: Option<String>,
+}
+
+impl Check {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.hint.is_none()
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.hint {
+            None => write!(f, "[ok]   {}: {}", self.name, self.detail),
+            Some(hint) => write!(
+                f,
+                "[FAIL] {}: {}\n       hint: {}",
+                self.name, self.detail, hint
+            ),
+        }
+    }
+}
+
+/// Check the prerequisites that do not need the target remote: the repository, the manifests
+/// of the workspace and its members, and `.cvm.toml`;
+pub fn local_checks(dir: &Path) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match Repository::discover(dir) {
+        Ok(repo) => checks.push(Check::pass(
+            "repository discovered",
+            repo.workdir()
+                .unwrap_or_else(|| repo.path())
+                .display()
+                .to_string(),
+        )),
+        Err(e) => checks.push(Check::fail(
+            "repository discovered",
+            e.message().to_string(),
+            "run `cargo cvm` inside a git repository",
+        )),
+    }
+
+    let cargo_toml = dir.join("Cargo.toml");
+    let manifest = read_to_string(&cargo_toml)
+        .map_err(anyhow::Error::from)
+        .and_then(|manifest| Ok(Manifest::from_str(&manifest)?));
+    match manifest {
+        Ok(manifest) => {
+            checks.push(Check::pass(
+                "manifest parseable",
+                cargo_toml.display().to_string(),
+            ));
+            if let Some(workspace) = manifest.workspace {
+                checks.extend(
+                    workspace
+                        .members
+                        .iter()
+                        .map(|member| check_member(dir, member)),
+                );
+            }
+        }
+        Err(e) => checks.push(Check::fail(
+            "manifest parseable",
+            format!("{}: {}", cargo_toml.display(), e),
+            "run `cargo cvm` in the directory of the workspace's `Cargo.toml`",
+        )),
+    }
+
+    match Config::load(dir) {
+        Ok(_) => checks.push(Check::pass("configuration parseable", ".cvm.toml")),
+        Err(e) => checks.push(Check::fail(
+            "configuration parseable",
+            e.to_string(),
+            "fix .cvm.toml or regenerate it with `cargo cvm init --force`",
+        )),
+    }
+
+    checks
+}
+
+/// Check that a workspace member is a package with a parseable manifest and version;
+fn check_member(dir: &Path, member: &str) -> Check {
+    if member.contains(['*', '?', '[']) {
+        return Check::fail(
+            "workspace member exists",
+            format!("{:?} is a glob pattern", member),
+            "`cargo cvm` does not expand globs in `workspace.members`; list the members explicitly",
+        );
+    }
+
+    let cargo_toml = dir.join(member).join("Cargo.toml");
+    let manifest = match read_to_string(&cargo_toml) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            return Check::fail(
+                "workspace member exists",
+                format!("{}: {}", cargo_toml.display(), e),
+                "remove the member from `workspace.members` or fix its path",
+            )
+        }
+    };
+
+    match Manifest::from_str(&manifest) {
+        Ok(manifest) if manifest.package.is_some() => {
+            Check::pass("workspace member exists", member)
+        }
+        Ok(_) => Check::fail(
+            "workspace member exists",
+            format!("{} has no [package] table", cargo_toml.display()),
+            "list only packages in `workspace.members`",
+        ),
+        Err(e) => Check::fail(
+            "workspace member exists",
+            format!("{}: {}", cargo_toml.display(), e),
+            "fix the member's Cargo.toml",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_check_member_glob() {
+        let check = super::check_member(std::path::Path::new("."), "crates/*");
+        assert!(!check.passed());
+        assert!(check.detail.contains("glob"));
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::Error;
+use serde::Serialize;
+
+const DAY: u64 = 86400;
+
+/// A crate with unreleased changes, see `cargo cvm due`;
+#[derive(Debug, Serialize)]
+pub struct DueCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// Version of the last release tag;
+    pub version: String,
+    pub tag: String,
+    /// Number of commits since the tag that changed the crate;
+    pub commits: usize,
+    /// UTC time of the oldest of these commits in RFC 3339 format;
+    pub oldest: String,
+    /// Whole days since the oldest of these commits;
+    pub age_days: u64,
+}
+
+impl std::fmt::Display for DueCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}): {} commit(s), oldest {} day(s) ago",
+            self.krate, self.version, self.tag, self.commits, self.age_days
+        )
+    }
+}
+
+/// Thresholds of `cargo cvm due`; a crate is due once its unreleased changes exceed either;
+#[derive(Debug, Default)]
+pub struct Thresholds {
+    /// Seconds since the oldest unreleased commit;
+    pub max_age: Option<u64>,
+    pub min_commits: Option<usize>,
+}
+
+impl Thresholds {
+    /// Returns true if the crate is due; without thresholds, any unreleased commit makes it due;
+    pub fn is_due(&self, commits: usize, age: u64) -> bool {
+        if commits == 0 {
+            return false;
+        }
+
+        match (self.max_age, self.min_commits) {
+            (None, None) => true,
+            (max_age, min_commits) => {
+                max_age.is_some_and(|max_age| age >= max_age)
+                    || min_commits.is_some_and(|min_commits| commits >= min_commits)
+            }
+        }
+    }
+}
+
+/// Parse an age as a number followed by a unit, i.e. `s`, `m`, `h`, `d`, or `w`, e.g. `30d`,
+/// into seconds;
+pub fn parse_age(age: &str) -> Result<u64, Error> {
+    let invalid = || {
+        Error::msg(format!(
+            "Invalid age {:?}; expected a number followed by s, m, h, d, or w, e.g. `30d`",
+            age
+        ))
+    };
+
+    let unit = match age.chars().last().ok_or_else(invalid)? {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => DAY,
+        'w' => 7 * DAY,
+        _ => return Err(invalid()),
+    };
+
+    let count = age[..age.len() - 1].parse::<u64>().map_err(|_| invalid())?;
+    count.checked_mul(unit).ok_or_else(invalid)
+}
+
+/// Sort the due crates by priority: the longest waiting first, then the most commits;
+pub fn prioritize(crates: &mut [DueCrate]) {
+    crates.sort_by(|a, b| {
+        b.age_days
+            .cmp(&a.age_days)
+            .then(b.commits.cmp(&a.commits))
+            .then(a.krate.cmp(&b.krate))
+    });
+}
+
+/// Returns whole days of `seconds`;
+pub fn days(seconds: u64) -> u64 {
+    seconds / DAY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_age, prioritize, DueCrate, Thresholds};
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").ok(), Some(30 * 86400));
+        assert_eq!(parse_age("2w").ok(), Some(14 * 86400));
+        assert_eq!(parse_age("90m").ok(), Some(5400));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("").is_err());
+        // Ages that overflow are rejected rather than wrapped;
+        assert!(parse_age(&format!("{}w", u64::MAX / 2)).is_err());
+    }
+
+    #[test]
+    fn test_due() {
+        let thresholds = Thresholds {
+            max_age: Some(30 * 86400),
+            min_commits: Some(5),
+        };
+        assert!(thresholds.is_due(5, 0));
+        assert!(thresholds.is_due(1, 31 * 86400));
+        assert!(!thresholds.is_due(4, 29 * 86400));
+        assert!(!thresholds.is_due(0, 31 * 86400));
+        assert!(Thresholds::default().is_due(1, 0));
+
+        let due = |krate: &str, commits, age_days| DueCrate {
+            krate: krate.to_string(),
+            version: String::from("0.1.0"),
+            tag: format!("{}-v0.1.0", krate),
+            commits,
+            oldest: String::new(),
+            age_days,
+        };
+        let mut crates = vec![due("a", 9, 3), due("b", 2, 40), due("c", 12, 3)];
+        prioritize(&mut crates);
+        assert_eq!(
+            crates
+                .iter()
+                .map(|krate| krate.krate.as_str())
+                .collect::<Vec<&str>>(),
+            ["b", "c", "a"]
+        );
+    }
+}
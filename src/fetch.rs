@@ -0,0 +1,851 @@
+use crate::cargo_config::CargoNetConfig;
+use crate::git_cli::{self, GitBackend};
+use crate::manager::INTERACTION_REQUIRED_EXIT_CODE;
+use crate::ssh_config;
+use anyhow::Error;
+use git2::cert::Cert;
+use git2::{Cred, CredentialType, Repository};
+use serde::Deserialize;
+use std::ffi::CString;
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// How SSH host keys of the remote are verified;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyPolicy {
+    /// Leave host key verification to libgit2;
+    Default,
+    /// Only accept host keys whose fingerprint is pinned;
+    Pinned,
+    /// Record the host key on first use and reject keys that change afterwards;
+    AcceptNew,
+}
+
+impl std::convert::TryFrom<&str> for HostKeyPolicy {
+    type Error = Error;
+    fn try_from(policy: &str) -> Result<Self, Error> {
+        match policy {
+            "default" => Ok(HostKeyPolicy::Default),
+            "pinned" => Ok(HostKeyPolicy::Pinned),
+            "accept-new" => Ok(HostKeyPolicy::AcceptNew),
+            _ => Err(Error::msg(format!(
+                "Invalid ssh host key policy: {:?}",
+                policy
+            ))),
+        }
+    }
+}
+
+/// Verifies SSH host keys according to a `HostKeyPolicy`;
+#[derive(Debug, Clone)]
+pub struct HostKeyVerifier {
+    pub policy: HostKeyPolicy,
+    /// Pinned fingerprints in OpenSSH format, e.g.
+    /// `SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s`;
+    pub fingerprints: Vec<String>,
+    /// File recording the host keys accepted on first use;
+    pub known_hosts: PathBuf,
+}
+
+impl HostKeyVerifier {
+    /// Returns true if the host key presented by `host` is trusted;
+    pub fn verify(&self, cert: &Cert<'_>, host: &str) -> bool {
+        let fingerprint = match cert.as_hostkey().and_then(|key| key.hash_sha256()) {
+            Some(hash) => fingerprint(hash),
+            None => {
+                eprintln!("ssh host {} did not provide a SHA256 host key hash", host);
+                return false;
+            }
+        };
+
+        if self.fingerprints.contains(&fingerprint) {
+            return true;
+        }
+
+        match self.policy {
+            HostKeyPolicy::Default => true,
+            HostKeyPolicy::Pinned => {
+                eprintln!(
+                    "ssh host key {} of {} does not match any pinned fingerprint",
+                    fingerprint, host
+                );
+                false
+            }
+            HostKeyPolicy::AcceptNew => match self.accept_new(host, &fingerprint) {
+                Ok(trusted) => trusted,
+                Err(e) => {
+                    eprintln!("Failed to verify ssh host key of {}: {}", host, e);
+                    false
+                }
+            },
+        }
+    }
+
+    fn accept_new(&self, host: &str, fingerprint: &str) -> Result<bool, Error> {
+        if self.known_hosts.exists() {
+            for line in read_to_string(&self.known_hosts)?.lines() {
+                let mut fields = line.split_whitespace();
+                if fields.next() == Some(host) {
+                    let known = fields.next().unwrap_or_default();
+                    if known != fingerprint {
+                        eprintln!(
+                            "ssh host key of {} changed from {} to {}; remove it from {:?} if the change is expected",
+                            host, known, fingerprint, self.known_hosts
+                        );
+                    }
+                    return Ok(known == fingerprint);
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.known_hosts)?;
+        writeln!(file, "{} {}", host, fingerprint)?;
+        eprintln!("Permanently added ssh host key {} of {}", fingerprint, host);
+
+        Ok(true)
+    }
+}
+
+/// Format a SHA256 host key hash the way OpenSSH prints fingerprints;
+pub fn fingerprint(hash: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::from("SHA256:");
+    for chunk in hash.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+
+        // Unpadded base64;
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+/// Trust the certificates in the given CA bundle file for HTTPS remotes, in addition to the
+/// system certificates;
+pub fn set_ca_bundle(path: &Path) -> Result<(), Error> {
+    if !path.is_file() {
+        return Err(Error::msg(format!(
+            "CA bundle does not exist at {:?}",
+            path.display()
+        )));
+    }
+
+    let file = CString::new(path.display().to_string())?;
+
+    libgit2_sys::init();
+    // Safety: the option takes a file and a directory path, both of which may be null;
+    let result = unsafe {
+        libgit2_sys::git_libgit2_opts(
+            libgit2_sys::GIT_OPT_SET_SSL_CERT_LOCATIONS as c_int,
+            file.as_ptr(),
+            std::ptr::null::<c_char>(),
+        )
+    };
+
+    if result < 0 {
+        return Err(git2::Error::last_error(result)
+            .map(Error::from)
+            .unwrap_or_else(|| Error::msg("Failed to set the CA bundle")));
+    }
+
+    Ok(())
+}
+
+/// Returns true if a failed fetch may succeed when retried, e.g. after a network error or
+/// timeout, but not after an authentication failure;
+pub fn is_retryable(e: &git2::Error) -> bool {
+    !matches!(
+        e.code(),
+        git2::ErrorCode::Auth | git2::ErrorCode::Certificate | git2::ErrorCode::NotFound
+    ) && !is_auth_error(e)
+}
+
+/// Returns true if the remote was reached but rejected the credentials, or none were available;
+pub fn is_auth_error(e: &git2::Error) -> bool {
+    e.code() == git2::ErrorCode::Auth
+        || e.class() == git2::ErrorClass::Ssh
+        || e.message().contains("credentials")
+        || e.message().contains("authentication")
+}
+
+/// The `url.<base>.insteadOf` rules of the git configuration, as pairs of the URL prefix to
+/// replace and its replacement;
+pub fn insteadof_rules(config: &git2::Config) -> Result<Vec<(String, String)>, git2::Error> {
+    let mut rules = Vec::new();
+    let entries = config.entries(Some(r"^url\..*\.insteadof$"))?;
+
+    for entry in &entries {
+        let entry = entry?;
+        let base = entry
+            .name()
+            .and_then(|name| name.strip_prefix("url."))
+            .and_then(|name| name.strip_suffix(".insteadof"));
+
+        if let (Some(base), Some(prefix)) = (base, entry.value()) {
+            rules.push((prefix.to_string(), base.to_string()));
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Rewrite a URL with the rule of the longest matching prefix, as `git fetch` does;
+pub fn rewrite_url(url: &str, rules: &[(String, String)]) -> String {
+    rules
+        .iter()
+        .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(prefix, base)| format!("{}{}", base, &url[prefix.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Host of an SSH remote URL, e.g. `github.com` for `git@github.com:infinyon/cargo-cvm.git`;
+pub fn ssh_host(url: &str) -> Option<String> {
+    let url = anonymous_url(url)?;
+    url.strip_prefix("https://")?
+        .split('/')
+        .next()
+        .map(String::from)
+}
+
+/// Rewrite an SSH remote URL to its HTTPS equivalent so it can be fetched without credentials,
+/// e.g. `git@github.com:infinyon/cargo-cvm.git` to `https://github.com/infinyon/cargo-cvm.git`;
+///
+/// Returns `None` for URLs that are already anonymous (HTTPS or local paths) or unrecognized.
+pub fn anonymous_url(url: &str) -> Option<String> {
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        // ssh://[user@]host[:port]/path
+        let rest = rest.splitn(2, '@').last()?;
+        let (host, path) = rest.split_at(rest.find('/')?);
+        let host = host.split(':').next()?;
+        return Some(format!("https://{}{}", host, path));
+    }
+
+    if url.contains("://") {
+        return None;
+    }
+
+    // scp-like syntax: [user@]host:path
+    let (host, path) = url.split_at(url.find(':')?);
+    let host = host.splitn(2, '@').last()?;
+    let path = path.trim_start_matches(':').trim_start_matches('/');
+
+    if host.is_empty() || host.contains('/') || path.is_empty() {
+        return None;
+    }
+
+    Some(format!("https://{}/{}", host, path))
+}
+
+/// Credentials for a remote, tried in the order of the existing ssh private keys, the ssh agent,
+/// and git's default credential helpers; `attempt` counts the ssh credentials already rejected;
+pub fn credentials(
+    ssh_keys: &[PathBuf],
+    attempt: usize,
+    username_from_url: Option<&str>,
+    allowed_types: CredentialType,
+) -> Result<Cred, git2::Error> {
+    let username = username_from_url.unwrap_or("git");
+
+    if allowed_types.contains(CredentialType::SSH_KEY) {
+        let keys = ssh_keys
+            .iter()
+            .filter(|key| key.is_file())
+            .collect::<Vec<&PathBuf>>();
+
+        return match keys.get(attempt) {
+            Some(key) => Cred::ssh_key(username, None, key, None),
+            None if attempt == keys.len() => Cred::ssh_key_from_agent(username),
+            None => Err(git2::Error::from_str(
+                "no ssh key was accepted by the remote; pass the right key with --ssh-key",
+            )),
+        };
+    }
+
+    if allowed_types.contains(CredentialType::DEFAULT) {
+        return Cred::default();
+    }
+
+    Err(git2::Error::from_str(
+        "no credentials available for the remote; use --anonymous to fetch public repositories without credentials",
+    ))
+}
+
+/// Operations on the target remote, implemented once per `--git-backend`;
+pub trait Transport {
+    /// Fetch the target branch into its remote-tracking branch;
+    fn fetch(&self, fetcher: &Fetcher, repo: &Repository, branch: &str) -> Result<(), Error>;
+
+    /// Returns the refs of the heads of the remote at `url`, e.g. `refs/heads/master`;
+    fn list_heads(
+        &self,
+        fetcher: &Fetcher,
+        repo: &Repository,
+        url: &str,
+    ) -> Result<Vec<String>, Error>;
+}
+
+/// Transport of the bundled libgit2, which only speaks HTTPS and SSH if built with the `https`
+/// and `ssh` features;
+pub struct Libgit2Transport;
+
+/// Transport of the `git` executable;
+pub struct CliTransport;
+
+impl Libgit2Transport {
+    /// Fail early for remotes whose protocol this build of libgit2 lacks, rather than with
+    /// libgit2's unsupported protocol error;
+    fn check_protocol(url: &str) -> Result<(), Error> {
+        let missing = if url.starts_with("https://") || url.starts_with("http://") {
+            Some("https").filter(|_| !cfg!(feature = "https"))
+        } else if ssh_host(url).is_some() {
+            Some("ssh").filter(|_| !cfg!(feature = "ssh"))
+        } else {
+            None
+        };
+
+        match missing {
+            Some(feature) => Err(Error::msg(format!(
+                "cargo-cvm was built without the `{}` feature, so libgit2 cannot reach {}; use --git-backend cli",
+                feature, url
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Transport for Libgit2Transport {
+    fn fetch(&self, fetcher: &Fetcher, repo: &Repository, branch: &str) -> Result<(), Error> {
+        if let Ok(url) = fetcher.target_url(repo) {
+            Self::check_protocol(&url)?;
+        }
+        fetcher.fetch_target_libgit2(repo, branch)
+    }
+
+    fn list_heads(
+        &self,
+        fetcher: &Fetcher,
+        repo: &Repository,
+        url: &str,
+    ) -> Result<Vec<String>, Error> {
+        Self::check_protocol(url)?;
+        if let Some(ca_bundle) = &fetcher.ca_bundle {
+            set_ca_bundle(ca_bundle)?;
+        }
+
+        let remote = repo.find_remote(&fetcher.target_remote)?;
+        let mut remote = if fetcher.anonymous || remote.url() != Some(url) {
+            repo.remote_anonymous(url)?
+        } else {
+            remote
+        };
+        let deadline = fetcher
+            .fetch_timeout
+            .map(|timeout| Instant::now() + timeout);
+        let connection = remote.connect_auth(
+            git2::Direction::Fetch,
+            Some(fetcher.remote_callbacks(repo, deadline)),
+            Some(fetcher.proxy_options()),
+        )?;
+
+        Ok(connection
+            .list()?
+            .iter()
+            .map(|head| head.name().to_string())
+            .collect())
+    }
+}
+
+impl Transport for CliTransport {
+    fn fetch(&self, fetcher: &Fetcher, repo: &Repository, branch: &str) -> Result<(), Error> {
+        fetcher.fetch_target_cli(repo, branch)
+    }
+
+    fn list_heads(
+        &self,
+        fetcher: &Fetcher,
+        repo: &Repository,
+        url: &str,
+    ) -> Result<Vec<String>, Error> {
+        let workdir = match repo.workdir() {
+            Some(workdir) => workdir,
+            None => return Err(Error::msg("Repository has no working directory")),
+        };
+        let target = if fetcher.anonymous {
+            url
+        } else {
+            fetcher.target_remote.as_str()
+        };
+        let output = git_cli::output(
+            workdir,
+            &fetcher.cli_overrides(repo),
+            &["ls-remote", "--heads", target],
+        )?;
+
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .filter_map(|line| line.split('\t').nth(1).map(String::from))
+            .collect())
+    }
+}
+
+/// Settings for fetching and listing the branches of the target remote;
+///
+/// Unlike the `Manager`, the fetcher can be shared between threads, each fetching with a
+/// repository handle of its own.
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    pub target_remote: String,
+    pub anonymous: bool,
+    pub non_interactive: bool,
+    pub proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+    /// Minimum TLS version from cargo's `http.ssl-version`, e.g. `tlsv1.2`; only the `cli`
+    /// backend can apply it;
+    pub ssl_version: Option<String>,
+    /// Cargo's `http.check-revoke`, which git only applies on Windows with the `cli` backend;
+    pub check_revoke: Option<bool>,
+    /// Cargo's `http.timeout`, after which a `git fetch` receiving less than 10 bytes per second
+    /// is aborted, as cargo does; libgit2 has no such setting;
+    pub low_speed_timeout: Option<Duration>,
+    pub host_keys: HostKeyVerifier,
+    pub fetch_timeout: Option<Duration>,
+    pub fetch_retries: u32,
+    pub git_backend: GitBackend,
+    /// Fetch with `git` even with the libgit2 backend, following cargo's
+    /// `net.git-fetch-with-cli`; commits are still made with libgit2;
+    pub fetch_with_cli: bool,
+    pub ssh_keys: Vec<PathBuf>,
+}
+
+impl Fetcher {
+    /// Fetch the target branch, exiting with `INTERACTION_REQUIRED_EXIT_CODE` if it fails for
+    /// want of a prompt with `--non-interactive`;
+    pub fn fetch_target(&self, repo: &Repository, branch: &str) -> Result<(), Error> {
+        let result = self.transport().fetch(self, repo, branch);
+
+        match result {
+            Err(e) if self.non_interactive && Self::needs_interaction(&e) => {
+                eprintln!(
+                    "Fetching {}/{} requires interaction, which --non-interactive disables: {}",
+                    self.target_remote, branch, e
+                );
+                std::process::exit(INTERACTION_REQUIRED_EXIT_CODE)
+            }
+            result => result,
+        }
+    }
+
+    /// Returns the network settings of the HTTP requests to registries and webhooks, i.e.
+    /// cargo's with `--proxy` and `--ca-bundle` applied, as for fetches;
+    pub fn net_config(&self) -> CargoNetConfig {
+        CargoNetConfig {
+            proxy: self.proxy.clone(),
+            cainfo: self.ca_bundle.clone(),
+            ssl_version: self.ssl_version.clone(),
+            check_revoke: self.check_revoke,
+            ..CargoNetConfig::default()
+        }
+    }
+
+    /// Returns the transport of the `--git-backend`;
+    pub fn transport(&self) -> &'static dyn Transport {
+        match self.git_backend {
+            GitBackend::Libgit2 if !self.fetch_with_cli => &Libgit2Transport,
+            GitBackend::Libgit2 => &CliTransport,
+            // gitoxide is only built with its local features, so `git` fetches for it;
+            GitBackend::Cli | GitBackend::Gix => &CliTransport,
+        }
+    }
+
+    /// Returns true if a fetch failed because it needed credentials or a confirmation;
+    fn needs_interaction(e: &Error) -> bool {
+        match e.downcast_ref::<git2::Error>() {
+            Some(e) => is_auth_error(e),
+            None => git_cli::is_prompt_error(&e.to_string()),
+        }
+    }
+
+    /// Fetch the target branch with libgit2, retrying failed attempts with exponential backoff;
+    fn fetch_target_libgit2(&self, repo: &Repository, branch: &str) -> Result<(), Error> {
+        if let Some(ca_bundle) = &self.ca_bundle {
+            set_ca_bundle(ca_bundle)?;
+        }
+
+        let mut attempt = 1;
+
+        loop {
+            match self.fetch_target_watched(repo, branch) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt <= self.fetch_retries && is_retryable(&e) => {
+                    let backoff = Duration::from_secs(1 << (attempt - 1).min(6));
+                    eprintln!(
+                        "Fetch attempt {} of {} failed: {}; retrying in {}s",
+                        attempt,
+                        self.fetch_retries + 1,
+                        e,
+                        backoff.as_secs()
+                    );
+                    sleep(backoff);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Fetch the target branch once, giving up after `--fetch-timeout` even if the connection or
+    /// negotiation stalls, where libgit2 calls no progress callback that could cancel it;
+    ///
+    /// The fetch runs on a repository handle of its own in another thread, which is left behind
+    /// if it does not finish in time.
+    fn fetch_target_watched(&self, repo: &Repository, branch: &str) -> Result<(), git2::Error> {
+        let timeout = match self.fetch_timeout {
+            Some(timeout) => timeout,
+            None => return self.fetch_target_once(repo, branch, None),
+        };
+        let deadline = Instant::now() + timeout;
+
+        let (sender, receiver) = mpsc::channel();
+        let fetcher = self.clone();
+        let (path, branch) = (repo.path().to_path_buf(), branch.to_string());
+        std::thread::spawn(move || {
+            let result = Repository::open(&path)
+                .and_then(|repo| fetcher.fetch_target_once(&repo, &branch, Some(deadline)));
+            let _ = sender.send(result);
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            // Classified like a transfer cancelled by the progress callbacks;
+            Err(_) => Err(git2::Error::new(
+                git2::ErrorCode::GenericError,
+                git2::ErrorClass::Callback,
+                format!("fetch timed out after {}s", timeout.as_secs()),
+            )),
+        }
+    }
+
+    /// Private keys tried in order when the remote asks for an ssh key: those given by
+    /// `--ssh-key`, otherwise the `IdentityFile` entries of `~/.ssh/config` for the remote's
+    /// host followed by `~/.ssh/id_rsa`;
+    pub fn ssh_key_candidates(&self, repo: &Repository) -> Vec<PathBuf> {
+        if !self.ssh_keys.is_empty() {
+            return self.ssh_keys.clone();
+        }
+
+        let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+        let mut keys = match (
+            self.target_url(repo).ok().as_deref().and_then(ssh_host),
+            read_to_string(home.join(".ssh/config")),
+        ) {
+            (Some(host), Ok(config)) => ssh_config::identity_files(&config, &host, &home),
+            _ => Vec::new(),
+        };
+
+        let default = home.join(".ssh/id_rsa");
+        if !keys.contains(&default) {
+            keys.push(default);
+        }
+
+        keys
+    }
+
+    /// Callbacks authenticating against the target remote and verifying its host key, cancelling
+    /// the transfer if it is still running at `deadline`;
+    fn remote_callbacks(
+        &self,
+        repo: &Repository,
+        deadline: Option<Instant>,
+    ) -> git2::RemoteCallbacks<'_> {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        if !self.anonymous {
+            let ssh_keys = self.ssh_key_candidates(repo);
+            let mut attempt = 0;
+            // libgit2 calls back again after the remote rejected a credential;
+            callbacks.credentials(move |_url, username_from_url, allowed_types| {
+                let credentials = credentials(&ssh_keys, attempt, username_from_url, allowed_types);
+                if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+                    attempt += 1;
+                }
+                credentials
+            });
+        }
+
+        // Only verify host keys of SSH remotes; overriding the certificate check of HTTPS
+        // remotes would skip libgit2's own validation;
+        let is_ssh = match self.target_url(repo) {
+            Ok(url) => anonymous_url(&url).is_some(),
+            Err(_) => false,
+        };
+        if is_ssh && !self.anonymous && self.host_keys.policy != HostKeyPolicy::Default {
+            callbacks.certificate_check(move |cert, host| self.host_keys.verify(cert, host));
+        }
+
+        // Returning false from the progress callbacks cancels the transfer;
+        let in_time = move || deadline.map(|d| Instant::now() < d).unwrap_or(true);
+        callbacks.transfer_progress(move |_| in_time());
+        callbacks.sideband_progress(move |_| in_time());
+
+        callbacks
+    }
+
+    fn proxy_options(&self) -> git2::ProxyOptions<'_> {
+        // Use the explicit proxy if given, otherwise detect it from the git configuration and
+        // environment, i.e. `http.proxy` and `https_proxy`;
+        let mut proxy_options = git2::ProxyOptions::new();
+        match &self.proxy {
+            Some(proxy) => proxy_options.url(proxy),
+            None => proxy_options.auto(),
+        };
+
+        proxy_options
+    }
+
+    /// URL of the target remote after applying the `url.<base>.insteadOf` rules of the git
+    /// configuration;
+    pub fn target_url(&self, repo: &Repository) -> Result<String, git2::Error> {
+        let config = repo.config()?;
+        let url = config.get_string(&format!("remote.{}.url", self.target_remote))?;
+
+        Ok(rewrite_url(&url, &insteadof_rules(&config)?))
+    }
+
+    /// Fetch the target branch once, aborting the transfer if it is still running at `deadline`;
+    pub fn fetch_target_once(
+        &self,
+        repo: &Repository,
+        branch: &str,
+        deadline: Option<Instant>,
+    ) -> Result<(), git2::Error> {
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(self.remote_callbacks(repo, deadline));
+        fetch_options.proxy_options(self.proxy_options());
+        // Concurrent fetches of several target branches would race on the lock of
+        // `.git/FETCH_HEAD`, which nothing reads;
+        fetch_options.update_fetchhead(false);
+
+        let refspec = format!(
+            "+refs/heads/{branch}:refs/remotes/{remote}/{branch}",
+            branch = branch,
+            remote = self.target_remote
+        );
+
+        match repo.find_remote(&self.target_remote) {
+            Ok(_) if self.anonymous => {
+                // Fetch over HTTPS without credentials, updating the remote-tracking branch;
+                let url = self.target_url(repo)?;
+                let url = anonymous_url(&url).unwrap_or(url);
+
+                repo.remote_anonymous(&url)?
+                    .fetch(&[&refspec], Some(&mut fetch_options), None)?;
+                Ok(())
+            }
+            Ok(mut remote) => {
+                // Fetch the rewritten URL explicitly if libgit2 did not apply the insteadOf
+                // rules itself, updating the remote-tracking branch like `git fetch` would;
+                let url = self.target_url(repo)?;
+                if remote.url() == Some(url.as_str()) {
+                    remote.fetch(&[branch], Some(&mut fetch_options), None)?;
+                } else {
+                    repo.remote_anonymous(&url)?.fetch(
+                        &[&refspec],
+                        Some(&mut fetch_options),
+                        None,
+                    )?;
+                }
+                Ok(())
+            }
+            Err(e) => {
+                eprint!(
+                    "Failed to find target remote host: {:?}; Error: {:?}",
+                    &self.target_remote, e
+                );
+                let remotes = repo.remotes()?;
+                let remotes = &remotes
+                    .iter()
+                    .map(|remote| remote.unwrap_or(""))
+                    .collect::<Vec<&str>>();
+                println!("\nAvailable Remotes: {:?}", remotes);
+                eprintln!("Remote does not exist; try again with an available remote.");
+                std::process::exit(1)
+            }
+        }
+    }
+
+    /// Overrides of the `git` commands reaching the target remote: the proxy and TLS settings,
+    /// and no prompts with `--non-interactive`;
+    pub fn cli_overrides(&self, repo: &Repository) -> git_cli::Overrides {
+        let mut overrides = git_cli::Overrides::default();
+        let config = &mut overrides.config;
+        if let Some(proxy) = &self.proxy {
+            config.push(format!("http.proxy={}", proxy));
+        }
+        if let Some(ca_bundle) = &self.ca_bundle {
+            config.push(format!("http.sslCAInfo={}", ca_bundle.display()));
+        }
+        if let Some(ssl_version) = &self.ssl_version {
+            config.push(format!("http.sslVersion={}", ssl_version));
+        }
+        if let Some(check_revoke) = self.check_revoke {
+            config.push(format!("http.schannelCheckRevoke={}", check_revoke));
+        }
+        if let Some(timeout) = self.low_speed_timeout {
+            config.push(String::from("http.lowSpeedLimit=10"));
+            config.push(format!("http.lowSpeedTime={}", timeout.as_secs()));
+        }
+
+        if self.non_interactive {
+            let ssh_command = repo
+                .config()
+                .and_then(|config| config.get_string("core.sshCommand"))
+                .ok();
+            overrides.disable_prompts(
+                std::env::var("GIT_SSH_COMMAND").ok(),
+                std::env::var("GIT_SSH").ok().filter(|ssh| !ssh.is_empty()),
+                ssh_command,
+            );
+        }
+
+        overrides
+    }
+
+    /// Fetch the target branch with `git fetch`, leaving credentials, retries, and timeouts
+    /// to the user's git configuration;
+    pub fn fetch_target_cli(&self, repo: &Repository, branch: &str) -> Result<(), Error> {
+        let workdir = match repo.workdir() {
+            Some(workdir) => workdir,
+            None => return Err(Error::msg("Repository has no working directory")),
+        };
+        let mut overrides = self.cli_overrides(repo);
+
+        let refspec = format!(
+            "+refs/heads/{branch}:refs/remotes/{remote}/{branch}",
+            branch = branch,
+            remote = self.target_remote
+        );
+
+        if self.anonymous {
+            let url = self.target_url(repo)?;
+            let url = anonymous_url(&url).unwrap_or(url);
+            // Disable credential helpers and prompts so the fetch never asks for credentials;
+            overrides.config.push("credential.helper=".to_string());
+            overrides
+                .env
+                .push(("GIT_TERMINAL_PROMPT", String::from("0")));
+            git_cli::run(
+                workdir,
+                &overrides,
+                &["fetch", "--no-write-fetch-head", &url, &refspec],
+            )
+        } else {
+            git_cli::run(
+                workdir,
+                &overrides,
+                &[
+                    "fetch",
+                    "--no-write-fetch-head",
+                    &self.target_remote,
+                    &refspec,
+                ],
+            )
+        }
+    }
+
+    /// List the branches of the target remote without fetching them, which requires the remote
+    /// to be reachable and the credentials to be accepted;
+    pub fn list_remote_branches(&self, repo: &Repository) -> Result<Vec<String>, Error> {
+        repo.find_remote(&self.target_remote)?;
+        let url = self.target_url(repo)?;
+        let url = match anonymous_url(&url) {
+            Some(anonymous) if self.anonymous => anonymous,
+            _ => url,
+        };
+
+        let heads = self.transport().list_heads(self, repo, &url)?;
+
+        Ok(heads
+            .iter()
+            .filter_map(|head| head.strip_prefix("refs/heads/"))
+            .map(String::from)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{anonymous_url, fingerprint, rewrite_url};
+
+    #[test]
+    fn test_fingerprint() {
+        // `ssh-keygen -lf` prints the SHA256 of the empty input as below;
+        let hash = [
+            0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f,
+            0xb9, 0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b,
+            0x78, 0x52, 0xb8, 0x55,
+        ];
+        assert_eq!(
+            fingerprint(&hash),
+            "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+    }
+
+    #[test]
+    fn test_anonymous_url() {
+        assert_eq!(
+            anonymous_url("git@github.com:infinyon/cargo-cvm.git").as_deref(),
+            Some("https://github.com/infinyon/cargo-cvm.git")
+        );
+        assert_eq!(
+            anonymous_url("ssh://git@gitlab.example.com:2222/group/repo.git").as_deref(),
+            Some("https://gitlab.example.com/group/repo.git")
+        );
+        assert_eq!(
+            anonymous_url("https://github.com/infinyon/cargo-cvm.git"),
+            None
+        );
+        assert_eq!(anonymous_url("/srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn test_rewrite_url() {
+        let rules = vec![
+            (
+                String::from("https://github.com/"),
+                String::from("git@github.com:"),
+            ),
+            (
+                String::from("https://github.com/infinyon/"),
+                String::from("ssh://git@mirror.example.com/infinyon/"),
+            ),
+        ];
+
+        assert_eq!(
+            rewrite_url("https://github.com/rust-lang/cargo.git", &rules),
+            "git@github.com:rust-lang/cargo.git"
+        );
+        assert_eq!(
+            rewrite_url("https://github.com/infinyon/cargo-cvm.git", &rules),
+            "ssh://git@mirror.example.com/infinyon/cargo-cvm.git"
+        );
+        assert_eq!(
+            rewrite_url("https://gitlab.com/foo/bar.git", &rules),
+            "https://gitlab.com/foo/bar.git"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::fs::FileSystem;
+use anyhow::Error;
+use std::path::Path;
+use toml_edit::{value, DocumentMut, Item, Table};
+
+/// Value of a `[package]` field in a member manifest;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// The field is not declared;
+    Missing,
+    /// The field is inherited from `[workspace.package]`, i.e. `field.workspace = true`;
+    Inherited,
+    Value(String),
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Missing => write!(f, "unset"),
+            FieldValue::Inherited => write!(f, "inherited from the workspace"),
+            FieldValue::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Read a manifest, failing clearly if it is not UTF-8, e.g. saved as UTF-16 by an editor;
+/// a UTF-8 byte order mark is kept as part of the contents;
+pub fn read_manifest(fs: &dyn FileSystem, manifest: &Path) -> Result<String, Error> {
+    let bytes = fs.read(manifest)?;
+
+    String::from_utf8(bytes).map_err(|e| {
+        let utf16 =
+            e.as_bytes().starts_with(&[0xff, 0xfe]) || e.as_bytes().starts_with(&[0xfe, 0xff]);
+        Error::msg(format!(
+            "Manifest {:?} is not valid UTF-8{}; Cargo only reads UTF-8 manifests",
+            manifest.display(),
+            if utf16 { " but UTF-16" } else { "" }
+        ))
+    })
+}
+
+pub fn load(fs: &dyn FileSystem, manifest: &Path) -> Result<DocumentMut, Error> {
+    read_manifest(fs, manifest)?
+        .parse::<DocumentMut>()
+        .map_err(|e| Error::msg(format!("Invalid manifest {:?}: {}", manifest.display(), e)))
+}
+
+/// Read a field of the `[package]` table, or of the `[workspace.package]` table if `workspace`
+/// is set;
+pub fn get(doc: &DocumentMut, key: &str, workspace: bool) -> FieldValue {
+    let table = match package_table(doc, workspace) {
+        Some(table) => table,
+        None => return FieldValue::Missing,
+    };
+
+    match table.get(key) {
+        None => FieldValue::Missing,
+        Some(item) => {
+            if item.get("workspace").and_then(Item::as_bool) == Some(true) {
+                FieldValue::Inherited
+            } else if let Some(value) = item.as_str() {
+                FieldValue::Value(value.to_string())
+            } else {
+                FieldValue::Value(item.to_string().trim().to_string())
+            }
+        }
+    }
+}
+
+/// Set a string field of the `[package]` table, or of the `[workspace.package]` table if
+/// `workspace` is set, preserving the formatting of the rest of the manifest; returns false
+/// if the field already had the value;
+pub fn set(doc: &mut DocumentMut, key: &str, new: &str, workspace: bool) -> Result<bool, Error> {
+    if get(doc, key, workspace) == FieldValue::Value(new.to_string()) {
+        return Ok(false);
+    }
+
+    let table = if workspace {
+        doc.entry("workspace")
+            .or_insert_with(|| {
+                let mut workspace = Table::new();
+                workspace.set_implicit(true);
+                Item::Table(workspace)
+            })
+            .as_table_mut()
+            .and_then(|workspace| {
+                workspace
+                    .entry("package")
+                    .or_insert_with(|| Item::Table(Table::new()))
+                    .as_table_mut()
+            })
+    } else {
+        doc.get_mut("package").and_then(Item::as_table_mut)
+    };
+
+    match table {
+        Some(table) => {
+            table[key] = value(new);
+            Ok(true)
+        }
+        None => Err(Error::msg("Manifest has no [package] table")),
+    }
+}
+
+/// Write the document, keeping the line endings of the manifest it replaces;
+pub fn save(fs: &dyn FileSystem, doc: &DocumentMut, manifest: &Path) -> Result<(), Error> {
+    let original = read_manifest(fs, manifest).unwrap_or_default();
+    fs.write(manifest, &match_line_endings(&doc.to_string(), &original))
+}
+
+/// Convert the line endings of `contents` to CRLF if `original` uses them, so that editing a
+/// manifest checked out with CRLF line endings does not rewrite every line;
+pub fn match_line_endings(contents: &str, original: &str) -> String {
+    if original.contains("\r\n") {
+        contents.replace("\r\n", "\n").replace('\n', "\r\n")
+    } else {
+        contents.to_string()
+    }
+}
+
+/// Read a setting of the `[package.metadata.cvm]` table;
+pub fn cvm_metadata<'a>(doc: &'a DocumentMut, key: &str) -> Option<&'a Item> {
+    doc.get("package")?.get("metadata")?.get("cvm")?.get(key)
+}
+
+fn package_table(doc: &DocumentMut, workspace: bool) -> Option<&Table> {
+    if workspace {
+        doc.get("workspace")?.get("package")?.as_table()
+    } else {
+        doc.get("package")?.as_table()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get, match_line_endings, set, FieldValue};
+
+    #[test]
+    fn test_set_field() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = "[package]\nname = \"foo\" # the name\nedition.workspace = true\n"
+            .parse::<toml_edit::DocumentMut>()?;
+
+        assert_eq!(get(&doc, "edition", false), FieldValue::Inherited);
+        assert_eq!(get(&doc, "rust-version", false), FieldValue::Missing);
+
+        assert!(set(&mut doc, "rust-version", "1.56", false)?);
+        assert!(!set(&mut doc, "rust-version", "1.56", false)?);
+        assert!(set(&mut doc, "edition", "2021", true)?);
+
+        assert_eq!(
+            doc.to_string(),
+            "[package]\nname = \"foo\" # the name\nedition.workspace = true\nrust-version = \"1.56\"\n\n[workspace.package]\nedition = \"2021\"\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_line_endings() {
+        let original = "[package]\r\nname = \"foo\"\r\n";
+        let edited = "[package]\nname = \"foo\"\nedition = \"2021\"\n";
+
+        assert_eq!(
+            match_line_endings(edited, original),
+            "[package]\r\nname = \"foo\"\r\nedition = \"2021\"\r\n"
+        );
+        assert_eq!(match_line_endings(edited, "[package]\n"), edited);
+    }
+}
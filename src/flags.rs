@@ -0,0 +1,258 @@
+use anyhow::Error;
+use clap::{ArgMatches, Values};
+
+/// Subcommands naming the mode of a run, with the mode flags that each implies;
+const VERBS: &[(&str, &[&str])] = &[
+    ("check", &["check"]),
+    ("status", &["warn"]),
+    ("fix", &["fix"]),
+    ("release", &["force", "commit"]),
+];
+
+/// Mode flags of `cvm` replaced by the verbs, with the command to use instead; they remain
+/// hidden aliases for one release;
+const DEPRECATED: &[(&str, &str)] = &[
+    ("check", "cargo cvm check"),
+    ("fix", "cargo cvm fix"),
+    ("force", "cargo cvm fix --force"),
+    ("warn", "cargo cvm status"),
+];
+
+/// Flags that bump versions, i.e. that write manifests;
+const BUMPS: &[&str] = &["fix", "force", "fix-path-deps"];
+
+/// Flags that only take effect with a bump flag;
+const NEEDS_BUMP: &[&str] = &[
+    "commit",
+    "dry-run",
+    "preflight",
+    "allow-dirty",
+    "bump-from-commits",
+];
+
+/// Subcommands that write manifests themselves, to which `--dry-run` applies as well;
+const EDITS: &[&str] = &["apply", "align-deps", "hoist-deps", "edition", "msrv"];
+
+/// Returns warnings for flags given without the flags they take effect with, where that is any
+/// of several flags, which clap's `requires` cannot express; `present` tells whether a flag of
+/// the run was given, see [`Invocation::is_present`];
+///
+/// Combinations in which one flag silently wins, e.g. `--check` with `--fix`, are rejected by
+/// clap instead.
+pub fn incoherent(present: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if !BUMPS.iter().any(|flag| present(flag)) {
+        let edits = EDITS.iter().any(|subcommand| present(subcommand));
+        for flag in NEEDS_BUMP
+            .iter()
+            .filter(|flag| present(flag) && !(edits && **flag == "dry-run"))
+        {
+            warnings.push(format!(
+                "--{} has no effect without `cargo cvm fix`, `cargo cvm release`, or --fix-path-deps",
+                flag
+            ));
+        }
+    }
+
+    if present("show-ok") && !present("check") {
+        warnings.push(String::from(
+            "--show-ok has no effect without `cargo cvm check`",
+        ));
+    }
+
+    if present("allow-version-jump") && !(present("strict") && present("check")) {
+        warnings.push(String::from(
+            "--allow-version-jump has no effect without --strict and `cargo cvm check`",
+        ));
+    }
+
+    warnings
+}
+
+/// Returns a note for each deprecated mode flag given to `cvm`, naming the verb to use instead;
+/// subcommands taking them, e.g. `cargo cvm --force plan`, have no verb to use;
+pub fn deprecated(args: &ArgMatches) -> Vec<String> {
+    if args.subcommand_name().is_some() {
+        return Vec::new();
+    }
+
+    DEPRECATED
+        .iter()
+        .filter(|(flag, _)| given(args, flag))
+        .map(|(flag, verb)| format!("--{} is deprecated; use `{}` instead", flag, verb))
+        .collect()
+}
+
+/// Returns true if the flag was given on the command line; unlike `is_present`, not if a
+/// subcommand has its name, e.g. `cargo cvm check`;
+fn given(args: &ArgMatches, flag: &str) -> bool {
+    args.occurrences_of(flag) > 0
+}
+
+/// The options of a run, given to `cvm` and, if any, to its verb, e.g. `cargo cvm fix --commit`;
+///
+/// Options of the verb take precedence over the same options given to `cvm` before it, and
+/// the mode flags implied by the verb are present.
+pub struct Invocation<'a> {
+    args: &'a ArgMatches<'a>,
+    verb: Option<(&'a ArgMatches<'a>, &'static [&'static str])>,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn new(args: &'a ArgMatches<'a>) -> Result<Self, Error> {
+        let verb = VERBS.iter().find_map(|(verb, implied)| {
+            args.subcommand_matches(verb)
+                .map(|matches| (*verb, matches, *implied))
+        });
+
+        let verb = match verb {
+            Some((name, matches, implied)) => {
+                if let Some((flag, _)) = DEPRECATED.iter().find(|(flag, _)| given(args, flag)) {
+                    return Err(Error::msg(format!(
+                        "--{} cannot be combined with `cargo cvm {}`",
+                        flag, name
+                    )));
+                }
+                Some((matches, implied))
+            }
+            None => None,
+        };
+
+        // A dry run writes nothing to commit, e.g. `cargo cvm --dry-run apply --commit`;
+        if let (name, Some(matches)) = args.subcommand() {
+            if given(args, "dry-run") && given(matches, "commit") {
+                return Err(Error::msg(format!(
+                    "--dry-run cannot be combined with `cargo cvm {} --commit`",
+                    name
+                )));
+            }
+        }
+
+        Ok(Self { args, verb })
+    }
+
+    /// Returns true if the flag was given, or is implied by the verb;
+    pub fn is_present(&self, name: &str) -> bool {
+        match self.verb {
+            Some((matches, implied)) => {
+                implied.contains(&name) || matches.is_present(name) || self.args.is_present(name)
+            }
+            None => self.args.is_present(name),
+        }
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<&'a str> {
+        self.verb
+            .and_then(|(matches, _)| matches.value_of(name))
+            .or_else(|| self.args.value_of(name))
+    }
+
+    pub fn values_of(&self, name: &str) -> Option<Values<'a>> {
+        self.verb
+            .and_then(|(matches, _)| matches.values_of(name))
+            .or_else(|| self.args.values_of(name))
+    }
+
+    /// The subcommand of `cvm` other than a verb, e.g. `plan`;
+    pub fn subcommand_name(&self) -> Option<&'a str> {
+        match self.verb {
+            Some(_) => None,
+            None => self.args.subcommand_name(),
+        }
+    }
+
+    pub fn subcommand_matches(&self, name: &str) -> Option<&'a ArgMatches<'a>> {
+        match self.verb {
+            Some(_) => None,
+            None => self.args.subcommand_matches(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deprecated, incoherent, Invocation};
+    use clap::{App, Arg, SubCommand};
+
+    #[test]
+    fn test_incoherent() {
+        let given = |flags: &'static [&'static str]| move |flag: &str| flags.contains(&flag);
+
+        assert!(incoherent(given(&["fix", "commit", "preflight"])).is_empty());
+        assert!(incoherent(given(&["check", "strict", "allow-version-jump"])).is_empty());
+        assert!(incoherent(given(&["msrv", "dry-run"])).is_empty());
+        assert_eq!(
+            incoherent(given(&["commit", "show-ok"])),
+            vec![
+                "--commit has no effect without `cargo cvm fix`, `cargo cvm release`, or --fix-path-deps",
+                "--show-ok has no effect without `cargo cvm check`",
+            ]
+        );
+        assert_eq!(
+            incoherent(given(&["check", "allow-version-jump"])),
+            vec!["--allow-version-jump has no effect without --strict and `cargo cvm check`"]
+        );
+    }
+
+    #[test]
+    fn test_invocation() -> Result<(), anyhow::Error> {
+        let options = || {
+            vec![
+                Arg::with_name("semver").long("semver").takes_value(true),
+                Arg::with_name("commit").long("commit"),
+            ]
+        };
+        let app = App::new("cvm")
+            .args(&options())
+            .arg(Arg::with_name("fix").long("fix"))
+            .arg(Arg::with_name("dry-run").long("dry-run"))
+            .subcommand(SubCommand::with_name("release").args(&options()))
+            .subcommand(SubCommand::with_name("fix").args(&options()))
+            .subcommand(SubCommand::with_name("plan"))
+            .subcommand(SubCommand::with_name("apply").args(&options()));
+
+        let args = app
+            .clone()
+            .get_matches_from(vec!["cvm", "--semver", "major", "release"]);
+        let invocation = Invocation::new(&args)?;
+        assert!(invocation.is_present("force") && invocation.is_present("commit"));
+        assert!(!invocation.is_present("fix"));
+        assert_eq!(invocation.value_of("semver"), Some("major"));
+        assert_eq!(invocation.subcommand_name(), None);
+
+        let args = app.clone().get_matches_from(vec![
+            "cvm", "--semver", "major", "release", "--semver", "patch",
+        ]);
+        assert_eq!(Invocation::new(&args)?.value_of("semver"), Some("patch"));
+
+        let args = app.clone().get_matches_from(vec!["cvm", "--fix", "plan"]);
+        let invocation = Invocation::new(&args)?;
+        assert!(invocation.is_present("fix") && !invocation.is_present("commit"));
+        assert_eq!(invocation.subcommand_name(), Some("plan"));
+        assert!(deprecated(&args).is_empty());
+
+        let args = app.clone().get_matches_from(vec!["cvm", "--fix"]);
+        assert_eq!(
+            deprecated(&args),
+            vec!["--fix is deprecated; use `cargo cvm fix` instead"]
+        );
+
+        let args = app.clone().get_matches_from(vec!["cvm", "fix"]);
+        assert!(Invocation::new(&args)?.is_present("fix"));
+        assert!(deprecated(&args).is_empty());
+
+        let args = app
+            .clone()
+            .get_matches_from(vec!["cvm", "--fix", "release"]);
+        assert!(Invocation::new(&args).is_err());
+
+        let args = app
+            .clone()
+            .get_matches_from(vec!["cvm", "--dry-run", "apply"]);
+        assert!(Invocation::new(&args).is_ok());
+        let args = app.get_matches_from(vec!["cvm", "--dry-run", "apply", "--commit"]);
+        assert!(Invocation::new(&args).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::Error;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Reads and writes of the files that runs edit, i.e. manifests, so that `--dry-run` and tests
+/// can keep the edits in memory;
+pub trait FileSystem {
+    /// Read the whole file;
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error>;
+
+    /// Replace the file with `contents`;
+    fn write(&self, path: &Path, contents: &str) -> Result<(), Error>;
+
+    /// Returns true if the path is an existing file;
+    fn is_file(&self, path: &Path) -> bool;
+}
+
+/// The files on disk;
+#[derive(Debug, Default)]
+pub struct DiskFs;
+
+impl FileSystem for DiskFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        Ok(std::fs::read(path)?)
+    }
+
+    /// Replace the file atomically, keeping its permissions; a symlinked file stays a symlink
+    /// and the file it points to is replaced instead;
+    fn write(&self, path: &Path, contents: &str) -> Result<(), Error> {
+        let target = std::fs::canonicalize(path)?;
+        let permissions = std::fs::metadata(&target)?.permissions();
+
+        // Write next to the target so that the rename does not cross filesystems;
+        let tmp = target.with_extension("toml.cvm-tmp");
+        std::fs::write(&tmp, contents)?;
+        std::fs::set_permissions(&tmp, permissions)?;
+
+        if let Err(e) = std::fs::rename(&tmp, &target) {
+            std::fs::remove_file(&tmp)?;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+/// Keeps writes in memory instead of on disk, reading them back in place of the files;
+///
+/// Over the disk, it records what a `--dry-run` would write; without it, files exist only once
+/// written, for tests that must not touch the disk.
+#[derive(Debug, Default)]
+pub struct RecordingFs {
+    /// Whether files not written yet are read from the disk;
+    disk: bool,
+    writes: RefCell<BTreeMap<PathBuf, String>>,
+}
+
+impl RecordingFs {
+    pub fn over_disk() -> Self {
+        Self {
+            disk: true,
+            writes: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the contents written, by path;
+    pub fn writes(&self) -> BTreeMap<PathBuf, String> {
+        self.writes.borrow().clone()
+    }
+}
+
+impl FileSystem for RecordingFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        if let Some(contents) = self.writes.borrow().get(path) {
+            return Ok(contents.as_bytes().to_vec());
+        }
+
+        if self.disk {
+            return DiskFs.read(path);
+        }
+
+        Err(Error::msg(format!("{:?} does not exist", path.display())))
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<(), Error> {
+        self.writes
+            .borrow_mut()
+            .insert(path.to_path_buf(), contents.to_string());
+        Ok(())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.writes.borrow().contains_key(path) || (self.disk && path.is_file())
+    }
+}
+
+/// Returns the path with symlinks resolved, e.g. a member that links to a vendored crate, for
+/// comparison with the paths git reports; the components that do not exist, e.g. outside the
+/// cone of a sparse checkout, are kept as given;
+pub fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    loop {
+        if let Ok(resolved) = std::fs::canonicalize(existing) {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |path, name| path.join(name));
+        }
+
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Returns the lines of `new` that differ from `original`, as `-` and `+` lines by position,
+/// e.g. to show what a `--dry-run` would change in a manifest;
+pub fn changed_lines(original: &str, new: &str) -> Vec<String> {
+    let old = original.lines().collect::<Vec<&str>>();
+    let new = new.lines().collect::<Vec<&str>>();
+
+    let mut lines = Vec::new();
+    for i in 0..old.len().max(new.len()) {
+        let (before, after) = (old.get(i), new.get(i));
+        if before == after {
+            continue;
+        }
+        if let Some(before) = before {
+            lines.push(format!("-{}", before));
+        }
+        if let Some(after) = after {
+            lines.push(format!("+{}", after));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_lines, resolve, FileSystem, RecordingFs};
+    use crate::testing::TempDir;
+    use std::path::Path;
+
+    #[test]
+    fn test_recording_fs() -> Result<(), Box<dyn std::error::Error>> {
+        let fs = RecordingFs::default();
+        let path = Path::new("/cvm/foo/Cargo.toml");
+        assert!(!fs.is_file(path));
+        assert!(fs.read(path).is_err());
+
+        fs.write(path, "[package]\nversion = \"0.2.0\"\n")?;
+        assert!(fs.is_file(path));
+        assert_eq!(fs.read(path)?, b"[package]\nversion = \"0.2.0\"\n");
+        assert_eq!(fs.writes().len(), 1);
+
+        assert_eq!(
+            changed_lines(
+                "[package]\nversion = \"0.1.0\"\n",
+                "[package]\nversion = \"0.2.0\"\n"
+            ),
+            vec!["-version = \"0.1.0\"", "+version = \"0.2.0\""]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new("resolve")?;
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("vendor/foo/src"))?;
+        std::fs::create_dir_all(dir.join("crates"))?;
+        std::os::unix::fs::symlink("../vendor/foo", dir.join("crates/foo"))?;
+
+        let dir = std::fs::canonicalize(dir)?;
+        assert_eq!(
+            resolve(&dir.join("crates/foo/src")),
+            dir.join("vendor/foo/src")
+        );
+        // Missing components are kept after the resolved ancestor;
+        assert_eq!(
+            resolve(&dir.join("crates/foo/benches/a.rs")),
+            dir.join("vendor/foo/benches/a.rs")
+        );
+        assert_eq!(resolve(&dir.join("crates/bar")), dir.join("crates/bar"));
+
+        Ok(())
+    }
+}
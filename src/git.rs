@@ -0,0 +1,805 @@
+use crate::fetch::Fetcher;
+use crate::git_cli::{self, GitBackend};
+#[cfg(feature = "gix")]
+use crate::gix_backend;
+use anyhow::Error;
+use git2::{Oid, Repository};
+#[cfg(any(test, feature = "gix"))]
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A file added, deleted, or modified between two trees, relative to the repository root;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(PathBuf),
+    Deleted(PathBuf),
+    Modified(PathBuf),
+}
+
+impl Change {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added(path) | Change::Deleted(path) | Change::Modified(path) => path,
+        }
+    }
+}
+
+/// A commit listed by `GitProvider::log`;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub id: Oid,
+    /// Abbreviated id, e.g. `f698119`;
+    pub short_id: String,
+    pub author: String,
+    pub email: String,
+    pub message: String,
+    /// First paragraph of the message, i.e. the subject line;
+    pub summary: String,
+    /// Commit time in seconds since the epoch;
+    pub time: i64,
+    pub parent_count: usize,
+}
+
+/// Selects the paths of a diff as git pathspecs do, e.g. `git diff -- crates/foo *Cargo.toml`:
+/// a pathspec selects the path it names and everything under it, or the paths matching it if it
+/// has wildcards; no pathspecs select every path;
+///
+/// libgit2 applies the pathspecs itself; this matches them for the other providers.
+#[cfg(any(test, feature = "gix"))]
+pub struct PathspecMatcher {
+    prefixes: Vec<PathBuf>,
+    globs: GlobSet,
+    all: bool,
+}
+
+#[cfg(any(test, feature = "gix"))]
+impl PathspecMatcher {
+    pub fn new(pathspecs: &[String]) -> Result<Self, Error> {
+        let mut prefixes = Vec::new();
+        let mut globs = GlobSetBuilder::new();
+
+        for pathspec in pathspecs {
+            if pathspec.contains(['*', '?', '[']) {
+                globs.add(Glob::new(pathspec)?);
+            } else {
+                prefixes.push(PathBuf::from(pathspec));
+            }
+        }
+
+        Ok(Self {
+            prefixes,
+            globs: globs.build()?,
+            all: pathspecs.is_empty(),
+        })
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.all
+            || self.prefixes.iter().any(|prefix| path.starts_with(prefix))
+            || self.globs.is_match(path)
+    }
+}
+
+/// The git operations that checks and fixes are built on: fetching the target branches,
+/// resolving revisions to commits and trees, diffing and reading trees, listing tags, and
+/// staging, committing, and undoing the bumps;
+///
+/// Commits and trees are passed by id rather than as borrowed objects, so that
+/// `memory::MemoryGit` can stand in for a repository in tests. Inspecting the remotes and the
+/// configuration, e.g. for `cargo cvm doctor` and `cargo cvm init`, is left to libgit2.
+pub trait GitProvider: Sync {
+    /// Fetch `branch` of the target remote;
+    fn fetch(&self, branch: &str) -> Result<(), Error>;
+
+    /// Returns the commit a revision points at, e.g. `HEAD` or `refs/remotes/origin/main`;
+    fn resolve(&self, rev: &str) -> Result<Oid, Error>;
+
+    /// Returns the best common ancestor of two commits;
+    fn merge_base(&self, one: Oid, two: Oid) -> Result<Oid, Error>;
+
+    /// Returns the tree of a commit;
+    fn tree(&self, commit: Oid) -> Result<Oid, Error>;
+
+    /// Returns the files changed between two trees, limited to those selected by `pathspecs`,
+    /// see `PathspecMatcher`, so that the diff of a large repository only covers the files
+    /// checked;
+    fn diff(&self, old: Oid, new: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error>;
+
+    /// Returns the commits reachable from `head` but not from `hide`, most recent first;
+    fn log(&self, head: Oid, hide: Oid) -> Result<Vec<CommitInfo>, Error>;
+
+    /// Returns the files a commit changed compared to its first parent, limited to those
+    /// selected by `pathspecs` as in `diff`; every file of a root commit is added;
+    fn commit_changes(&self, commit: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error>;
+
+    /// Read a file of the tree, following symlinks within the tree, e.g. manifests symlinked
+    /// from a vendor directory; None if it does not exist or links outside the repository;
+    fn read_file(&self, tree: Oid, path: &Path) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Add the files, relative to the repository root, to the index;
+    fn add(&self, paths: &[PathBuf]) -> Result<(), Error>;
+
+    /// Commit the index onto HEAD, returning the new commit;
+    fn commit(&self, msg: &str) -> Result<Oid, Error>;
+
+    /// Returns the commit with its author and message;
+    fn find_commit(&self, commit: Oid) -> Result<CommitInfo, Error>;
+
+    /// Returns the tags with the commits they point at;
+    fn tags(&self) -> Result<Vec<(String, Oid)>, Error>;
+
+    /// Returns the git directory, e.g. `.git`, which holds the state of a fix run and the lock;
+    fn git_dir(&self) -> PathBuf;
+
+    /// Returns the root of the working tree, or None for a bare repository;
+    fn workdir(&self) -> Option<PathBuf>;
+
+    /// Returns the tracked files with changes in the index or working tree, relative to the
+    /// repository root;
+    fn dirty_files(&self) -> Result<Vec<Change>, Error>;
+
+    /// Returns true if HEAD points at a commit rather than a branch, e.g. in a CI checkout;
+    fn head_detached(&self) -> Result<bool, Error>;
+
+    /// Point HEAD at the local `branch`, creating it at the commit of HEAD and keeping the index
+    /// and working tree; fails if the branch points at another commit;
+    fn attach_head(&self, branch: &str) -> Result<(), Error>;
+
+    /// Move HEAD to `commit`, keeping the index and working tree, i.e. `git reset --soft`;
+    fn reset_soft(&self, commit: Oid) -> Result<(), Error>;
+
+    /// Reset the index entries of the files, relative to the repository root, to HEAD;
+    fn unstage(&self, paths: &[PathBuf]) -> Result<(), Error>;
+
+    /// Returns who new commits are by, as `user.name <user.email>`;
+    fn signature(&self) -> Result<String, Error>;
+}
+
+/// `GitProvider` of a repository on disk, backed by libgit2 unless `--git-backend` selects the
+/// `git` executable or gitoxide for some of the operations;
+pub struct Libgit2Provider {
+    /// `Repository` is not `Sync`, so it is locked for each operation;
+    repo: Mutex<Repository>,
+    fetcher: Fetcher,
+}
+
+impl Libgit2Provider {
+    pub fn new(repo: Repository, fetcher: Fetcher) -> Self {
+        Self {
+            repo: Mutex::new(repo),
+            fetcher,
+        }
+    }
+
+    fn repo(&self) -> MutexGuard<'_, Repository> {
+        self.repo.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Read the contents of a blob; blobs missing from a partial clone are fetched on demand
+    /// from the promisor remote with `git cat-file`, which libgit2 does not support;
+    fn read_blob(&self, repo: &Repository, oid: Oid) -> Result<Vec<u8>, Error> {
+        let e = match repo.find_blob(oid) {
+            Ok(blob) => return Ok(blob.content().to_vec()),
+            Err(e) => e,
+        };
+
+        if e.code() != git2::ErrorCode::NotFound || !is_partial_clone(repo)? {
+            return Err(e.into());
+        }
+
+        let dir = repo.workdir().unwrap_or_else(|| repo.path());
+        let overrides = self.fetcher.cli_overrides(repo);
+        git_cli::output(dir, &overrides, &["cat-file", "blob", &oid.to_string()]).map_err(|e| {
+            Error::msg(format!(
+                "Manifest blob {} is missing from this partial clone and could not be fetched from the promisor remote: {}; fetch it with `git fetch {}` or clone without `--filter`",
+                oid, e, self.fetcher.target_remote
+            ))
+        })
+    }
+
+    /// Returns the files changed between two trees, see `GitProvider::diff`, or every file of
+    /// `new` if there is no `old` tree, e.g. for a root commit;
+    fn diff_trees(
+        &self,
+        old: Option<Oid>,
+        new: Oid,
+        pathspecs: &[String],
+    ) -> Result<Vec<Change>, Error> {
+        let repo = self.repo();
+
+        // libgit2 skips the subtrees outside the pathspecs rather than diff and filter them;
+        // literal paths, e.g. the src directories of members, are looked up by binary search
+        // rather than matched one by one against every pathspec;
+        let mut options = git2::DiffOptions::new();
+        for pathspec in pathspecs {
+            options.pathspec(pathspec);
+        }
+        if !pathspecs
+            .iter()
+            .any(|pathspec| pathspec.contains(['*', '?', '[']))
+        {
+            options.disable_pathspec_match(true);
+        }
+
+        let old = old.map(|old| repo.find_tree(old)).transpose()?;
+        let diff = repo.diff_tree_to_tree(
+            old.as_ref(),
+            Some(&repo.find_tree(new)?),
+            Some(&mut options),
+        )?;
+
+        // Iterate the deltas directly; `Diff::foreach` loads every blob, which fails for blobs
+        // missing from a partial clone;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| match delta.status() {
+                git2::Delta::Added => delta.new_file().path().map(|p| Change::Added(p.into())),
+                git2::Delta::Deleted => delta.old_file().path().map(|p| Change::Deleted(p.into())),
+                _ => delta.new_file().path().map(|p| Change::Modified(p.into())),
+            })
+            .collect())
+    }
+}
+
+impl GitProvider for Libgit2Provider {
+    fn fetch(&self, branch: &str) -> Result<(), Error> {
+        // Fetches run in parallel, see `Manager::fetch_targets`, each with a handle of its own;
+        let path = self.repo().path().to_path_buf();
+        self.fetcher.fetch_target(&Repository::open(path)?, branch)
+    }
+
+    fn resolve(&self, rev: &str) -> Result<Oid, Error> {
+        Ok(self.repo().revparse_single(rev)?.peel_to_commit()?.id())
+    }
+
+    fn merge_base(&self, one: Oid, two: Oid) -> Result<Oid, Error> {
+        Ok(self.repo().merge_base(one, two)?)
+    }
+
+    fn tree(&self, commit: Oid) -> Result<Oid, Error> {
+        Ok(self.repo().find_commit(commit)?.tree_id())
+    }
+
+    fn diff(&self, old: Oid, new: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error> {
+        #[cfg(feature = "gix")]
+        if self.fetcher.git_backend == GitBackend::Gix {
+            let repo = gix_backend::open(self.repo().path())?;
+            return gix_backend::changes(&repo, old, new, pathspecs);
+        }
+
+        self.diff_trees(Some(old), new, pathspecs)
+    }
+
+    fn log(&self, head: Oid, hide: Oid) -> Result<Vec<CommitInfo>, Error> {
+        let repo = self.repo();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push(head)?;
+        revwalk.hide(hide)?;
+
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            commits.push(commit_info(&repo.find_commit(oid?)?)?);
+        }
+
+        Ok(commits)
+    }
+
+    fn commit_changes(&self, commit: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error> {
+        let (parent, tree) = {
+            let repo = self.repo();
+            let commit = repo.find_commit(commit)?;
+            (commit.parent_ids().next(), commit.tree_id())
+        };
+
+        match parent {
+            Some(parent) => self.diff(self.tree(parent)?, tree, pathspecs),
+            None => self.diff_trees(None, tree, pathspecs),
+        }
+    }
+
+    fn read_file(&self, tree: Oid, path: &Path) -> Result<Option<Vec<u8>>, Error> {
+        let repo = self.repo();
+        let tree = repo.find_tree(tree)?;
+        let mut path = path.to_path_buf();
+
+        // Bound the number of links followed, in case of a cycle;
+        for _ in 0..8 {
+            let entry = match tree.get_path(&path) {
+                Ok(entry) => entry,
+                Err(_) => return Ok(None),
+            };
+            let contents = self.read_blob(&repo, entry.id())?;
+
+            if entry.filemode() != i32::from(git2::FileMode::Link) {
+                return Ok(Some(contents));
+            }
+
+            // Resolve the link relative to the directory containing it;
+            let link = PathBuf::from(String::from_utf8_lossy(&contents).into_owned());
+            let mut resolved = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            for component in link.components() {
+                match component {
+                    std::path::Component::Normal(name) => resolved.push(name),
+                    std::path::Component::CurDir => {}
+                    std::path::Component::ParentDir if resolved.pop() => {}
+                    _ => return Ok(None),
+                }
+            }
+            path = resolved;
+        }
+
+        Ok(None)
+    }
+
+    fn add(&self, paths: &[PathBuf]) -> Result<(), Error> {
+        let mut index = self.repo().index()?;
+
+        for path in paths {
+            index.add_path(path)?;
+        }
+
+        index.write()?;
+        Ok(())
+    }
+
+    fn commit(&self, msg: &str) -> Result<Oid, Error> {
+        let repo = self.repo();
+
+        if self.fetcher.git_backend == GitBackend::Cli {
+            // Commit the staged changes with `git commit` so that hooks and signing apply;
+            let workdir = match repo.workdir() {
+                Some(workdir) => workdir,
+                None => return Err(Error::msg("Repository has no working directory")),
+            };
+            git_cli::run(
+                workdir,
+                &git_cli::Overrides::default(),
+                &["commit", "--quiet", "-m", msg],
+            )?;
+
+            return Ok(repo.refname_to_id("HEAD")?);
+        }
+
+        let mut index = repo.index()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = signature(&repo)?;
+        let parent_commit = repo.head()?.peel_to_commit()?;
+
+        Ok(repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&parent_commit])?)
+    }
+
+    fn find_commit(&self, commit: Oid) -> Result<CommitInfo, Error> {
+        commit_info(&self.repo().find_commit(commit)?)
+    }
+
+    fn tags(&self) -> Result<Vec<(String, Oid)>, Error> {
+        let repo = self.repo();
+
+        let mut tags = Vec::new();
+        for name in repo.tag_names(None)?.iter().flatten() {
+            let commit = repo
+                .revparse_single(&format!("refs/tags/{}", name))?
+                .peel_to_commit()?
+                .id();
+            tags.push((name.to_string(), commit));
+        }
+        Ok(tags)
+    }
+
+    fn git_dir(&self) -> PathBuf {
+        self.repo().path().to_path_buf()
+    }
+
+    fn workdir(&self) -> Option<PathBuf> {
+        self.repo().workdir().map(Path::to_path_buf)
+    }
+
+    fn dirty_files(&self) -> Result<Vec<Change>, Error> {
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(false).include_ignored(false);
+
+        let repo = self.repo();
+        let statuses = repo.statuses(Some(&mut options))?;
+        Ok(statuses
+            .iter()
+            .filter(|entry| entry.status() != git2::Status::CURRENT)
+            .filter_map(|entry| {
+                let path = PathBuf::from(entry.path()?);
+                let status = entry.status();
+                Some(
+                    if status.intersects(git2::Status::WT_DELETED | git2::Status::INDEX_DELETED) {
+                        Change::Deleted(path)
+                    } else if status.contains(git2::Status::INDEX_NEW) {
+                        Change::Added(path)
+                    } else {
+                        Change::Modified(path)
+                    },
+                )
+            })
+            .collect())
+    }
+
+    fn head_detached(&self) -> Result<bool, Error> {
+        Ok(self.repo().head_detached()?)
+    }
+
+    fn attach_head(&self, branch: &str) -> Result<(), Error> {
+        let repo = self.repo();
+        let head = repo.head()?.peel_to_commit()?;
+        match repo.find_branch(branch, git2::BranchType::Local) {
+            Ok(existing) => {
+                if existing.get().target() != Some(head.id()) {
+                    return Err(Error::msg(format!(
+                        "Branch {} does not point at the checked-out commit {}; refusing to commit onto it",
+                        branch,
+                        head.id()
+                    )));
+                }
+            }
+            Err(_) => {
+                repo.branch(branch, &head, false)?;
+            }
+        }
+        repo.set_head(&format!("refs/heads/{}", branch))?;
+
+        Ok(())
+    }
+
+    fn reset_soft(&self, commit: Oid) -> Result<(), Error> {
+        let repo = self.repo();
+        let commit = repo.find_commit(commit)?;
+        repo.reset(commit.as_object(), git2::ResetType::Soft, None)?;
+        Ok(())
+    }
+
+    fn unstage(&self, paths: &[PathBuf]) -> Result<(), Error> {
+        let repo = self.repo();
+        let head = repo.head()?.peel_to_commit()?;
+        repo.reset_default(Some(head.as_object()), paths.iter())?;
+        Ok(())
+    }
+
+    fn signature(&self) -> Result<String, Error> {
+        Ok(signature(&self.repo())?.to_string())
+    }
+}
+
+/// Returns the commit with its author and message, see `GitProvider::find_commit`;
+fn commit_info(commit: &git2::Commit<'_>) -> Result<CommitInfo, Error> {
+    let author = commit.author();
+    Ok(CommitInfo {
+        id: commit.id(),
+        short_id: commit
+            .as_object()
+            .short_id()?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        author: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+        parent_count: commit.parent_count(),
+    })
+}
+
+/// Returns the signature of new commits, from `user.name` and `user.email`;
+pub fn signature(repo: &Repository) -> Result<git2::Signature<'static>, Error> {
+    let config = repo.config()?;
+
+    let name = config.get_entry("user.name")?;
+    let email = config.get_entry("user.email")?;
+
+    let sig = git2::Signature::now(
+        name.value().unwrap_or_default(),
+        email.value().unwrap_or_default(),
+    )?;
+
+    Ok(sig)
+}
+
+/// Returns true if the repository is a partial clone, i.e. it has a promisor remote;
+pub fn is_partial_clone(repo: &Repository) -> Result<bool, Error> {
+    let config = repo.config()?;
+
+    if config.get_string("extensions.partialclone").is_ok() {
+        return Ok(true);
+    }
+
+    let entries = config.entries(Some("remote\\..*\\.promisor"))?;
+    for entry in &entries {
+        if entry?.value() == Some("true") {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+pub mod memory {
+    use super::{Change, CommitInfo, GitProvider, PathspecMatcher};
+    use anyhow::Error;
+    use git2::Oid;
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+    /// What was done to a `MemoryGit`;
+    #[derive(Debug, Default)]
+    pub struct Log {
+        pub fetched: Vec<String>,
+        pub staged: Vec<PathBuf>,
+        pub committed: Vec<String>,
+    }
+
+    /// `GitProvider` holding commits and trees in memory, for tests of the `Manager` that would
+    /// otherwise need a repository with a remote;
+    ///
+    /// Commits have at most one parent, and HEAD is never detached; fetches, staged files, and
+    /// commit messages are recorded in the `log`, which tests keep a handle of.
+    #[derive(Default)]
+    pub struct MemoryGit {
+        refs: Mutex<BTreeMap<String, Oid>>,
+        /// Parent and tree of each commit;
+        commits: Mutex<BTreeMap<Oid, (Option<Oid>, Oid)>>,
+        trees: BTreeMap<Oid, BTreeMap<PathBuf, Vec<u8>>>,
+        /// Root of the working tree, whose files are on disk; None for a bare repository;
+        pub workdir: Option<PathBuf>,
+        pub log: Arc<Mutex<Log>>,
+    }
+
+    impl MemoryGit {
+        /// Add a commit of the files onto `parent`, pointing `rev` at it;
+        pub fn commit_files(
+            &mut self,
+            rev: &str,
+            parent: Option<Oid>,
+            files: &[(&str, &str)],
+        ) -> Oid {
+            let files: BTreeMap<PathBuf, Vec<u8>> = files
+                .iter()
+                .map(|(path, contents)| (PathBuf::from(path), contents.as_bytes().to_vec()))
+                .collect();
+            let tree = Oid::hash_object(git2::ObjectType::Tree, format!("{:?}", files).as_bytes())
+                .expect("hash tree");
+            self.trees.insert(tree, files);
+
+            let commit = Oid::hash_object(
+                git2::ObjectType::Commit,
+                format!("{} {:?} {}", rev, parent, tree).as_bytes(),
+            )
+            .expect("hash commit");
+            lock(&self.commits).insert(commit, (parent, tree));
+            lock(&self.refs).insert(rev.to_string(), commit);
+            commit
+        }
+
+        fn find(&self, commit: Oid) -> Result<(Option<Oid>, Oid), Error> {
+            lock(&self.commits)
+                .get(&commit)
+                .copied()
+                .ok_or_else(|| Error::msg(format!("commit {} not found", commit)))
+        }
+    }
+
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the files changed between the files of two trees, see `GitProvider::diff`;
+    fn changes(
+        old: &BTreeMap<PathBuf, Vec<u8>>,
+        new: &BTreeMap<PathBuf, Vec<u8>>,
+        pathspecs: &[String],
+    ) -> Result<Vec<Change>, Error> {
+        let matcher = PathspecMatcher::new(pathspecs)?;
+
+        let mut changes = Vec::new();
+        for (path, contents) in new.iter().filter(|(path, _)| matcher.is_match(path)) {
+            match old.get(path) {
+                None => changes.push(Change::Added(path.clone())),
+                Some(old) if old != contents => changes.push(Change::Modified(path.clone())),
+                Some(_) => {}
+            }
+        }
+        for path in old
+            .keys()
+            .filter(|path| !new.contains_key(*path) && matcher.is_match(path))
+        {
+            changes.push(Change::Deleted(path.clone()));
+        }
+
+        Ok(changes)
+    }
+
+    impl GitProvider for MemoryGit {
+        fn fetch(&self, branch: &str) -> Result<(), Error> {
+            lock(&self.log).fetched.push(branch.to_string());
+            Ok(())
+        }
+
+        fn resolve(&self, rev: &str) -> Result<Oid, Error> {
+            // e.g. `HEAD^`, the parent of HEAD;
+            if let Some(child) = rev.strip_suffix('^') {
+                return self
+                    .find(self.resolve(child)?)?
+                    .0
+                    .ok_or_else(|| Error::msg(format!("revision {} has no parent", child)));
+            }
+            if let Some(commit) = lock(&self.refs).get(rev) {
+                return Ok(*commit);
+            }
+            match Oid::from_str(rev) {
+                Ok(commit) if self.find(commit).is_ok() => Ok(commit),
+                _ => Err(Error::msg(format!("revision {} not found", rev))),
+            }
+        }
+
+        fn merge_base(&self, one: Oid, two: Oid) -> Result<Oid, Error> {
+            let mut ancestors = Vec::new();
+            let mut next = Some(one);
+            while let Some(commit) = next {
+                ancestors.push(commit);
+                next = self.find(commit)?.0;
+            }
+
+            let mut next = Some(two);
+            while let Some(commit) = next {
+                if ancestors.contains(&commit) {
+                    return Ok(commit);
+                }
+                next = self.find(commit)?.0;
+            }
+
+            Err(Error::msg(format!("no merge base of {} and {}", one, two)))
+        }
+
+        fn tree(&self, commit: Oid) -> Result<Oid, Error> {
+            Ok(self.find(commit)?.1)
+        }
+
+        fn diff(&self, old: Oid, new: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error> {
+            changes(&self.trees[&old], &self.trees[&new], pathspecs)
+        }
+
+        fn log(&self, head: Oid, hide: Oid) -> Result<Vec<CommitInfo>, Error> {
+            let mut hidden = Vec::new();
+            let mut next = Some(hide);
+            while let Some(commit) = next {
+                hidden.push(commit);
+                next = self.find(commit)?.0;
+            }
+
+            let mut commits = Vec::new();
+            let mut next = Some(head).filter(|commit| !hidden.contains(commit));
+            while let Some(commit) = next {
+                commits.push(self.find_commit(commit)?);
+                next = self
+                    .find(commit)?
+                    .0
+                    .filter(|parent| !hidden.contains(parent));
+            }
+
+            Ok(commits)
+        }
+
+        fn commit_changes(&self, commit: Oid, pathspecs: &[String]) -> Result<Vec<Change>, Error> {
+            let (parent, tree) = self.find(commit)?;
+            let empty = BTreeMap::new();
+            let old = match parent {
+                Some(parent) => &self.trees[&self.find(parent)?.1],
+                None => &empty,
+            };
+            changes(old, &self.trees[&tree], pathspecs)
+        }
+
+        fn read_file(&self, tree: Oid, path: &Path) -> Result<Option<Vec<u8>>, Error> {
+            Ok(self.trees[&tree].get(path).cloned())
+        }
+
+        fn add(&self, paths: &[PathBuf]) -> Result<(), Error> {
+            lock(&self.log).staged.extend(paths.iter().cloned());
+            Ok(())
+        }
+
+        fn commit(&self, msg: &str) -> Result<Oid, Error> {
+            let head = self.resolve("HEAD")?;
+            let tree = self.tree(head)?;
+
+            let mut log = lock(&self.log);
+            log.committed.push(msg.to_string());
+            let commit = Oid::hash_object(
+                git2::ObjectType::Commit,
+                format!("{} {}", head, log.committed.len()).as_bytes(),
+            )?;
+
+            lock(&self.commits).insert(commit, (Some(head), tree));
+            lock(&self.refs).insert(String::from("HEAD"), commit);
+            Ok(commit)
+        }
+
+        fn find_commit(&self, commit: Oid) -> Result<CommitInfo, Error> {
+            let (parent, _) = self.find(commit)?;
+            Ok(CommitInfo {
+                id: commit,
+                short_id: commit.to_string()[..7].to_string(),
+                author: String::new(),
+                email: String::new(),
+                message: String::new(),
+                summary: String::new(),
+                time: 0,
+                parent_count: usize::from(parent.is_some()),
+            })
+        }
+
+        fn tags(&self) -> Result<Vec<(String, Oid)>, Error> {
+            Ok(lock(&self.refs)
+                .iter()
+                .filter_map(|(name, commit)| {
+                    Some((name.strip_prefix("refs/tags/")?.to_string(), *commit))
+                })
+                .collect())
+        }
+
+        fn git_dir(&self) -> PathBuf {
+            self.workdir.clone().unwrap_or_default().join(".git")
+        }
+
+        fn workdir(&self) -> Option<PathBuf> {
+            self.workdir.clone()
+        }
+
+        fn dirty_files(&self) -> Result<Vec<Change>, Error> {
+            Ok(Vec::new())
+        }
+
+        fn head_detached(&self) -> Result<bool, Error> {
+            Ok(false)
+        }
+
+        fn attach_head(&self, _branch: &str) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn reset_soft(&self, commit: Oid) -> Result<(), Error> {
+            self.find(commit)?;
+            lock(&self.refs).insert(String::from("HEAD"), commit);
+            Ok(())
+        }
+
+        fn unstage(&self, paths: &[PathBuf]) -> Result<(), Error> {
+            lock(&self.log)
+                .staged
+                .retain(|staged| !paths.contains(staged));
+            Ok(())
+        }
+
+        fn signature(&self) -> Result<String, Error> {
+            Ok(String::from("cvm <cvm@example.com>"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathspecMatcher;
+    use std::path::Path;
+
+    #[test]
+    fn test_pathspecs() -> Result<(), anyhow::Error> {
+        let matcher =
+            PathspecMatcher::new(&[String::from("crates/foo/src"), String::from("*Cargo.toml")])?;
+        assert!(matcher.is_match(Path::new("crates/foo/src/lib.rs")));
+        assert!(matcher.is_match(Path::new("crates/bar/Cargo.toml")));
+        assert!(matcher.is_match(Path::new("Cargo.toml")));
+        // Pathspecs match whole components;
+        assert!(!matcher.is_match(Path::new("crates/foo/srcgen/lib.rs")));
+        assert!(!matcher.is_match(Path::new("docs/index.md")));
+        assert!(PathspecMatcher::new(&[])?.is_match(Path::new("docs/index.md")));
+        Ok(())
+    }
+}
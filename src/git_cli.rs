@@ -0,0 +1,187 @@
+use anyhow::Error;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Which implementation talks to git when fetching and committing;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitBackend {
+    /// Use the bundled libgit2;
+    Libgit2,
+    /// Shell out to the `git` executable, using the user's configuration, credential
+    /// helpers, and hooks;
+    Cli,
+    /// Diff trees with gitoxide, fetching with the `git` executable; requires the `gix`
+    /// feature;
+    Gix,
+}
+
+/// libgit2, unless this build lacks both its network transports, see the `https` and `ssh`
+/// features;
+impl Default for GitBackend {
+    fn default() -> Self {
+        if cfg!(any(feature = "https", feature = "ssh")) {
+            GitBackend::Libgit2
+        } else {
+            GitBackend::Cli
+        }
+    }
+}
+
+impl std::convert::TryFrom<&str> for GitBackend {
+    type Error = Error;
+    fn try_from(backend: &str) -> Result<Self, Error> {
+        match backend {
+            "libgit2" => Ok(GitBackend::Libgit2),
+            "cli" => Ok(GitBackend::Cli),
+            "gix" if cfg!(feature = "gix") => Ok(GitBackend::Gix),
+            "gix" => Err(Error::msg(
+                "cargo-cvm was built without the `gix` feature; reinstall it with `--features gix`",
+            )),
+            _ => Err(Error::msg(format!("Invalid git backend: {:?}", backend))),
+        }
+    }
+}
+
+/// `-c` configuration entries and environment variables of a `git` command, which only apply to
+/// it rather than to cvm and the other programs it runs, e.g. `cargo package` or hooks;
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub config: Vec<String>,
+    pub env: Vec<(&'static str, String)>,
+}
+
+impl Overrides {
+    /// Make `git` and `ssh` fail instead of prompting for credentials, passphrases, or unknown
+    /// host keys; `ssh` keeps the command git would run, i.e. `GIT_SSH_COMMAND` of `env_ssh`,
+    /// else the program `GIT_SSH` of `env_ssh_program`, else `core.sshCommand` of `config_ssh`,
+    /// adding `-o BatchMode=yes` to the commands; `GIT_SSH` takes no options, so it is left as
+    /// is;
+    pub fn disable_prompts(
+        &mut self,
+        env_ssh: Option<String>,
+        env_ssh_program: Option<String>,
+        config_ssh: Option<String>,
+    ) {
+        self.env.push(("GIT_TERMINAL_PROMPT", String::from("0")));
+        self.env.push(("GCM_INTERACTIVE", String::from("never")));
+        self.env
+            .push(("SSH_ASKPASS_REQUIRE", String::from("never")));
+
+        match (env_ssh, env_ssh_program) {
+            (Some(ssh), _) => self
+                .env
+                .push(("GIT_SSH_COMMAND", format!("{} -o BatchMode=yes", ssh))),
+            (None, Some(_)) => {}
+            (None, None) => self.config.push(format!(
+                "core.sshCommand={} -o BatchMode=yes",
+                config_ssh.unwrap_or_else(|| String::from("ssh"))
+            )),
+        }
+    }
+}
+
+fn command(workdir: &Path, overrides: &Overrides, args: &[&str]) -> Command {
+    let mut command = Command::new("git");
+    command.current_dir(workdir);
+
+    for entry in overrides.config.iter() {
+        command.arg("-c").arg(entry);
+    }
+    command.envs(overrides.env.iter().map(|(name, value)| (name, value)));
+
+    command.args(args);
+    command
+}
+
+/// Run `git` in `workdir` with the given overrides and arguments, failing with its standard
+/// error if it exits unsuccessfully;
+pub fn run(workdir: &Path, overrides: &Overrides, args: &[&str]) -> Result<(), Error> {
+    let output = command(workdir, overrides, args)
+        .stdout(Stdio::inherit())
+        .output()
+        .map_err(|e| Error::msg(format!("Failed to run `git`: {}", e)))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success() {
+        return Err(Error::msg(format!(
+            "`git {}` failed with {}: {}",
+            args.join(" "),
+            output.status,
+            stderr.trim()
+        )));
+    }
+
+    eprint!("{}", stderr);
+    Ok(())
+}
+
+/// Returns true if `git` failed because it would have prompted, see
+/// `Overrides::disable_prompts`;
+pub fn is_prompt_error(message: &str) -> bool {
+    [
+        "terminal prompts disabled",
+        "could not read Username",
+        "could not read Password",
+        "Host key verification failed",
+        "Permission denied (publickey",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Run `git` in `workdir` with the given overrides and return its standard output;
+pub fn output(workdir: &Path, overrides: &Overrides, args: &[&str]) -> Result<Vec<u8>, Error> {
+    let output = command(workdir, overrides, args)
+        .output()
+        .map_err(|e| Error::msg(format!("Failed to run `git`: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::msg(format!(
+            "`git {}` failed with {}: {}",
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Overrides;
+
+    #[test]
+    fn test_disable_prompts() {
+        let mut overrides = Overrides::default();
+        overrides.disable_prompts(None, None, Some(String::from("ssh -i ~/.ssh/deploy")));
+        assert_eq!(
+            overrides.config,
+            vec!["core.sshCommand=ssh -i ~/.ssh/deploy -o BatchMode=yes"]
+        );
+        assert!(overrides
+            .env
+            .contains(&("GIT_TERMINAL_PROMPT", String::from("0"))));
+
+        // git prefers `GIT_SSH_COMMAND` to `core.sshCommand`;
+        let mut overrides = Overrides::default();
+        overrides.disable_prompts(
+            Some(String::from("ssh -v")),
+            Some(String::from("plink")),
+            Some(String::from("ssh -i ~/.ssh/deploy")),
+        );
+        assert!(overrides.config.is_empty());
+        assert!(overrides
+            .env
+            .contains(&("GIT_SSH_COMMAND", String::from("ssh -v -o BatchMode=yes"))));
+
+        // then `GIT_SSH`, which would ignore `core.sshCommand`;
+        let mut overrides = Overrides::default();
+        overrides.disable_prompts(None, Some(String::from("plink")), None);
+        assert!(overrides.config.is_empty());
+        assert!(!overrides
+            .env
+            .iter()
+            .any(|(name, _)| *name == "GIT_SSH_COMMAND"));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::git::{Change, PathspecMatcher};
+use anyhow::Error;
+use gix::object::tree::diff::ChangeDetached;
+use std::path::{Path, PathBuf};
+
+/// Opens the repository at `git_dir` with gitoxide, for the diffs of `--git-backend gix`;
+pub fn open(git_dir: &Path) -> Result<gix::Repository, Error> {
+    gix::open(git_dir).map_err(|e| {
+        Error::msg(format!(
+            "Failed to open {:?} with gitoxide: {}",
+            git_dir.display(),
+            e
+        ))
+    })
+}
+
+/// Returns the files added, deleted, or modified between the trees `old` and `new`, relative to
+/// the repository root, like a libgit2 diff without rename detection, limited to `pathspecs`;
+pub fn changes(
+    repo: &gix::Repository,
+    old: git2::Oid,
+    new: git2::Oid,
+    pathspecs: &[String],
+) -> Result<Vec<Change>, Error> {
+    let old = repo.find_tree(object_id(old)?)?;
+    let new = repo.find_tree(object_id(new)?)?;
+    let matcher = PathspecMatcher::new(pathspecs)?;
+
+    // The default options track full paths without rewrites, so a rename is reported as a
+    // deletion and an addition, which are both changes to a member;
+    Ok(repo
+        .diff_tree_to_tree(&old, &new, gix::diff::Options::default())?
+        .iter()
+        .filter(|change| !change.entry_mode().is_tree())
+        .filter_map(|change| {
+            let path = PathBuf::from(change.location().to_string());
+            if !matcher.is_match(&path) {
+                return None;
+            }
+            Some(match change {
+                ChangeDetached::Addition { .. } => Change::Added(path),
+                ChangeDetached::Deletion { .. } => Change::Deleted(path),
+                _ => Change::Modified(path),
+            })
+        })
+        .collect())
+}
+
+fn object_id(oid: git2::Oid) -> Result<gix::ObjectId, Error> {
+    Ok(gix::ObjectId::from_hex(oid.to_string().as_bytes())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_changes() -> Result<(), Error> {
+        let tmp = TempDir::new("gix")?;
+        let dir = tmp.path();
+        let repo = git2::Repository::init_bare(dir)?;
+
+        let tree = |files: &[(&str, &str)]| -> Result<git2::Oid, Error> {
+            let mut src = repo.treebuilder(None)?;
+            for (name, contents) in files {
+                src.insert(name, repo.blob(contents.as_bytes())?, 0o100644)?;
+            }
+            let src = src.write()?;
+            let mut root = repo.treebuilder(None)?;
+            root.insert("src", src, 0o040000)?;
+            Ok(root.write()?)
+        };
+        let old = tree(&[("lib.rs", "fn a() {}"), ("old.rs", "")])?;
+        let new = tree(&[("lib.rs", "fn b() {}"), ("new.rs", "")])?;
+
+        let mut diff = changes(&open(dir)?, old, new, &[])?;
+        diff.sort_by(|a, b| a.path().cmp(b.path()));
+        assert_eq!(
+            diff,
+            vec![
+                Change::Modified(PathBuf::from("src/lib.rs")),
+                Change::Added(PathBuf::from("src/new.rs")),
+                Change::Deleted(PathBuf::from("src/old.rs")),
+            ]
+        );
+        assert!(changes(&open(dir)?, old, old, &[])?.is_empty());
+        assert_eq!(
+            changes(&open(dir)?, old, new, &[String::from("src/lib.rs")])?,
+            vec![Change::Modified(PathBuf::from("src/lib.rs"))]
+        );
+
+        Ok(())
+    }
+}
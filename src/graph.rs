@@ -0,0 +1,386 @@
+use crate::deps::Dependency;
+use anyhow::Error;
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::fmt::Write as _;
+
+/// Format of the graph printed by `cargo cvm graph`;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`;
+    Dot,
+    /// Mermaid flowchart, which GitHub and GitLab render in Markdown;
+    Mermaid,
+}
+
+impl TryInto<GraphFormat> for &str {
+    type Error = Error;
+    fn try_into(self) -> Result<GraphFormat, Error> {
+        match self {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(Error::msg(format!("Invalid graph format: {:?}", self))),
+        }
+    }
+}
+
+/// A workspace member;
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub version: String,
+    /// Whether the sources changed without a version bump;
+    pub outdated: bool,
+}
+
+/// A dependency of the member `from` on the member `to`, by index of the nodes;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// Dependency table declaring it, e.g. `dev-dependencies`;
+    pub table: &'static str,
+    /// Whether it requires a version, see `Dependency::versioned`;
+    pub versioned: bool,
+}
+
+impl Edge {
+    /// Dev-dependencies are not part of the published crate, so they do not cascade bumps;
+    pub fn is_dev(&self) -> bool {
+        self.table == "dev-dependencies"
+    }
+
+    /// Whether `to` must be published before `from`; `cargo publish` resolves every dependency
+    /// against the registry, except dev-dependencies without a version, which it drops;
+    pub fn orders_publish(&self) -> bool {
+        !self.is_dev() || self.versioned
+    }
+}
+
+/// Workspace members and their dependencies on each other;
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Build the graph from members and the package names of their dependencies; dependencies
+    /// on packages outside of the workspace are left out;
+    pub fn new(members: Vec<(Node, Vec<Dependency>)>) -> Self {
+        let nodes = members
+            .iter()
+            .map(|(node, _)| node.clone())
+            .collect::<Vec<Node>>();
+
+        let mut edges = BTreeSet::new();
+        for (from, (_, dependencies)) in members.iter().enumerate() {
+            for dependency in dependencies.iter() {
+                if let Some(to) = nodes
+                    .iter()
+                    .position(|node| node.name == dependency.package)
+                {
+                    edges.insert(Edge {
+                        from,
+                        to,
+                        table: dependency.table,
+                        versioned: dependency.versioned,
+                    });
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    /// Returns the members that depend on an outdated member, directly or transitively, but are
+    /// not outdated themselves; bumping an outdated member incompatibly requires bumping them
+    /// as well;
+    pub fn cascade(&self) -> BTreeSet<usize> {
+        let mut reached = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.outdated)
+            .map(|(i, _)| i)
+            .collect::<BTreeSet<usize>>();
+        let mut queue = reached.iter().copied().collect::<Vec<usize>>();
+
+        while let Some(to) = queue.pop() {
+            for edge in self.edges.iter() {
+                if edge.to == to && !edge.is_dev() && reached.insert(edge.from) {
+                    queue.push(edge.from);
+                }
+            }
+        }
+
+        reached
+            .into_iter()
+            .filter(|i| !self.nodes[*i].outdated)
+            .collect()
+    }
+
+    /// Returns the `selected` members in an order they can be published in, i.e. every member
+    /// after the members it depends on, keeping the order of the nodes otherwise; dependencies
+    /// through members that are not selected count as well;
+    pub fn publish_order(&self, selected: &BTreeSet<usize>) -> Result<Vec<usize>, Error> {
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| edge.orders_publish() && edge.from != edge.to)
+            .collect::<Vec<&Edge>>();
+
+        let mut published = BTreeSet::new();
+        let mut order = Vec::new();
+        while published.len() < self.nodes.len() {
+            // The first member whose dependencies are all published;
+            let next = (0..self.nodes.len()).find(|i| {
+                !published.contains(i)
+                    && edges
+                        .iter()
+                        .all(|edge| edge.from != *i || published.contains(&edge.to))
+            });
+
+            match next {
+                Some(i) => {
+                    published.insert(i);
+                    if selected.contains(&i) {
+                        order.push(i);
+                    }
+                }
+                None => {
+                    let blocked = (0..self.nodes.len())
+                        .filter(|i| !published.contains(i))
+                        .collect::<BTreeSet<usize>>();
+                    // Unselected members in a cycle are not published, so they do not matter;
+                    if blocked.is_disjoint(selected) {
+                        break;
+                    }
+                    return Err(self.cycle_error(&edges, &blocked));
+                }
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Describe a dependency cycle among the `blocked` members, each of which depends on
+    /// another blocked member;
+    fn cycle_error(&self, edges: &[&Edge], blocked: &BTreeSet<usize>) -> Error {
+        // Following dependencies among blocked members must eventually revisit one;
+        let mut path: Vec<&Edge> = Vec::new();
+        let mut current = blocked.iter().next().copied().unwrap_or_default();
+        while let Some(edge) = edges
+            .iter()
+            .find(|edge| edge.from == current && blocked.contains(&edge.to))
+        {
+            if let Some(start) = path.iter().position(|seen| seen.from == edge.to) {
+                path.drain(..start);
+                path.push(edge);
+                break;
+            }
+            path.push(edge);
+            current = edge.to;
+        }
+
+        let mut cycle = path
+            .first()
+            .map(|edge| self.nodes[edge.from].name.clone())
+            .unwrap_or_default();
+        for edge in path.iter() {
+            cycle.push_str(&format!(" → {} ({})", self.nodes[edge.to].name, edge.table));
+        }
+
+        let mut message = format!(
+            "Cannot order the crates for publishing, their dependencies form a cycle: {}",
+            cycle
+        );
+        if path.iter().any(|edge| edge.is_dev()) {
+            message.push_str("; `cargo publish` only drops dev-dependencies without a version, so remove the `version` of the dev-dependency to break the cycle");
+        }
+
+        Error::msg(message)
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let cascade = self.cascade();
+        let mut out = String::from("digraph workspace {\n    node [shape=box];\n");
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let style = if node.outdated {
+                ", style=filled, fillcolor=\"#f8d7da\", color=\"#dc3545\""
+            } else if cascade.contains(&i) {
+                ", style=\"filled,dashed\", fillcolor=\"#fff3cd\", color=\"#fd7e14\""
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    n{} [label=\"{}\\n{}\"{}];",
+                i,
+                escape(&node.name),
+                escape(&node.version),
+                style
+            );
+        }
+        for edge in self.edges.iter() {
+            let _ = writeln!(
+                out,
+                "    n{} -> n{}{};",
+                edge.from,
+                edge.to,
+                if edge.is_dev() { " [style=dashed]" } else { "" }
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn to_mermaid(&self) -> String {
+        let cascade = self.cascade();
+        let mut out = String::from("flowchart LR\n");
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let class = if node.outdated {
+                ":::outdated"
+            } else if cascade.contains(&i) {
+                ":::cascade"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    n{}[\"{} {}\"]{}",
+                i,
+                node.name.replace('"', "#quot;"),
+                node.version.replace('"', "#quot;"),
+                class
+            );
+        }
+        for edge in self.edges.iter() {
+            let arrow = if edge.is_dev() { "-.->" } else { "-->" };
+            let _ = writeln!(out, "    n{} {} n{}", edge.from, arrow, edge.to);
+        }
+
+        out.push_str("    classDef outdated fill:#f8d7da,stroke:#dc3545\n");
+        out.push_str("    classDef cascade fill:#fff3cd,stroke:#fd7e14,stroke-dasharray:4\n");
+        out
+    }
+}
+
+/// Escape a DOT string;
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Graph, GraphFormat, Node};
+    use crate::deps::Dependency;
+    use std::collections::BTreeSet;
+
+    fn node(name: &str, outdated: bool) -> Node {
+        Node {
+            name: String::from(name),
+            version: String::from("0.1.0"),
+            outdated,
+        }
+    }
+
+    fn dep(package: &str, table: &'static str, versioned: bool) -> Dependency {
+        Dependency {
+            package: String::from(package),
+            table,
+            versioned,
+        }
+    }
+
+    #[test]
+    fn test_cascade() {
+        let graph = Graph::new(vec![
+            (node("core", true), vec![dep("serde", "dependencies", true)]),
+            (
+                node("parser", false),
+                vec![dep("core", "dependencies", false)],
+            ),
+            (
+                node("cli", false),
+                vec![dep("parser", "build-dependencies", false)],
+            ),
+            (
+                node("tests", false),
+                vec![dep("core", "dev-dependencies", false)],
+            ),
+        ]);
+
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(
+            graph.cascade().into_iter().collect::<Vec<usize>>(),
+            vec![1, 2]
+        );
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.contains("n0 [label=\"core\\n0.1.0\", style=filled"));
+        assert!(dot.contains("n3 -> n0 [style=dashed];"));
+        let mermaid = graph.render(GraphFormat::Mermaid);
+        assert!(mermaid.contains("    n1[\"parser 0.1.0\"]:::cascade\n"));
+        assert!(mermaid.contains("    n1 --> n0\n"));
+    }
+
+    #[test]
+    fn test_publish_order() {
+        let graph = Graph::new(vec![
+            (
+                node("cli", false),
+                vec![dep("parser", "dependencies", true)],
+            ),
+            (
+                node("parser", false),
+                vec![dep("core", "dependencies", true)],
+            ),
+            (
+                node("core", false),
+                vec![dep("parser", "dev-dependencies", false)],
+            ),
+        ]);
+        let all = (0..3).collect::<BTreeSet<usize>>();
+
+        assert_eq!(graph.publish_order(&all).unwrap(), vec![2, 1, 0]);
+        assert_eq!(
+            graph
+                .publish_order(&[0, 2].iter().copied().collect())
+                .unwrap(),
+            vec![2, 0]
+        );
+
+        // A dev-dependency with a version must be published first, closing the cycle;
+        let graph = Graph::new(vec![
+            (
+                node("parser", false),
+                vec![dep("core", "dependencies", true)],
+            ),
+            (
+                node("core", false),
+                vec![dep("parser", "dev-dependencies", true)],
+            ),
+        ]);
+        let e = graph
+            .publish_order(&(0..2).collect())
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains(
+            "cycle: parser → core (dependencies) → parser (dev-dependencies); `cargo publish`"
+        ));
+    }
+}
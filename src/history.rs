@@ -0,0 +1,125 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Location of the append-only audit log, relative to the repository root; it is meant to be
+/// committed, so unlike the generated files it is not kept in the artifact directory;
+pub const HISTORY_FILE: &str = ".cvm/history.jsonl";
+
+/// A single version change recorded in the audit log;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub old_version: String,
+    pub new_version: String,
+    /// Why the version was changed; i.e. `outdated` or `forced`;
+    pub reason: String,
+    /// The version commit of the bump, or without `--commit` the commit it is staged on top of;
+    pub commit: String,
+    /// UTC time of the bump in RFC 3339 format;
+    pub timestamp: String,
+    pub user: String,
+}
+
+pub fn history_path(root: &Path) -> PathBuf {
+    root.join(HISTORY_FILE)
+}
+
+/// Append entries to the audit log, creating it if necessary;
+pub fn append(root: &Path, entries: &[HistoryEntry]) -> Result<(), Error> {
+    let path = history_path(root);
+
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    for entry in entries {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+
+    Ok(())
+}
+
+/// Read the audit log, optionally only the entries for the given crate;
+pub fn read(root: &Path, krate: Option<&str>) -> Result<Vec<HistoryEntry>, Error> {
+    let path = history_path(root);
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+
+    for (n, line) in read_to_string(&path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: HistoryEntry = serde_json::from_str(line).map_err(|e| {
+            Error::msg(format!(
+                "Invalid entry on line {} of {:?}: {}",
+                n + 1,
+                path,
+                e
+            ))
+        })?;
+
+        if krate.map(|name| name == entry.krate).unwrap_or(true) {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Current UTC time formatted as RFC 3339, e.g. `2020-08-14T09:30:00Z`;
+pub fn now_rfc3339() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    format_rfc3339(secs)
+}
+
+pub fn format_rfc3339(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // Convert days since the epoch to a civil date;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(super::format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(super::format_rfc3339(1_597_397_400), "2020-08-14T09:30:00Z");
+        assert_eq!(super::format_rfc3339(951_825_600), "2000-02-29T12:00:00Z");
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Error;
+
+/// A ref update of a push, as passed to the `pre-receive` hook on stdin;
+#[derive(Debug, PartialEq, Eq)]
+pub struct RefUpdate {
+    /// Previous commit of the ref, none if the push creates it;
+    pub old: Option<String>,
+    /// New commit of the ref, none if the push deletes it;
+    pub new: Option<String>,
+    /// Full name of the ref, e.g. `refs/heads/main`;
+    pub refname: String,
+}
+
+impl RefUpdate {
+    /// Only pushed branches carry crate changes to check; tags and deletions do not;
+    pub fn is_branch_push(&self) -> bool {
+        self.new.is_some() && self.refname.starts_with("refs/heads/")
+    }
+
+    /// Returns the name of the pushed branch, e.g. `main`, or none for other refs;
+    pub fn branch(&self) -> Option<&str> {
+        self.refname.strip_prefix("refs/heads/")
+    }
+}
+
+/// Parse the `<old> <new> <ref>` lines of the `pre-receive` hook protocol; the all-zero object
+/// name stands for a missing commit;
+pub fn parse_updates(input: &str) -> Result<Vec<RefUpdate>, Error> {
+    let commit = |oid: &str| {
+        if oid.chars().all(|c| c == '0') {
+            None
+        } else {
+            Some(String::from(oid))
+        }
+    };
+
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [old, new, refname] => Ok(RefUpdate {
+                    old: commit(old),
+                    new: commit(new),
+                    refname: String::from(refname),
+                }),
+                _ => Err(Error::msg(format!(
+                    "Invalid pre-receive input {:?}, expected `<old> <new> <ref>`",
+                    line
+                ))),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse_updates() -> Result<(), anyhow::Error> {
+        let zero = "0".repeat(40);
+        let a = "a".repeat(40);
+        let b = "b".repeat(40);
+        let input = format!(
+            "{a} {b} refs/heads/main\n{zero} {b} refs/heads/feature\n{a} {zero} refs/heads/old\n{zero} {a} refs/tags/v1.0.0\n",
+            a = a,
+            b = b,
+            zero = zero
+        );
+
+        let updates = super::parse_updates(&input)?;
+        assert_eq!(updates.len(), 4);
+        assert_eq!(updates[0].old.as_deref(), Some(a.as_str()));
+        assert_eq!(updates[1].old, None);
+        assert_eq!(
+            updates
+                .iter()
+                .filter(|update| update.is_branch_push())
+                .map(|update| update.refname.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["refs/heads/main", "refs/heads/feature"]
+        );
+
+        assert!(super::parse_updates("refs/heads/main\n").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::cargo_config::CargoNetConfig;
+use anyhow::Error;
+use std::time::Duration;
+
+/// Returns a builder of HTTP agents with the TLS stack this binary was built with, see the
+/// `rustls` and `native-tls` features, honoring the proxy environment variables and the
+/// `http.proxy`, `http.cainfo`, and `http.ssl-version` settings of `net`;
+///
+/// `http.check-revoke` is left to the TLS stack: cargo only applies it on Windows, where
+/// native TLS always checks revocation, and rustls cannot check it without a list of revoked
+/// certificates.
+pub fn agent_builder(timeout: Duration, net: &CargoNetConfig) -> Result<ureq::AgentBuilder, Error> {
+    let mut builder = ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .try_proxy_from_env(true)
+        .user_agent(concat!("cargo-cvm/", env!("CARGO_PKG_VERSION")));
+    if let Some(proxy) = &net.proxy {
+        builder = builder.proxy(ureq::Proxy::new(proxy)?);
+    }
+
+    #[cfg(feature = "rustls")]
+    if net.cainfo.is_some() || net.ssl_version.is_some() {
+        builder = builder.tls_config(std::sync::Arc::new(rustls_config(net)?));
+    }
+
+    // ureq only uses native-tls by default if it lacks rustls;
+    #[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+    let builder = builder.tls_connector(std::sync::Arc::new(native_tls_connector(net)?));
+
+    Ok(builder)
+}
+
+/// Returns the minimum TLS version of `http.ssl-version`, e.g. `tlsv1.2`, as a pair of the
+/// major and minor version; None for `default`;
+fn min_tls_version(ssl_version: &str) -> Result<Option<(u8, u8)>, Error> {
+    match ssl_version {
+        "default" => Ok(None),
+        "tlsv1" | "tlsv1.0" => Ok(Some((1, 0))),
+        "tlsv1.1" => Ok(Some((1, 1))),
+        "tlsv1.2" => Ok(Some((1, 2))),
+        "tlsv1.3" => Ok(Some((1, 3))),
+        _ => Err(Error::msg(format!(
+            "Unsupported `http.ssl-version` {:?}; expected one of default, tlsv1, tlsv1.1, tlsv1.2, or tlsv1.3",
+            ssl_version
+        ))),
+    }
+}
+
+/// rustls trusts the certificates of `http.cainfo` instead of its built-in roots, and only
+/// speaks TLS 1.2 and 1.3, so older minimum versions allow both;
+#[cfg(feature = "rustls")]
+fn rustls_config(net: &CargoNetConfig) -> Result<rustls::ClientConfig, Error> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+
+    let mut roots = rustls::RootCertStore::empty();
+    match &net.cainfo {
+        Some(cainfo) => {
+            for cert in CertificateDer::pem_file_iter(cainfo)
+                .map_err(|e| Error::msg(format!("Failed to read {:?}: {}", cainfo.display(), e)))?
+            {
+                roots.add(cert?)?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+
+    let min_version = match &net.ssl_version {
+        Some(ssl_version) => min_tls_version(ssl_version)?,
+        None => None,
+    };
+    let versions: &[&rustls::SupportedProtocolVersion] = match min_version {
+        Some(version) if version >= (1, 3) => &[&rustls::version::TLS13],
+        _ => rustls::DEFAULT_VERSIONS,
+    };
+
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(versions)?
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+/// native TLS trusts the certificates of `http.cainfo` instead of the system's, and has no
+/// setting for TLS 1.3, so `tlsv1.3` requires TLS 1.2;
+#[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+fn native_tls_connector(net: &CargoNetConfig) -> Result<native_tls::TlsConnector, Error> {
+    let mut builder = native_tls::TlsConnector::builder();
+
+    if let Some(cainfo) = &net.cainfo {
+        let contents = std::fs::read_to_string(cainfo)
+            .map_err(|e| Error::msg(format!("Failed to read {:?}: {}", cainfo.display(), e)))?;
+        // A bundle holds several certificates, which native TLS takes one at a time;
+        let end = "-----END CERTIFICATE-----";
+        for pem in contents
+            .split_inclusive(end)
+            .filter(|pem| pem.contains(end))
+        {
+            builder.add_root_certificate(native_tls::Certificate::from_pem(pem.as_bytes())?);
+        }
+        builder.disable_built_in_roots(true);
+    }
+
+    if let Some(ssl_version) = &net.ssl_version {
+        builder.min_protocol_version(match min_tls_version(ssl_version)? {
+            None => None,
+            Some((1, 0)) => Some(native_tls::Protocol::Tlsv10),
+            Some((1, 1)) => Some(native_tls::Protocol::Tlsv11),
+            Some(_) => Some(native_tls::Protocol::Tlsv12),
+        });
+    }
+
+    builder
+        .build()
+        .map_err(|e| Error::msg(format!("Failed to initialize native TLS: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::min_tls_version;
+
+    #[test]
+    fn test_min_tls_version() {
+        assert_eq!(min_tls_version("default").unwrap(), None);
+        assert_eq!(min_tls_version("tlsv1").unwrap(), Some((1, 0)));
+        assert_eq!(min_tls_version("tlsv1.3").unwrap(), Some((1, 3)));
+        assert!(min_tls_version("sslv3").is_err());
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_rustls_config() {
+        use crate::cargo_config::CargoNetConfig;
+        use std::path::PathBuf;
+
+        let net = CargoNetConfig {
+            ssl_version: Some(String::from("tlsv1.3")),
+            ..CargoNetConfig::default()
+        };
+        assert!(super::rustls_config(&net).is_ok());
+
+        let net = CargoNetConfig {
+            cainfo: Some(PathBuf::from("missing.pem")),
+            ..CargoNetConfig::default()
+        };
+        assert!(super::rustls_config(&net).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::Error;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Message catalogs by language, English first as the fallback of the others, see
+/// `locales/en.ftl`;
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+/// Environment variables selecting the locale, in the order of precedence of POSIX;
+const LOCALE_VARS: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Catalog of the selected locale, see `init`;
+static SELECTED: OnceLock<Catalog> = OnceLock::new();
+static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+
+/// Messages by id, parsed from a subset of the Fluent syntax: `id = text`, with `{ $name }`
+/// placeholders, with or without the spaces, and indented continuation lines;
+#[derive(Debug, Default)]
+pub struct Catalog {
+    messages: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    pub fn parse(contents: &str, language: &str) -> Result<Self, Error> {
+        let mut messages: BTreeMap<String, String> = BTreeMap::new();
+        let mut last: Option<String> = None;
+
+        for (n, line) in contents.lines().enumerate() {
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                last = None;
+                continue;
+            }
+
+            if line.starts_with(char::is_whitespace) {
+                if let Some(message) = last.as_ref().and_then(|id| messages.get_mut(id)) {
+                    message.push('\n');
+                    message.push_str(trimmed);
+                    continue;
+                }
+            }
+
+            let (id, text) = trimmed
+                .split_once('=')
+                .map(|(id, text)| (id.trim(), text.trim()))
+                .filter(|(id, _)| {
+                    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+                .ok_or_else(|| {
+                    Error::msg(format!(
+                        "Invalid line {} of the {} message catalog: expected `id = text`",
+                        n + 1,
+                        language
+                    ))
+                })?;
+
+            messages.insert(id.to_string(), text.to_string());
+            last = Some(id.to_string());
+        }
+
+        Ok(Self { messages })
+    }
+
+    /// Returns the message `id` with its placeholders replaced by `args`; placeholders of other
+    /// variables are kept as written;
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> Option<String> {
+        let mut rest = self.messages.get(id)?.as_str();
+        let mut formatted = String::new();
+
+        while let Some(start) = rest.find('{') {
+            let (placeholder, after) = match rest[start + 1..].split_once('}') {
+                Some(split) => split,
+                None => break,
+            };
+            formatted.push_str(&rest[..start]);
+            match variable(placeholder).and_then(|name| args.iter().find(|(arg, _)| *arg == name)) {
+                Some((_, value)) => formatted.push_str(&value.to_string()),
+                None => formatted.push_str(&rest[start..rest.len() - after.len()]),
+            }
+            rest = after;
+        }
+
+        formatted.push_str(rest);
+        Some(formatted)
+    }
+}
+
+/// Returns the variable of the inside of a placeholder, e.g. `name` of ` $name ` or `$name`;
+fn variable(placeholder: &str) -> Option<&str> {
+    placeholder.trim().strip_prefix('$').filter(|name| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+/// Select the locale of the messages: `locale` if given, e.g. from `--locale`, otherwise the
+/// one of the environment, falling back to English for unsupported ones;
+pub fn init(locale: Option<&str>) -> Result<(), Error> {
+    let language = match locale {
+        Some(locale) => {
+            let language = language(locale);
+            if !CATALOGS.iter().any(|(name, _)| *name == language) {
+                return Err(Error::msg(format!(
+                    "Unsupported locale {:?}; available locales: {}",
+                    locale,
+                    CATALOGS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )));
+            }
+            language
+        }
+        None => env_locale(|var| std::env::var(var).ok())
+            .map(|locale| language(&locale))
+            .unwrap_or_default(),
+    };
+
+    if let Some((language, contents)) = CATALOGS.iter().find(|(name, _)| *name == language) {
+        let _ = SELECTED.set(Catalog::parse(contents, language)?);
+    }
+
+    Ok(())
+}
+
+/// Returns the message `id` in the selected locale, or in English if it is not translated;
+pub fn tr(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let english = ENGLISH.get_or_init(|| {
+        let (language, contents) = CATALOGS[0];
+        Catalog::parse(contents, language).unwrap_or_default()
+    });
+
+    SELECTED
+        .get()
+        .and_then(|catalog| catalog.format(id, args))
+        .or_else(|| english.format(id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Returns the locale of the environment, i.e. the first set of `LOCALE_VARS`;
+fn env_locale(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    LOCALE_VARS
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|value| !value.is_empty())
+}
+
+/// Returns the language of a locale, e.g. `de` for `de_DE.UTF-8`;
+fn language(locale: &str) -> String {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{env_locale, language, variable, Catalog, CATALOGS};
+
+    /// Returns the variables of the placeholders of a message, e.g. `path` of `at { $path }`;
+    fn variables(message: &str) -> impl Iterator<Item = &str> {
+        message.split('{').skip(1).filter_map(|part| {
+            part.split_once('}')
+                .and_then(|(inside, _)| variable(inside))
+        })
+    }
+
+    #[test]
+    fn test_catalog() -> Result<(), anyhow::Error> {
+        let catalog = Catalog::parse(
+            "# comment\nup-to-date = { $target }: up to date\nmissing = no file\n    at {$path}\nliteral = {braces} { $unset }\n",
+            "en",
+        )?;
+        assert_eq!(
+            catalog.format("up-to-date", &[("target", &"master")]),
+            Some(String::from("master: up to date"))
+        );
+        assert_eq!(
+            catalog.format("missing", &[("path", &"Cargo.toml")]),
+            Some(String::from("no file\nat Cargo.toml"))
+        );
+        assert_eq!(
+            catalog.format("literal", &[("path", &"Cargo.toml")]),
+            Some(String::from("{braces} { $unset }"))
+        );
+        assert_eq!(catalog.format("unknown", &[]), None);
+        assert!(Catalog::parse("not a message\n", "en").is_err());
+
+        // Translations only use ids and placeholders of the English messages;
+        let (_, english) = CATALOGS[0];
+        let english = Catalog::parse(english, "en")?;
+        for (language, contents) in CATALOGS.iter() {
+            for (id, message) in Catalog::parse(contents, language)?.messages.iter() {
+                let original = english.messages.get(id);
+                assert!(original.is_some(), "{} is not an English message", id);
+                for name in variables(message) {
+                    assert!(
+                        original
+                            .is_some_and(|original| variables(original).any(|known| known == name)),
+                        "{} of {} uses an unknown placeholder {}",
+                        id,
+                        language,
+                        name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(language("de_DE.UTF-8"), "de");
+        assert_eq!(language("en-US"), "en");
+        assert_eq!(language("C"), "c");
+
+        let env = |vars: &[(&str, &str)]| {
+            let vars = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<(String, String)>>();
+            env_locale(move |name| {
+                vars.iter()
+                    .find(|(var, _)| var == name)
+                    .map(|(_, value)| value.clone())
+            })
+        };
+        assert_eq!(
+            env(&[("LANG", "en_US.UTF-8"), ("LC_ALL", "de_DE.UTF-8")]).as_deref(),
+            Some("de_DE.UTF-8")
+        );
+        assert_eq!(
+            env(&[("LANG", "de_AT"), ("LC_ALL", "")]).as_deref(),
+            Some("de_AT")
+        );
+        assert_eq!(env(&[]), None);
+    }
+}
@@ -0,0 +1,120 @@
+use git2::Repository;
+use std::collections::BTreeMap;
+
+/// Returns the default branch of the remote, i.e. the branch its `HEAD` points to, falling back
+/// to `main` or `master` if the remote-tracking branch exists;
+pub fn default_branch(repo: &Repository, remote: &str) -> Option<String> {
+    let head = format!("refs/remotes/{}/HEAD", remote);
+    let prefix = format!("refs/remotes/{}/", remote);
+
+    if let Ok(reference) = repo.find_reference(&head) {
+        if let Some(target) = reference.symbolic_target() {
+            return target.strip_prefix(&prefix).map(String::from);
+        }
+    }
+
+    ["main", "master"]
+        .iter()
+        .find(|branch| {
+            repo.find_reference(&format!("{}{}", prefix, branch))
+                .is_ok()
+        })
+        .map(|branch| branch.to_string())
+}
+
+/// Returns the patterns of the tags in the repository, most common first, e.g. `v*` for
+/// `v1.2.3` or `foo-v*` for `foo-v0.1.0`;
+pub fn tag_patterns(repo: &Repository) -> Vec<String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    if let Ok(tags) = repo.tag_names(None) {
+        for tag in tags.iter().flatten() {
+            if let Some(pattern) = tag_pattern(tag) {
+                *counts.entry(pattern).or_default() += 1;
+            }
+        }
+    }
+
+    let mut patterns = counts.into_iter().collect::<Vec<(String, usize)>>();
+    patterns.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    patterns.into_iter().map(|(pattern, _)| pattern).collect()
+}
+
+/// Replace the trailing version of a tag with `*`, or None if it does not end in a version;
+pub fn tag_pattern(tag: &str) -> Option<String> {
+    let prefix = tag.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    if prefix.len() == tag.len() || !tag[prefix.len()..].contains('.') {
+        return None;
+    }
+
+    Some(format!("{}*", prefix))
+}
+
+/// Starter `.cvm.toml` listing the detected settings, with the optional tables commented out;
+pub fn config_template(branch: &str, remote: &str, members: &[String], tags: &[String]) -> String {
+    let mut config = String::from(
+        "# Generated by `cargo cvm init`; see https://github.com/infinyon/cargo-cvm#configuration\n\n",
+    );
+
+    config.push_str("# Branch and remote that versions are compared against by default;\n");
+    config.push_str(&format!(
+        "branch = \"{}\"\nremote = \"{}\"\n\n",
+        branch, remote
+    ));
+
+    config.push_str(&format!("# Checked members: {}\n", members.join(", ")));
+    config.push_str("nested-packages = \"warn\"\n\n");
+
+    if !tags.is_empty() {
+        config.push_str(&format!(
+            "# Release tags found in history match: {}\n\n",
+            tags.join(", ")
+        ));
+    }
+
+    config.push_str("# Minimum bump level required by changes to matching files;\n");
+    config.push_str("# [severity]\n# \"src/api/**\" = \"major\"\n\n");
+    config.push_str("# Highest bump level allowed on matching target branches;\n");
+    config.push_str("# [release-train]\n# \"release/*\" = \"patch\"\n");
+
+    config
+}
+
+/// GitHub Actions workflow checking versions on pull requests into `branch`;
+pub fn workflow_template(branch: &str) -> String {
+    format!(
+        r#"name: cvm
+
+on:
+  pull_request:
+    branches: [{branch}]
+
+jobs:
+  versions:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          fetch-depth: 0
+      - run: cargo install cargo-cvm
+      - run: cargo cvm check --branch {branch}
+"#,
+        branch = branch
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_tag_pattern() {
+        assert_eq!(super::tag_pattern("v1.2.3"), Some(String::from("v*")));
+        assert_eq!(
+            super::tag_pattern("foo-v0.1.0"),
+            Some(String::from("foo-v*"))
+        );
+        assert_eq!(super::tag_pattern("1.0.0"), Some(String::from("*")));
+        assert_eq!(super::tag_pattern("release"), None);
+        assert_eq!(super::tag_pattern("build-42"), None);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Number of jobs run at once if `--jobs` is not given, i.e. the available parallelism;
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Run `f` on every item on up to `jobs` threads, returning the results in the order of the
+/// items; network-bound work, e.g. fetches, then takes about as long as the slowest item rather
+/// than the sum of all items;
+pub fn run<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let item = match items.get(i) {
+                    Some(item) => item,
+                    None => break,
+                };
+
+                let result = f(item);
+                if let Ok(mut results) = results.lock() {
+                    results[i] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_run_keeps_order() {
+        let items = (0..20).collect::<Vec<u64>>();
+        let started = Instant::now();
+
+        let results = run(&items, 10, |n| {
+            std::thread::sleep(Duration::from_millis(50));
+            n * 2
+        });
+
+        assert_eq!(results, items.iter().map(|n| n * 2).collect::<Vec<u64>>());
+        // Twenty sleeps on ten threads take two rounds, not twenty;
+        assert!(started.elapsed() < Duration::from_millis(50 * 10));
+        assert_eq!(run(&items, 1, |n| *n), items);
+    }
+}
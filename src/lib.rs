@@ -0,0 +1,4 @@
+//! Types of the machine-readable output of `cargo cvm`, for tools parsing it, e.g.
+//! `cargo cvm check --output json`;
+
+pub mod schema;
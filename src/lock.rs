@@ -0,0 +1,83 @@
+use anyhow::Error;
+use std::fs::{read_to_string, remove_file, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Name of the advisory lock file created inside the `.git` directory;
+pub const LOCK_FILE: &str = "cvm.lock";
+
+/// Advisory lock held while manifests are edited, staged, or committed, so
+/// concurrent runs against the same repository do not race on the git index;
+///
+/// The lock is released when the value is dropped.
+#[derive(Debug)]
+pub struct RepoLock {
+    path: PathBuf,
+}
+
+impl RepoLock {
+    /// Acquire the lock in `git_dir`, waiting up to `timeout` for another
+    /// process to release it;
+    pub fn acquire(git_dir: &Path, timeout: Duration) -> Result<Self, Error> {
+        let path = git_dir.join(LOCK_FILE);
+        let started = Instant::now();
+        let mut notified = false;
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if started.elapsed() >= timeout {
+                        let owner = read_to_string(&path).unwrap_or_default();
+                        return Err(Error::msg(format!(
+                            "Timed out after {}s waiting for lock file {:?} held by process {}; if no other `cargo cvm` is running, remove the file and try again.",
+                            timeout.as_secs(),
+                            path.display(),
+                            owner.trim()
+                        )));
+                    }
+
+                    if !notified {
+                        eprintln!("Blocking waiting for file lock on {:?}", path.display());
+                        notified = true;
+                    }
+
+                    sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        // Ignore errors; the lock file may have been removed manually;
+        let _ = remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepoLock;
+    use crate::testing::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lock_is_exclusive() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new("lock")?;
+
+        let lock = RepoLock::acquire(dir.path(), Duration::from_secs(1))?;
+        assert!(RepoLock::acquire(dir.path(), Duration::from_millis(200)).is_err());
+
+        drop(lock);
+        assert!(RepoLock::acquire(dir.path(), Duration::from_millis(200)).is_ok());
+
+        Ok(())
+    }
+}
@@ -1,8 +1,60 @@
+mod artifacts;
+mod baseline;
+mod cargo_config;
+mod ci;
+mod commits;
+mod config;
+mod deps;
+mod doctor;
+mod due;
+mod fetch;
+mod fields;
+mod flags;
+mod fs;
+mod git;
+mod git_cli;
+#[cfg(feature = "gix")]
+mod gix_backend;
+mod graph;
+mod history;
+mod hook;
+mod http;
+mod i18n;
+mod init;
+mod jobs;
+mod lock;
 mod manager;
+mod metrics;
+mod notes;
+mod notify;
+mod owners;
+mod path_trie;
+mod plan;
+mod registry;
+mod report;
+mod ssh_config;
+mod state;
+mod tags;
+#[cfg(test)]
+mod testing;
+mod trace;
+mod update;
+mod warning;
+mod workspace;
 
 use anyhow::Error;
-use clap::{crate_authors, crate_description, crate_version, App, Arg, SubCommand};
+use clap::{
+    crate_authors, crate_description, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
+use due::Thresholds;
+use flags::Invocation;
+use i18n::tr;
 use manager::Manager;
+use std::convert::TryInto;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use warning::StrictWarning;
 
 fn main() -> Result<(), Error> {
     env_logger::init();
@@ -13,75 +65,724 @@ fn main() -> Result<(), Error> {
         .about(crate_description!())
         .subcommand(
             SubCommand::with_name("cvm")
-                .arg(
-                    Arg::with_name("semver")
-                        .short("s")
-                        .long("semver")
-                        .help("Type of Semantic Versioning; i.e. `minor`, `major`, or `patch`. Defaults to `minor`")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("branch")
-                        .short("b")
-                        .long("branch")
-                        .help("Which branch to compare to the current. Will attempt to find the version in the target branch and check if the version has been bumped or not.")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("remote")
-                        .short("r")
-                        .long("remote")
-                        .help("Determine which remote to use for the target branch. Defaults to `origin`.")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("ssh-key")
-                        .short("k")
-                        .long("ssh-key")
-                        .help("Provide the path to your ssh private key for authenticating against remote git hosts. Defaults to $HOME/.ssh/id_rsa")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("fix")
-                        .short("f")
-                        .long("fix")
-                        .takes_value(false)
-                        .help("Automatically fix the version if it is outdated. By default, this will bump the minor version, unless otherwise specified by the --semver option"))
-                .arg(
-                    Arg::with_name("force")
-                        .short("F")
-                        .long("force")
-                        .takes_value(false)
-                        .help("Force a version bump. Can use be used with --semver option to determine version type"),
-                )
-                .arg(
-                    Arg::with_name("check")
-                        .short("x")
-                        .long("check")
-                        .takes_value(false)
-                        .help("Panic if the versions are out-of-date"),
-                )
-                .arg(
-                    Arg::with_name("warn")
-                        .short("w")
-                        .long("warn")
-                        .takes_value(false)
-                        .help("Warn if the versions are out-of-date"),
-                )
-                .arg(
-                    Arg::with_name("commit")
-                        .short("c")
-                        .long("commit")
-                        .takes_value(false)
-                        .help("git commit updated version(s), otherwise will only add the files to git. Can only be used with --fix or --force flags"),
+                .args(&options())
+                .args(&report_options())
+                .args(&bump_options())
+                .args(&fix_options())
+                .args(&deprecated_flags())
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Fail if the version of a changed crate has not been bumped compared to the target branch")
+                        .args(&options())
+                        .args(&report_options()),
+                )
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("List the crates whose version has not been bumped since they changed, without failing")
+                        .args(&options())
+                        .args(&report_options()),
+                )
+                .subcommand(
+                    SubCommand::with_name("fix")
+                        .about("Bump the version of each changed crate that has not been bumped. By default, this bumps the minor version, unless otherwise specified by the --semver option")
+                        .args(&options())
+                        .args(&bump_options())
+                        .args(&fix_options())
+                        .arg(
+                            Arg::with_name("force")
+                                .short("F")
+                                .long("force")
+                                .takes_value(false)
+                                .help("Bump the version of each changed crate even if it was already bumped. Can be used with --semver option to determine version type"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("release")
+                        .about("Bump the version of each changed crate, even if it was already bumped, and commit the bumps")
+                        .args(&options())
+                        .args(&bump_options())
+                        .arg(
+                            Arg::with_name("commit-branch")
+                                .long("commit-branch")
+                                .takes_value(true)
+                                .help("Branch to commit onto when HEAD is detached, e.g. in CI; it is created at the checked-out commit if missing. Without it, the commit fails on a detached HEAD"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("plan")
+                        .about("Write the version bumps that `cargo cvm fix` would apply to a plan file, to be reviewed before `cargo cvm apply`")
+                        .arg(
+                            Arg::with_name("out")
+                                .short("o")
+                                .long("out")
+                                .takes_value(true)
+                                .help("Path of the plan file. Defaults to `plan.json` in the artifact directory, `target/cvm` unless relocated"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("baseline")
+                        .about("Record the current outdated crates and release train violations in `.cvm-baseline.json`, so that `cargo cvm check` only fails on new ones"),
+                )
+                .subcommand(
+                    SubCommand::with_name("apply")
+                        .about("Apply the version bumps of a plan file written by `cargo cvm plan`")
+                        .arg(
+                            Arg::with_name("plan")
+                                .required(true)
+                                .help("Path of the plan file"),
+                        )
+                        .arg(
+                            Arg::with_name("commit")
+                                .short("c")
+                                .long("commit")
+                                .takes_value(false)
+                                .help("git commit the updated version(s), otherwise only add the files to git"),
+                        )
+                        .arg(
+                            Arg::with_name("allow-dirty")
+                                .long("allow-dirty")
+                                .takes_value(false)
+                                .help("Apply the bumps although tracked files have uncommitted changes"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("undo")
+                        .about("Revert the version edits of the most recent `cargo cvm fix` or `cargo cvm release` run and unstage them"),
+                )
+                .subcommand(
+                    SubCommand::with_name("msrv")
+                        .about("Show the `rust-version` of every member and check that they agree, or set it across the workspace")
+                        .arg(
+                            Arg::with_name("set")
+                                .long("set")
+                                .help("Minimum supported Rust version to set, e.g. `1.56`")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("commit")
+                                .short("c")
+                                .long("commit")
+                                .takes_value(false)
+                                .requires("set")
+                                .help("git commit the updated manifests, otherwise will only add the files to git"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("edition")
+                        .about("Show the `edition` of every member and report members that differ, or set it across the workspace")
+                        .arg(
+                            Arg::with_name("set")
+                                .long("set")
+                                .help("Edition to set")
+                                .takes_value(true)
+                                .possible_values(&["2015", "2018", "2021", "2024"]),
+                        )
+                        .arg(
+                            Arg::with_name("commit")
+                                .short("c")
+                                .long("commit")
+                                .takes_value(false)
+                                .requires("set")
+                                .help("git commit the updated manifests, otherwise will only add the files to git"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("align-deps")
+                        .about("Report external dependencies that members require at different versions")
+                        .arg(
+                            Arg::with_name("fix")
+                                .short("f")
+                                .long("fix")
+                                .takes_value(false)
+                                .help("Align the versions to the version in [workspace.dependencies], or else the highest required version"),
+                        )
+                        .arg(
+                            Arg::with_name("commit")
+                                .short("c")
+                                .long("commit")
+                                .takes_value(false)
+                                .requires("fix")
+                                .help("git commit the updated manifests, otherwise will only add the files to git"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("hoist-deps")
+                        .about("Move external dependencies shared by several members into [workspace.dependencies]")
+                        .arg(
+                            Arg::with_name("min-members")
+                                .long("min-members")
+                                .help("Only hoist dependencies declared by at least this many members. Defaults to 2")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("commit")
+                                .short("c")
+                                .long("commit")
+                                .takes_value(false)
+                                .help("git commit the updated manifests, otherwise will only add the files to git"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("notes")
+                        .about("Render release notes for the crates bumped by the most recent `cargo cvm fix` or `cargo cvm release` run")
+                        .arg(
+                            Arg::with_name("template")
+                                .long("template")
+                                .help("Path to a Tera template to render the notes with, instead of the default Markdown")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("out")
+                                .long("out")
+                                .help("Write the notes to this file instead of stdout; without a file, to `notes.md` in the artifact directory, `target/cvm` unless relocated")
+                                .takes_value(true)
+                                .min_values(0),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("publish")
+                        .about("Publish the crates bumped by the most recent `cargo cvm fix` or `cargo cvm release` run, dependencies first, waiting for the registry index to list each before publishing its dependents")
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .takes_value(false)
+                                .help("Run `cargo publish --dry-run` for each crate, without waiting for the registry"),
+                        )
+                        .arg(
+                            Arg::with_name("index")
+                                .long("index")
+                                .takes_value(true)
+                                .help("Sparse index of the registry. Defaults to `https://index.crates.io/`"),
+                        )
+                        .arg(
+                            Arg::with_name("index-timeout")
+                                .long("index-timeout")
+                                .takes_value(true)
+                                .help("Seconds to wait for a published crate to appear in the registry index. Defaults to 300"),
+                        )
+                        .arg(
+                            Arg::with_name("poll-interval")
+                                .long("poll-interval")
+                                .takes_value(true)
+                                .help("Seconds between queries of the registry index. Defaults to 5"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("self-update")
+                        .about("Replace this binary with the latest GitHub release of cargo-cvm, checking the integrity of the download against its published checksum")
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .takes_value(false)
+                                .help("Only report whether a newer version is available"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("hook")
+                        .about("Run as a git hook")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("pre-receive")
+                                .about("Read the pushed refs from stdin, as passed to a `pre-receive` hook, and reject the push if crate sources changed without version bumps; each branch is checked against its previous tip, and a new branch against --branch"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("doctor")
+                        .about("Check the repository, manifests, configuration, target remote and branches, credentials, and commit signature, with hints for failed checks"),
+                )
+                .subcommand(
+                    SubCommand::with_name("init")
+                        .about("Write a starter .cvm.toml based on the workspace, its remotes, and its tags")
+                        .arg(
+                            Arg::with_name("github-actions")
+                                .long("github-actions")
+                                .takes_value(false)
+                                .help("Also write a GitHub Actions workflow running `cargo cvm check` on pull requests"),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .long("force")
+                                .takes_value(false)
+                                .help("Overwrite existing files"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("graph")
+                        .about("Print the workspace members, their versions, and their dependencies on each other as a graph, highlighting outdated crates and the crates depending on them")
+                        .arg(
+                            Arg::with_name("format")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&["dot", "mermaid"])
+                                .help("Graph format; i.e. `dot` for Graphviz, or `mermaid`. Defaults to `dot`"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("last-release")
+                        .about("Show the last released version of a crate, from its release tags, and the number of commits that changed it since")
+                        .arg(
+                            Arg::with_name("crate")
+                                .required(true)
+                                .help("Name of the crate, or the path of its directory, e.g. `crates/foo-rs`")
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("due")
+                        .about("List the crates whose changes since their last release tag exceed the thresholds, the longest waiting first, e.g. to plan the week's releases")
+                        .arg(
+                            Arg::with_name("max-age")
+                                .long("max-age")
+                                .takes_value(true)
+                                .help("List crates whose oldest unreleased commit is at least this old, e.g. `30d`; i.e. a number followed by s, m, h, d, or w"),
+                        )
+                        .arg(
+                            Arg::with_name("min-commits")
+                                .long("min-commits")
+                                .takes_value(true)
+                                .help("List crates with at least this many unreleased commits. Without either threshold, every crate with unreleased commits is listed"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("history")
+                        .about("Show the audit log of version changes recorded in .cvm/history.jsonl")
+                        .arg(
+                            Arg::with_name("crate")
+                                .help("Only show the changes of this crate, by name or by the path of its directory")
+                                .index(1),
+                        ),
                 ),
         )
         .get_matches()
         .subcommand_matches("cvm")
     {
-        let manager = Manager::new(args)?;
-        manager.check_workspaces()?;
+        // Updating does not need a repository;
+        if let ("self-update", Some(update)) = args.subcommand() {
+            return update::self_update(update.is_present("dry-run"));
+        }
+
+        let result = run(args);
+        trace::finish();
+
+        if let Err(e) = result {
+            if e.downcast_ref::<StrictWarning>().is_some() {
+                eprintln!("{}", tr("error", &[("message", &e)]));
+                std::process::exit(warning::STRICT_EXIT_CODE)
+            }
+            return Err(e);
+        }
     };
 
     Ok(())
 }
+
+/// Options of `cvm` shared by its verbs, e.g. `cargo cvm check`;
+fn options<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("semver")
+            .short("s")
+            .long("semver")
+            .help("Type of Semantic Versioning; i.e. `minor`, `major`, or `patch`. Defaults to `minor`")
+            .takes_value(true),
+        Arg::with_name("strict")
+            .long("strict")
+            .takes_value(false)
+            .help("Treat warnings as errors, exiting with code 4, and when checking reject version bumps that skip versions, e.g. `1.2.3 → 1.5.0`"),
+        Arg::with_name("bump-from-commits")
+            .long("bump-from-commits")
+            .takes_value(false)
+            .help("Bump by the level of the conventional commit messages with --fix, too, i.e. apply the version that `cargo cvm check` suggests"),
+        Arg::with_name("allow-version-jump")
+            .long("allow-version-jump")
+            .takes_value(false)
+            .help("Allow version bumps that skip versions with --strict when checking, e.g. for an intentional jump to `2.0.0`"),
+        Arg::with_name("required-bump")
+            .long("required-bump")
+            .env("CVM_REQUIRED_BUMP")
+            .help("Minimum level of the version bump of each changed crate, e.g. set from a pull request label; i.e. `minor`, `major`, or `patch`. Crates bumped by less are reported as outdated")
+            .takes_value(true),
+        Arg::with_name("branch")
+            .short("b")
+            .long("branch")
+            .help("Which branch to compare to the current. Will attempt to find the version in the target branch and check if the version has been bumped or not. Can be repeated to check against several branches, e.g. maintenance branches")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("remote")
+            .short("r")
+            .long("remote")
+            .help("Determine which remote to use for the target branch. Defaults to `origin`.")
+            .takes_value(true),
+        Arg::with_name("ssh-key")
+            .short("k")
+            .long("ssh-key")
+            .help("Provide the path to your ssh private key for authenticating against remote git hosts. Can be repeated to try several keys in order. Defaults to the `IdentityFile` entries of $HOME/.ssh/config for the remote's host, then $HOME/.ssh/id_rsa")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("anonymous")
+            .long("anonymous")
+            .takes_value(false)
+            .conflicts_with("ssh-key")
+            .help("Fetch the target branch without credentials, rewriting SSH remote URLs to HTTPS. Use for read-only checks of public repositories, e.g. in pull requests from forks"),
+        Arg::with_name("non-interactive")
+            .long("non-interactive")
+            .takes_value(false)
+            .help("Never prompt, e.g. for credentials, passphrases, or unknown SSH host keys. A fetch that would need a prompt fails with exit code 3 instead, so CI jobs do not hang"),
+        Arg::with_name("proxy")
+            .long("proxy")
+            .env("CVM_PROXY")
+            .help("HTTP(S) proxy used to fetch the target branch, e.g. `http://proxy.example.com:8080`. Defaults to the proxy configured by git's `http.proxy` setting or the `https_proxy` environment variable")
+            .takes_value(true),
+        Arg::with_name("ca-bundle")
+            .long("ca-bundle")
+            .help("Path to a CA bundle to trust for HTTPS remotes, in addition to the system certificates, e.g. for self-hosted git servers with an internal CA")
+            .takes_value(true),
+        Arg::with_name("ssh-host-key-policy")
+            .long("ssh-host-key-policy")
+            .help("How to verify SSH host keys of the remote; i.e. `default` to leave it to libgit2, `pinned` to only accept --ssh-host-fingerprint keys, or `accept-new` to record keys on first use and reject changed keys. Defaults to `pinned` if fingerprints are given, otherwise `default`")
+            .takes_value(true)
+            .possible_values(&["default", "pinned", "accept-new"]),
+        Arg::with_name("ssh-host-fingerprint")
+            .long("ssh-host-fingerprint")
+            .help("Trusted SHA256 fingerprint of the remote's SSH host key, as printed by `ssh-keygen -lf`, e.g. `SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s`. Can be repeated")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("fetch-timeout")
+            .long("fetch-timeout")
+            .help("Seconds after which a fetch of the target branch is aborted and retried. Defaults to no timeout")
+            .takes_value(true),
+        Arg::with_name("fetch-retries")
+            .long("fetch-retries")
+            .help("Number of times a failed fetch is retried, with exponential backoff starting at 1 second. Defaults to 3")
+            .takes_value(true),
+        Arg::with_name("git-backend")
+            .long("git-backend")
+            .help("How to fetch and commit; i.e. `libgit2`, `cli` to run the `git` executable with your git configuration, credential helpers, and hooks, or `gix` to diff with gitoxide when built with the `gix` feature. Defaults to `libgit2`")
+            .takes_value(true)
+            // `gix` is listed without the feature, so that its error names the feature;
+            .possible_values(&["libgit2", "cli", "gix"]),
+        Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .help("Maximum number of network operations run at once, e.g. fetches of several --branch targets. Defaults to the number of CPUs")
+            .takes_value(true),
+        Arg::with_name("allow-equal")
+            .long("allow-equal")
+            .takes_value(false)
+            .help("Allow a bumped version to equal the version on the target branch if the crate's sources are unchanged compared to it, e.g. after the same bump landed there"),
+        Arg::with_name("unified")
+            .long("unified")
+            .takes_value(false)
+            .help("Version all members as one product: every member must have the version of the root package, which any member change must bump, and fixing bumps all members to the same new version"),
+        Arg::with_name("unified-crate")
+            .long("unified-crate")
+            .takes_value(true)
+            .value_name("CRATE")
+            .help("Take the unified version from this crate instead of the root package, e.g. in a virtual workspace; implies --unified"),
+        Arg::with_name("tip")
+            .long("tip")
+            .takes_value(false)
+            .help("Compare against the tip of the target branch instead of its merge-base with HEAD"),
+        Arg::with_name("since")
+            .long("since")
+            .help("Compare against an earlier revision of the current branch instead of a target branch, e.g. `HEAD~3`, to check whether the commits since then changed crates without bumping them")
+            .takes_value(true)
+            .conflicts_with_all(&["branch", "remote", "tip"]),
+        Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .help("Output format of the report; i.e. `human`, `json`, `gitlab` for a GitLab Code Quality report, or `junit` for JUnit XML. Defaults to `human`")
+            .takes_value(true)
+            .possible_values(&["human", "json", "gitlab", "junit"]),
+        Arg::with_name("locale")
+            .long("locale")
+            .help("Language of the messages, e.g. `de`; defaults to the locale of LC_ALL, LC_MESSAGES, or LANG, and to English if it is not translated")
+            .takes_value(true),
+        Arg::with_name("trace-file")
+            .long("trace-file")
+            .help("Write the time spent fetching, diffing, parsing manifests, editing, and committing as a Chrome trace, e.g. to open in Perfetto, to `trace.json` in the artifact directory, `target/cvm` unless relocated, or to the file given as `--trace-file=<file>`")
+            .takes_value(true)
+            .min_values(0)
+            .require_equals(true),
+        Arg::with_name("show-ok")
+            .long("show-ok")
+            .takes_value(false)
+            .help("List the up-to-date crates instead of only counting them"),
+        Arg::with_name("template")
+            .long("template")
+            .help("Path to a Tera template to render the report with, e.g. a Slack payload or wiki table, instead of --output")
+            .takes_value(true)
+            .conflicts_with("output"),
+        Arg::with_name("notify-webhook")
+            .long("notify-webhook")
+            .env("CVM_NOTIFY_WEBHOOK")
+            .help("POST a JSON summary of outdated crates and applied bumps to this webhook URL, e.g. a Slack incoming webhook")
+            .takes_value(true),
+        Arg::with_name("metrics")
+            .long("metrics")
+            .help("Write Prometheus metrics of the run to this file, e.g. for the node_exporter textfile collector")
+            .takes_value(true),
+        Arg::with_name("lock-timeout")
+            .long("lock-timeout")
+            .help("Seconds to wait for another `cargo cvm` run to release the repository lock before failing. Defaults to 30")
+            .takes_value(true),
+    ]
+}
+
+/// Options of the runs that bump versions, i.e. `cargo cvm fix` and `cargo cvm release`;
+fn bump_options<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("fix-path-deps")
+            .long("fix-path-deps")
+            .takes_value(false)
+            .help("Add the current version of the dependency to path dependencies without one, which `cargo publish` rejects"),
+        Arg::with_name("preflight")
+            .long("preflight")
+            .takes_value(true)
+            .possible_values(&["no-verify", "verify"])
+            .help("Run `cargo package`, with `--no-verify` or building the package, for each bumped crate before the bumps are committed. Defaults to `preflight` in .cvm.toml, else none"),
+        Arg::with_name("allow-dirty")
+            .long("allow-dirty")
+            .takes_value(false)
+            .help("Bump versions although tracked files have uncommitted changes, which would otherwise be refused so that they are not mixed with the bumps"),
+    ]
+}
+
+/// Options of the runs that only report, i.e. `cargo cvm check` and `cargo cvm status`;
+fn report_options<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("head")
+            .long("head")
+            .takes_value(true)
+            .conflicts_with_all(&["fix", "force", "commit", "fix-path-deps"])
+            .help("Evaluate this revision instead of HEAD, e.g. `feature/foo`, without checking it out. Members are listed from the checkout, but their manifests and sources are read from the revision"),
+    ]
+}
+
+/// Options of `cargo cvm fix` that `cargo cvm release` implies or rejects, also given to `cvm`
+/// with the deprecated mode flags;
+fn fix_options<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("commit")
+            .short("c")
+            .long("commit")
+            .takes_value(false)
+            .help("git commit updated version(s), otherwise will only add the files to git"),
+        Arg::with_name("commit-branch")
+            .long("commit-branch")
+            .takes_value(true)
+            .requires("commit")
+            .help("Branch to commit onto when HEAD is detached, e.g. in CI; it is created at the checked-out commit if missing. Without it, --commit fails on a detached HEAD"),
+        Arg::with_name("dry-run")
+            .long("dry-run")
+            .takes_value(false)
+            .conflicts_with("commit")
+            .help("Print the manifest lines that the bumps would change instead of writing them; nothing is staged, recorded, or committed"),
+    ]
+}
+
+/// Mode flags replaced by the verbs, kept as hidden aliases for one release;
+fn deprecated_flags<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("fix")
+            .short("f")
+            .long("fix")
+            .takes_value(false)
+            .hidden(true)
+            .help("Deprecated alias of `cargo cvm fix`"),
+        Arg::with_name("force")
+            .short("F")
+            .long("force")
+            .takes_value(false)
+            .hidden(true)
+            .help("Deprecated alias of `cargo cvm fix --force`"),
+        Arg::with_name("check")
+            .short("x")
+            .long("check")
+            .takes_value(false)
+            .hidden(true)
+            .conflicts_with_all(&["fix", "warn"])
+            .help("Deprecated alias of `cargo cvm check`"),
+        Arg::with_name("warn")
+            .short("w")
+            .long("warn")
+            .takes_value(false)
+            .hidden(true)
+            .help("Deprecated alias of `cargo cvm status`"),
+    ]
+}
+
+/// Run the `cvm` subcommand given by `args`;
+fn run(args: &ArgMatches) -> Result<(), Error> {
+    let invocation = Invocation::new(args)?;
+    i18n::init(invocation.value_of("locale"))?;
+    if invocation.is_present("trace-file") {
+        let path = match invocation.value_of("trace-file") {
+            Some(path) => PathBuf::from(path),
+            None => default_path("trace.json")?,
+        };
+        trace::init(&path)?;
+    }
+    let _span = trace::span("run", args.subcommand_name().unwrap_or("check"));
+
+    // Not a warning of `--strict`, so that CI jobs using the old flags keep passing;
+    for note in flags::deprecated(args) {
+        eprintln!("{}", tr("warning", &[("message", &note)]));
+    }
+
+    for warning in flags::incoherent(|flag| invocation.is_present(flag)) {
+        warning::warn(invocation.is_present("strict"), warning)?;
+    }
+
+    if let ("doctor", Some(_)) = args.subcommand() {
+        let mut checks = doctor::local_checks(&std::env::current_dir()?);
+
+        // Only check the remote once the repository and manifests are usable;
+        if checks.iter().all(|check| check.passed()) {
+            checks.extend(Manager::new(&invocation)?.diagnose());
+        }
+
+        for check in checks.iter() {
+            println!("{}", check);
+        }
+
+        if !checks.iter().all(|check| check.passed()) {
+            std::process::exit(1)
+        }
+
+        return Ok(());
+    }
+
+    if let ("hook", Some(hook)) = args.subcommand() {
+        if let ("pre-receive", Some(_)) = hook.subcommand() {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+
+            // A failed check exits unsuccessfully, which rejects the whole push;
+            for update in hook::parse_updates(&input)?
+                .iter()
+                .filter(|update| update.is_branch_push())
+            {
+                let manager = Manager::for_push(&invocation, update)?;
+                // Other branches are compared against the target branches, e.g. unless the push
+                // creates them;
+                if manager.compares_to_target()
+                    && manager
+                        .target_branches()
+                        .iter()
+                        .any(|branch| manager.get_target_commit(branch).is_err())
+                {
+                    println!("{}", tr("push-skipping", &[("ref", &update.refname)]));
+                    continue;
+                }
+
+                println!("{}", tr("push-checking", &[("ref", &update.refname)]));
+                manager.check_workspaces()?;
+            }
+        }
+        return Ok(());
+    }
+
+    let manager = Manager::new(&invocation)?;
+
+    match args.subcommand() {
+        ("undo", Some(_)) => manager.undo()?,
+        ("plan", Some(_)) | ("baseline", Some(_)) => manager.check_workspaces()?,
+        ("apply", Some(apply)) => manager.apply_plan(
+            Path::new(apply.value_of("plan").unwrap_or_default()),
+            apply.is_present("commit"),
+            apply.is_present("allow-dirty"),
+        )?,
+        ("history", Some(history)) => manager.print_history(history.value_of("crate"))?,
+        ("due", Some(due)) => manager.print_due(&Thresholds {
+            max_age: due.value_of("max-age").map(due::parse_age).transpose()?,
+            min_commits: due.value_of("min-commits").map(str::parse).transpose()?,
+        })?,
+        ("last-release", Some(last)) => {
+            manager.print_last_release(last.value_of("crate").unwrap_or_default())?
+        }
+        ("publish", Some(publish)) => manager.publish(
+            publish.value_of("index"),
+            Duration::from_secs(publish.value_of("index-timeout").unwrap_or("300").parse()?),
+            Duration::from_secs(publish.value_of("poll-interval").unwrap_or("5").parse()?),
+            publish.is_present("dry-run"),
+        )?,
+        ("graph", Some(graph)) => {
+            let format = graph.value_of("format").unwrap_or("dot").try_into()?;
+            print!("{}", manager.dependency_graph()?.render(format));
+        }
+        ("align-deps", Some(align)) => {
+            manager.align_dependencies(align.is_present("fix"), align.is_present("commit"))?
+        }
+        ("hoist-deps", Some(hoist)) => manager.hoist_dependencies(
+            hoist.value_of("min-members").unwrap_or("2").parse()?,
+            hoist.is_present("commit"),
+        )?,
+        ("notes", Some(notes)) => {
+            let template = match notes.value_of("template") {
+                Some(path) => Some(std::fs::read_to_string(path)?),
+                None => None,
+            };
+            let rendered = manager.release_notes(template.as_deref())?;
+
+            match notes.value_of("out") {
+                Some(path) => std::fs::write(path, rendered)?,
+                None if notes.is_present("out") => {
+                    std::fs::write(default_path("notes.md")?, rendered)?
+                }
+                None => print!("{}", rendered),
+            }
+        }
+        ("init", Some(init)) => {
+            manager.init(init.is_present("github-actions"), init.is_present("force"))?
+        }
+        ("edition", Some(edition)) => manager.manage_package_field(
+            "edition",
+            edition.value_of("set"),
+            edition.is_present("commit"),
+            false,
+        )?,
+        ("msrv", Some(msrv)) => {
+            if let Some(version) = msrv.value_of("set") {
+                if !is_rust_version(version) {
+                    eprintln!(
+                        "Invalid Rust version {:?}; expected e.g. `1.56` or `1.56.1`",
+                        version
+                    );
+                    std::process::exit(1)
+                }
+            }
+            manager.manage_package_field(
+                "rust-version",
+                msrv.value_of("set"),
+                msrv.is_present("commit"),
+                true,
+            )?
+        }
+        _ => manager.check_workspaces()?,
+    }
+
+    Ok(())
+}
+
+/// Returns the path of the generated file `name` in the artifact directory of the workspace in
+/// the current directory, creating the directory; it is resolved without a `Manager`, so that
+/// the trace also covers setting one up;
+fn default_path(name: &str) -> Result<PathBuf, Error> {
+    let dir = std::env::current_dir()?;
+    let config = config::Config::load(&dir)?;
+    let artifact_dir = artifacts::artifact_dir(&dir, config.artifact_dir.as_deref());
+    std::fs::create_dir_all(&artifact_dir)?;
+    Ok(artifact_dir.join(name))
+}
+
+/// Returns true if `version` is a valid `rust-version`, i.e. two or three numeric components;
+fn is_rust_version(version: &str) -> bool {
+    let parts = version.split('.').collect::<Vec<&str>>();
+    (2..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
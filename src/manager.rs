@@ -1,19 +1,64 @@
+use crate::artifacts;
+use crate::baseline::{self, Baseline, BaselineEntry};
+use crate::cargo_config::CargoNetConfig;
+use crate::commits::{self, CommitFilter};
+use crate::config::{
+    self, AssetChanges, Assets, Changelog, Config, Hooks, NestedPackages, Preflight,
+};
+use crate::deps::{self, Requirement};
+use crate::doctor::Check;
+use crate::due::{self, DueCrate, Thresholds};
+use crate::fetch::{self, Fetcher, HostKeyPolicy, HostKeyVerifier};
+use crate::fields::{self, FieldValue};
+use crate::flags::Invocation;
+use crate::fs::{self, DiskFs, FileSystem, RecordingFs};
+use crate::git::{Change, CommitInfo, GitProvider, Libgit2Provider};
+use crate::git_cli::GitBackend;
+use crate::graph::{Graph, Node};
+use crate::history::{self, HistoryEntry};
+use crate::hook::RefUpdate;
+use crate::i18n::tr;
+use crate::init;
+use crate::jobs;
+use crate::lock::RepoLock;
+use crate::metrics;
+use crate::notes::{self, CommitNote, CrateNotes, ReleaseNotes};
+use crate::notify;
+use crate::owners::CodeOwners;
+use crate::path_trie::PathTrie;
+use crate::plan::Plan;
+use crate::registry::{SparseIndex, CRATES_IO_INDEX};
+use crate::report::{
+    Bump, FrozenCrate, OutputFormat, PathDependency, Report, TargetStatus, UnversionedCrate,
+};
+use crate::state::{FixState, ManifestEdit};
+use crate::tags::{self, LastRelease, ReleaseTag};
+use crate::trace;
+use crate::warning;
+use crate::workspace::WorkspaceModel;
 use anyhow::Error;
 use cargo_toml::Manifest;
-use clap::ArgMatches;
-use git2::{BranchType, Repository, Tree};
+use git2::{Oid, Repository};
+use globset::GlobMatcher;
+use ignore::gitignore::Gitignore;
+use serde::{Deserialize, Serialize, Serializer};
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::convert::TryInto;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::{TryFrom, TryInto};
 use std::fs::read_to_string;
-use std::fs::{remove_file, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Eq)]
 pub struct Version {
-    major: u8,
-    minor: u8,
-    patch: u8,
+    major: u64,
+    minor: u64,
+    patch: u64,
+    /// Everything after the patch version, i.e. a pre-release such as `-alpha.1`, build
+    /// metadata such as `+build.5`, or extra components such as `.4` with `lenient-versions`;
+    suffix: String,
 }
 
 impl Ord for Version {
@@ -24,7 +69,10 @@ impl Ord for Version {
 
         match major_ord {
             Ordering::Equal => match minor_ord {
-                Ordering::Equal => patch_ord,
+                Ordering::Equal => match patch_ord {
+                    Ordering::Equal => compare_suffixes(&self.suffix, &other.suffix),
+                    _ => patch_ord,
+                },
                 _ => minor_ord,
             },
             _ => major_ord,
@@ -32,6 +80,40 @@ impl Ord for Version {
     }
 }
 
+/// Compare the suffixes of versions with equal `major.minor.patch`: a pre-release precedes the
+/// release, which precedes extra components, and build metadata is ignored, as in SemVer;
+fn compare_suffixes(a: &str, b: &str) -> Ordering {
+    // Rank and dot-separated identifiers of the suffix;
+    let key = |suffix: &str| -> (u8, Vec<String>) {
+        let suffix = suffix.split('+').next().unwrap_or_default();
+        match suffix.chars().next() {
+            Some('-') => (0, suffix[1..].split('.').map(String::from).collect()),
+            Some('.') => (2, suffix[1..].split('.').map(String::from).collect()),
+            _ => (1, Vec::new()),
+        }
+    };
+    let ((a_rank, a_ids), (b_rank, b_ids)) = (key(a), key(b));
+
+    if a_rank != b_rank {
+        return a_rank.cmp(&b_rank);
+    }
+
+    // Numeric identifiers compare numerically and precede alphanumeric ones;
+    for (a, b) in a_ids.iter().zip(b_ids.iter()) {
+        let ord = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    a_ids.len().cmp(&b_ids.len())
+}
+
 impl PartialOrd for Version {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -40,12 +122,97 @@ impl PartialOrd for Version {
 
 impl PartialEq for Version {
     fn eq(&self, other: &Self) -> bool {
-        self.major == other.major && self.minor == other.minor && self.patch == other.patch
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl Version {
+    /// Parse `major.minor.patch` with an optional pre-release and build metadata, naming the
+    /// part that is invalid; with `lenient`, extra numeric components such as `1.2.3.4` are
+    /// accepted and kept, e.g. to compare versions that Cargo would not publish;
+    pub fn parse(version: &str, lenient: bool) -> Result<Version, Error> {
+        let invalid =
+            |reason: String| Error::msg(format!("Invalid version {:?}: {}", version, reason));
+
+        // Build metadata may contain `-`, so split it off first;
+        let (release, build) = match version.find('+') {
+            Some(i) => (&version[..i], Some(&version[i + 1..])),
+            None => (version, None),
+        };
+        let (core, pre) = match release.find('-') {
+            Some(i) => (&release[..i], Some(&release[i + 1..])),
+            None => (release, None),
+        };
+
+        let components = core.split('.').collect::<Vec<&str>>();
+        if components.len() < 3 {
+            return Err(invalid(format!(
+                "expected `major.minor.patch`, found {} component(s)",
+                components.len()
+            )));
+        }
+        if components.len() > 3 && !lenient {
+            return Err(invalid(format!(
+                "expected `major.minor.patch`, found {} components; set `lenient-versions = true` in .cvm.toml to compare such versions",
+                components.len()
+            )));
+        }
+
+        let names = ["major", "minor", "patch"];
+        let mut numbers = Vec::new();
+        for (i, component) in components.iter().enumerate() {
+            let name = names.get(i).copied().unwrap_or("extra");
+            if component.is_empty() {
+                return Err(invalid(format!("the {} component is empty", name)));
+            }
+            if !component.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid(format!(
+                    "the {} component {:?} is not a number",
+                    name, component
+                )));
+            }
+            if component.len() > 1 && component.starts_with('0') {
+                return Err(invalid(format!(
+                    "the {} component {:?} has a leading zero",
+                    name, component
+                )));
+            }
+            numbers.push(
+                component
+                    .parse::<u64>()
+                    .map_err(|e| invalid(format!("the {} component {}", name, e)))?,
+            );
+        }
+
+        for (part, identifiers) in [("pre-release", pre), ("build metadata", build)] {
+            if let Some(identifiers) = identifiers {
+                let valid = identifiers.split('.').all(|identifier| {
+                    !identifier.is_empty()
+                        && identifier
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+                });
+                if !valid {
+                    return Err(invalid(format!(
+                        "the {} {:?} must be dot-separated alphanumeric identifiers",
+                        part, identifiers
+                    )));
+                }
+            }
+        }
+
+        Ok(Version {
+            major: numbers[0],
+            minor: numbers[1],
+            patch: numbers[2],
+            suffix: version[components[..3].join(".").len()..].to_string(),
+        })
+    }
+
     pub fn bump(&mut self, semver: SemVer) {
+        // A bump releases the version, dropping any pre-release, build, or extra components;
+        self.suffix.clear();
+
         match semver {
             SemVer::Major => {
                 self.major += 1;
@@ -60,29 +227,563 @@ impl Version {
         };
     }
 
+    /// Raise `self` to `floor` if it is lower;
+    pub fn raise_to(&mut self, floor: Option<&Version>) {
+        if let Some(floor) = floor {
+            if *self < *floor {
+                *self = floor.clone();
+            }
+        }
+    }
+
+    /// Returns the next version of `self` at each level, i.e. the major, minor, and patch bumps;
+    pub fn next_versions(&self) -> Vec<Version> {
+        [SemVer::Major, SemVer::Minor, SemVer::Patch]
+            .iter()
+            .map(|semver| {
+                let mut next = self.clone();
+                next.bump(*semver);
+                next
+            })
+            .collect()
+    }
+
+    /// Returns the level of the bump from `self` to `newer`, or None if `newer` is not greater;
+    pub fn bump_level(&self, newer: &Version) -> Option<SemVer> {
+        if newer <= self {
+            None
+        } else if newer.major != self.major {
+            Some(SemVer::Major)
+        } else if newer.minor != self.minor {
+            Some(SemVer::Minor)
+        } else {
+            Some(SemVer::Patch)
+        }
+    }
+
     pub fn default() -> Self {
         Self {
             major: 0,
             minor: 1,
             patch: 0,
+            suffix: String::new(),
         }
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SemVer {
+    Patch,
     Minor,
     Major,
-    Patch,
+}
+
+/// A crate whose sources changed without a version bump;
+#[derive(Debug, Serialize)]
+pub struct OutdatedCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub version: Version,
+    pub cargo_toml: PathBuf,
+    /// Changed files that require a bump;
+    pub changed_files: Vec<ChangedFile>,
+    pub suggested_version: Version,
+    pub suggested_semver: SemVer,
+    /// Why the suggested level is required, e.g. `feat commits detected`;
+    pub reason: String,
+    /// Revision the crate was compared against, e.g. `origin/master`;
+    pub target: String,
+    /// Most recent commit that changed the files requiring the bump, i.e. whom to ask for it;
+    pub last_change: Option<LastChange>,
+    /// Owners of the files requiring the bump in CODEOWNERS, e.g. `@org/parser-team`;
+    pub owners: Vec<String>,
+}
+
+/// A commit that changed the sources of an outdated crate;
+#[derive(Debug, Clone, Serialize)]
+pub struct LastChange {
+    /// Short id of the commit;
+    pub commit: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// Changed files relative to the repository root, by the src directory of the member
+/// containing them;
+pub type MemberChanges = BTreeMap<PathBuf, Vec<PathBuf>>;
+
+/// A crate whose version was bumped by more than the release train of the target branch allows;
+#[derive(Debug, Serialize)]
+pub struct ReleaseTrainViolation {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: Version,
+    pub new_version: Version,
+    pub semver: SemVer,
+    pub allowed: SemVer,
+    /// The `[release-train]` pattern the target branch matched;
+    pub rule: String,
+    pub target: String,
+}
+
+impl std::fmt::Display for ReleaseTrainViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} → {} of {} in {:?} is a {} bump, but only {} bumps are allowed on {} (matched `{}`)",
+            self.old_version,
+            self.new_version,
+            self.krate,
+            self.cargo_toml,
+            self.semver,
+            self.allowed,
+            self.target,
+            self.rule
+        )
+    }
+}
+
+/// A crate whose version was bumped past the next version, e.g. `1.2.3 → 1.5.0`, which usually
+/// indicates a mistake in a manual edit;
+#[derive(Debug, Serialize)]
+pub struct VersionJump {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: Version,
+    pub new_version: Version,
+    pub target: String,
+}
+
+impl std::fmt::Display for VersionJump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let next = self
+            .old_version
+            .next_versions()
+            .iter()
+            .map(|version| version.to_string())
+            .collect::<Vec<String>>();
+
+        write!(
+            f,
+            "version {} → {} of {} in {:?} skips versions compared to {}; expected one of {}, or use --allow-version-jump if the jump is intentional",
+            self.old_version,
+            self.new_version,
+            self.krate,
+            self.cargo_toml,
+            self.target,
+            next.join(", ")
+        )
+    }
+}
+
+/// A crate whose version was bumped although its sources did not change compared to the target
+/// branch, which would publish a release identical to the previous one;
+#[derive(Debug, Serialize)]
+pub struct UnchangedBump {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: Version,
+    pub new_version: Version,
+    pub target: String,
+}
+
+impl std::fmt::Display for UnchangedBump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} → {} of {} in {:?} was bumped, but its sources did not change compared to {}",
+            self.old_version, self.new_version, self.krate, self.cargo_toml, self.target
+        )
+    }
+}
+
+/// Returns the contents of the file at `path`, relative to the repository root, in the tree of
+/// `rev`, or of HEAD, e.g. to read a bare repository;
+fn read_revision_file(
+    repo: &Repository,
+    rev: Option<&str>,
+    path: &Path,
+) -> Result<Option<String>, Error> {
+    let commit = match rev {
+        Some(rev) => repo.revparse_single(rev)?.peel_to_commit()?,
+        None => match repo.head() {
+            Ok(head) => head.peel_to_commit()?,
+            // e.g. a bare repository whose HEAD names a branch that was never pushed;
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(None),
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    let entry = match commit.tree()?.get_path(path) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+
+    Ok(Some(String::from_utf8(blob.content().to_vec()).map_err(
+        |e| Error::msg(format!("{:?} is not valid UTF-8: {}", path.display(), e)),
+    )?))
+}
+
+/// Returns true if the directory is the working tree of a repository of its own, e.g. a
+/// submodule, whose `.git` is a directory or a file pointing to it;
+fn is_nested_repo(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+/// Parse a `[floors]` entry of `.cvm.toml`, i.e. `>=major.minor.patch` or a bare version;
+pub fn parse_floor(krate: &str, floor: &str) -> Result<Version, Error> {
+    let version = floor.trim().trim_start_matches(">=").trim();
+
+    Version::parse(version, false).map_err(|e| {
+        Error::msg(format!(
+            "Invalid floor {:?} for {} in .cvm.toml, expected e.g. `>=2.0.0`: {}",
+            floor, krate, e
+        ))
+    })
+}
+
+/// An outdated crate exempted from the check by a commit message, see `commits::waivers`;
+#[derive(Debug, Serialize)]
+pub struct Waiver {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    /// Short id of the commit carrying the waiver;
+    pub commit: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for Waiver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "waived by commit {}", self.commit)?;
+        if !self.reason.is_empty() {
+            write!(f, ": {}", self.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// A crate whose version is below its floor in `.cvm.toml`;
+#[derive(Debug, Serialize)]
+pub struct BelowFloor {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub version: Version,
+    pub floor: Version,
+}
+
+impl std::fmt::Display for BelowFloor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} of {} in {:?} is below its floor {} in .cvm.toml",
+            self.version, self.krate, self.cargo_toml, self.floor
+        )
+    }
+}
+
+/// A member whose version differs from the unified version, see `--unified`;
+#[derive(Debug, Serialize)]
+pub struct VersionMismatch {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub version: Version,
+    pub unified: Version,
+}
+
+impl std::fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} of {} in {:?} differs from the unified version {}",
+            self.version, self.krate, self.cargo_toml, self.unified
+        )
+    }
+}
+
+/// Package fields that `metadata-fields` in `.cvm.toml` can guard against removal or change;
+pub const METADATA_FIELDS: [&str; 6] = [
+    "license",
+    "license-file",
+    "repository",
+    "description",
+    "homepage",
+    "documentation",
+];
+
+/// Package fields that `new-crate-fields` in `.cvm.toml` can require of new crates;
+pub const NEW_CRATE_FIELDS: [&str; 8] = [
+    "license",
+    "license-file",
+    "repository",
+    "description",
+    "homepage",
+    "documentation",
+    "categories",
+    "keywords",
+];
+
+/// A crate added on the current branch whose manifest does not set the fields required by
+/// `new-crate-fields`, so that it would enter the registry without them;
+#[derive(Debug, Serialize)]
+pub struct IncompleteCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    /// Required fields the manifest does not set, e.g. `["repository", "categories"]`;
+    pub missing: Vec<String>,
+}
+
+impl std::fmt::Display for IncompleteCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let missing = self
+            .missing
+            .iter()
+            .map(|field| format!("`{}`", field))
+            .collect::<Vec<String>>();
+
+        write!(
+            f,
+            "new crate {} in {:?} does not set {}, required by `new-crate-fields` for its first release",
+            self.krate,
+            self.cargo_toml,
+            missing.join(", ")
+        )
+    }
+}
+
+/// A crate bumped compared to the target branch whose changelog has no entry for the new
+/// version, see `[changelog]` in `.cvm.toml`;
+#[derive(Debug, Serialize)]
+pub struct MissingChangelog {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    /// Changelog the entry is missing from, relative to the repository root;
+    pub changelog: PathBuf,
+    pub version: Version,
+    pub target: String,
+}
+
+impl std::fmt::Display for MissingChangelog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} has no entry for version {}, bumped compared to {}: {:?}",
+            self.changelog
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default(),
+            self.krate,
+            self.version,
+            self.target,
+            self.changelog
+        )
+    }
+}
+
+/// A guarded package field that was removed or changed compared to the target branch;
+#[derive(Debug, Serialize)]
+pub struct MetadataRegression {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub field: String,
+    pub old_value: String,
+    /// The current value, none if the field was removed;
+    pub new_value: Option<String>,
+    pub target: String,
+}
+
+impl std::fmt::Display for MetadataRegression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.new_value {
+            Some(new_value) => write!(
+                f,
+                "`{}` of {} in {:?} changed from {:?} to {:?} compared to {}",
+                self.field, self.krate, self.cargo_toml, self.old_value, new_value, self.target
+            ),
+            None => write!(
+                f,
+                "`{}` of {} in {:?} was removed, it is {:?} on {}",
+                self.field, self.krate, self.cargo_toml, self.old_value, self.target
+            ),
+        }
+    }
+}
+
+/// Returns true if the manifest sets `publish = false` or `publish = []`, i.e. the crate is never
+/// published;
+fn is_unpublished(doc: &toml_edit::DocumentMut) -> bool {
+    let publish = doc
+        .get("package")
+        .and_then(|package| package.get("publish"));
+    publish.and_then(toml_edit::Item::as_bool) == Some(false)
+        || publish
+            .and_then(toml_edit::Item::as_array)
+            .map(|registries| registries.is_empty())
+            == Some(true)
+}
+
+/// Returns a package field of the manifest, if it is set or resolved from the workspace;
+fn metadata_field(manifest: &Manifest, field: &str) -> Option<String> {
+    let pkg = manifest.package.as_ref()?;
+    let value = match field {
+        "license" => pkg.license.as_ref(),
+        "repository" => pkg.repository.as_ref(),
+        "description" => pkg.description.as_ref(),
+        "homepage" => pkg.homepage.as_ref(),
+        "documentation" => pkg.documentation.as_ref(),
+        "license-file" => {
+            return pkg
+                .license_file
+                .as_ref()?
+                .get()
+                .ok()
+                .map(|path| path.display().to_string())
+        }
+        "categories" => return list_field(&pkg.categories),
+        "keywords" => return list_field(&pkg.keywords),
+        _ => None,
+    }?;
+
+    value.get().ok().cloned()
+}
+
+/// Parse the manifest at `path` as read from a tree, resolving the fields it inherits from the
+/// root manifest `root` of the same tree, e.g. `license.workspace = true`, so that manifests of
+/// different trees compare by the values they resolve to;
+fn parse_tree_manifest(
+    contents: &[u8],
+    path: &Path,
+    root: Option<&[u8]>,
+    root_dir: &Path,
+) -> Result<Manifest, Error> {
+    let mut manifest = Manifest::from_slice(contents)?;
+    let root = root.map(Manifest::from_slice).transpose()?;
+    manifest.complete_from_path_and_workspace(path, root.as_ref().map(|root| (root, root_dir)))?;
+    Ok(manifest)
+}
+
+/// Returns the entries of a list field, e.g. `categories`, or none if it is empty;
+fn list_field(field: &cargo_toml::Inheritable<Vec<String>>) -> Option<String> {
+    field
+        .get()
+        .ok()
+        .filter(|entries| !entries.is_empty())
+        .map(|entries| entries.join(", "))
+}
+
+/// A crate whose version is not ahead of the target branch, e.g. after `--force` or a manual
+/// edit lowered it;
+#[derive(Debug, Serialize)]
+pub struct VersionRegression {
+    pub cargo_toml: PathBuf,
+    pub version: Version,
+    pub target_version: Version,
+    pub target: String,
+}
+
+impl std::fmt::Display for VersionRegression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.version == self.target_version {
+            write!(
+                f,
+                "version {} in {:?} was bumped but equals the version on {}; bump it again or use --allow-equal if its sources are unchanged",
+                self.version, self.cargo_toml, self.target
+            )
+        } else {
+            write!(
+                f,
+                "version {} in {:?} is lower than version {} on {}; publishing it would go backwards",
+                self.version, self.cargo_toml, self.target_version, self.target
+            )
+        }
+    }
+}
+
+impl std::error::Error for VersionRegression {}
+
+/// A changed file attributed to a crate, and the rule that made it require a bump;
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedFile {
+    /// Path relative to the crate directory;
+    pub path: PathBuf,
+    /// The `[severity]` pattern the file matched, `src/**` if it matched none, or `[assets]` for
+    /// an asset with `changes = "patch"`;
+    pub rule: String,
+    pub semver: SemVer,
+}
+
+impl OutdatedCrate {
+    /// Returns the description followed by the last change and the owners, for the human
+    /// report; CI reports leave the last change out, so that the fingerprint of a finding is
+    /// stable across commits;
+    pub fn describe(&self) -> String {
+        let mut description = self.to_string();
+
+        if let Some(change) = &self.last_change {
+            description.push_str(&format!(
+                "\n    last changed in {} by {}: {}",
+                change.commit, change.author, change.subject
+            ));
+        }
+
+        if !self.owners.is_empty() {
+            description.push_str(&format!("\n    owned by {}", self.owners.join(", ")));
+        }
+
+        description
+    }
+}
+
+impl std::fmt::Display for OutdatedCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} of {} is not updated for changes in workspace Cargo.toml file: {:?}",
+            self.version, self.krate, self.cargo_toml
+        )?;
+        write!(
+            f,
+            "\n    {} → suggest {} against {} ({})",
+            self.version, self.suggested_version, self.target, self.reason
+        )?;
+
+        for file in self.changed_files.iter() {
+            write!(
+                f,
+                "\n    {} (matched `{}`, requires {})",
+                file.path.display(),
+                file.rule,
+                file.semver
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 impl TryInto<Version> for Manifest {
     type Error = Error;
+    /// A package without a `version` has version `0.0.0`, as in Cargo;
     fn try_into(self) -> Result<Version, Self::Error> {
-        if let Some(pkg) = self.package {
-            Ok(pkg.version.try_into()?)
-        } else {
-            Err(Error::msg("Invalid cargo manifest"))
-        }
+        manifest_version(&self, false)
+    }
+}
+
+/// Returns the package version of the manifest, accepting extra components with `lenient`;
+pub fn manifest_version(manifest: &Manifest, lenient: bool) -> Result<Version, Error> {
+    match &manifest.package {
+        Some(pkg) => Version::parse(pkg.version.get()?, lenient),
+        None => Err(Error::msg("Invalid cargo manifest")),
     }
 }
 
@@ -114,299 +815,4346 @@ impl TryInto<SemVer> for String {
     }
 }
 
+impl std::fmt::Display for SemVer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let semver = match self {
+            SemVer::Major => "major",
+            SemVer::Minor => "minor",
+            SemVer::Patch => "patch",
+        };
+
+        write!(f, "{}", semver)
+    }
+}
+
 impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        write!(
+            f,
+            "{}.{}.{}{}",
+            self.major, self.minor, self.patch, self.suffix
+        )
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
 impl TryInto<Version> for String {
     type Error = Error;
     fn try_into(self) -> Result<Version, Self::Error> {
-        let version = self
-            .split('.')
-            .map(|v| v.parse())
-            .collect::<Result<Vec<u8>, std::num::ParseIntError>>()?;
-
-        if version.len() < 3 {
-            return Err(Error::msg(format!("Invalid version number: {:?}", version)));
-        }
-
-        Ok(Version {
-            major: version[0],
-            minor: version[1],
-            patch: version[2],
-        })
+        Version::parse(&self, false)
     }
 }
 
+/// Name of the edits of `workspace.package.version` in the root manifest, which is not a valid
+/// package name;
+pub const WORKSPACE_PACKAGE: &str = "workspace.package";
+
+/// Exit code when a run needs a prompt, e.g. for credentials, but `--non-interactive` was given;
+pub const INTERACTION_REQUIRED_EXIT_CODE: i32 = 3;
+
 pub struct Manager {
     semver: SemVer,
-    target_remote: String,
-    target_branch: String,
-    workspaces: Vec<String>,
+    target_branches: Vec<String>,
+    /// Settings for fetching the target branches;
+    fetcher: Fetcher,
+    /// Maximum number of network operations run at once, e.g. fetches of the target branches;
+    jobs: usize,
+    /// Member paths and manifests, read at most once per run;
+    model: WorkspaceModel,
     check: bool,
     fix: bool,
     warn: bool,
     force: bool,
     commit: bool,
-    repo: Repository,
-    ssh_key_path: String,
+    /// Branch to commit onto when HEAD is detached, e.g. in CI checkouts;
+    commit_branch: Option<String>,
+    tip: bool,
+    since: Option<String>,
+    /// Revision evaluated instead of HEAD, e.g. a colleague's branch;
+    head: Option<String>,
+    /// Members outside the sparse-checkout cone, which have no files on disk;
+    outside_cone: BTreeSet<String>,
+    output: OutputFormat,
+    template: Option<String>,
+    notify_webhook: Option<String>,
+    metrics: Option<PathBuf>,
+    /// Fetches, trees, diffs, and commits of the comparisons, see `GitProvider`;
+    git: Box<dyn GitProvider>,
+    /// Merge-bases by target and current commit, which every member compares against;
+    merge_bases: RefCell<BTreeMap<(Oid, Oid), Oid>>,
+    /// Where manifests are read from and written to, shared with the `model`;
+    fs: Rc<dyn FileSystem>,
+    /// Manifest writes kept in memory by `--dry-run`, shown instead of written;
+    dry_run: Option<Rc<RecordingFs>>,
+    lock_timeout: Duration,
+    ignore: Gitignore,
+    /// Owners of the repository's files from its CODEOWNERS file, if any;
+    codeowners: CodeOwners,
+    severity: Vec<(String, GlobMatcher, SemVer)>,
+    release_train: Vec<(String, GlobMatcher, SemVer)>,
+    commit_filter: CommitFilter,
+    /// Files whose changes are not code changes, see `[assets]` in `.cvm.toml`;
+    assets: Assets,
+    /// Workspace member directory of each conventional commit scope;
+    scopes: BTreeMap<String, PathBuf>,
+    allow_equal: bool,
+    required_bump: Option<SemVer>,
+    /// Whether `--fix` bumps by the level of the conventional commit messages, too;
+    bump_from_commits: bool,
+    strict: bool,
+    allow_version_jump: bool,
+    /// Where `cargo cvm plan` writes the edits that `--fix` would apply;
+    plan: Option<PathBuf>,
+    /// Directory of the generated files, see `artifacts::artifact_dir`;
+    artifact_dir: PathBuf,
+    /// Findings that `--check` does not fail on;
+    baseline: Baseline,
+    /// Whether `cargo cvm baseline` records the findings instead;
+    record_baseline: bool,
+    /// Whether members omitting `package.version` fail the run instead of being skipped;
+    require_version: bool,
+    /// Whether versions with extra components, e.g. `1.2.3.4`, can be compared;
+    lenient_versions: bool,
+    /// Whether checks list the up-to-date crates instead of only counting them;
+    show_ok: bool,
+    /// How bumped crates are packaged before the bumps are committed, if at all;
+    preflight: Option<Preflight>,
+    /// Commands run for each bumped crate, see `[hooks]` in `.cvm.toml`;
+    hooks: Hooks,
+    /// Changelog entries required of bumped crates, see `[changelog]` in `.cvm.toml`;
+    changelog: Changelog,
+    /// Whether path dependencies without a version get the current version of the dependency;
+    fix_path_deps: bool,
+    /// Whether versions are bumped although tracked files have uncommitted changes;
+    allow_dirty: bool,
+    /// Package fields whose removal or change compared to the target branch fails checks;
+    metadata_fields: Vec<String>,
+    /// Package fields required of crates added on the current branch;
+    new_crate_fields: Vec<String>,
+    /// Lowest version computed for each crate, by package name;
+    floors: BTreeMap<String, Version>,
+    /// Whether all members share one version, see `--unified`;
+    unified: bool,
+    /// Package whose version governs all members, instead of the root package;
+    unified_crate: Option<String>,
 }
 
-impl Manager {
-    pub fn new(args: &ArgMatches) -> Result<Self, Error> {
-        let dir = std::env::current_dir()?;
-        let repo = Repository::discover(dir.clone())?;
-        let ssh_key_path = format!("{}/.ssh/id_rsa", std::env::var("HOME")?);
+impl Manager {
+    pub fn new(args: &Invocation) -> Result<Self, Error> {
+        Self::with_revisions(
+            args,
+            args.value_of("head").map(String::from),
+            args.value_of("since").map(String::from),
+        )
+    }
+
+    /// Check a push in the `pre-receive` hook: the pushed commit of a target branch against its
+    /// previous tip, and the pushed commit of any other branch, or of a new target branch,
+    /// against its merge-base with the target branches, as a check in its checkout would;
+    pub fn for_push(args: &Invocation, update: &RefUpdate) -> Result<Self, Error> {
+        let mut manager = Self::with_revisions(args, update.new.clone(), None)?;
+        if let (Some(branch), Some(old)) = (update.branch(), &update.old) {
+            if manager
+                .target_branches
+                .iter()
+                .any(|target| target == branch)
+            {
+                manager.since = Some(old.clone());
+            }
+        }
+        manager.check = true;
+        Ok(manager)
+    }
+
+    /// Whether the current branch is compared against the target branches rather than an
+    /// earlier revision, see `--since`;
+    pub fn compares_to_target(&self) -> bool {
+        self.since.is_none()
+    }
+
+    /// Build the manager evaluating `head` instead of HEAD, against `since` instead of the
+    /// target branches, see `--head` and `--since`;
+    fn with_revisions(
+        args: &Invocation,
+        head: Option<String>,
+        since: Option<String>,
+    ) -> Result<Self, Error> {
+        let repo = Repository::discover(std::env::current_dir()?)?;
+        // The objects of a push are quarantined until the `pre-receive` hook accepts it; git
+        // passes their directory in the environment, which libgit2 does not read;
+        if let Some(quarantine) = std::env::var_os("GIT_QUARANTINE_PATH") {
+            repo.odb()?
+                .add_disk_alternate(&quarantine.to_string_lossy())?;
+        }
+        // A bare repository, e.g. on a server, has no working tree: its git directory stands in
+        // for the workspace root, and files are read from git trees instead;
+        let bare = repo.is_bare();
+        let dir = if bare {
+            repo.path().to_path_buf()
+        } else {
+            std::env::current_dir()?
+        };
+        // The policy comes from HEAD, not from the revision under check, e.g. a pushed branch;
+        let config = if bare {
+            match read_revision_file(&repo, None, Path::new(config::CONFIG_FILE))? {
+                Some(contents) => Config::parse(&contents, Path::new(config::CONFIG_FILE))?,
+                None => Config::default(),
+            }
+        } else {
+            Config::load(&dir)?
+        };
+        let cargo_config = CargoNetConfig::load(&dir)?;
+        let strict = args.is_present("strict");
+
+        if bare
+            && (["fix", "force", "commit", "fix-path-deps"]
+                .iter()
+                .any(|flag| args.is_present(flag))
+                || !matches!(
+                    args.subcommand_name(),
+                    None | Some("graph") | Some("hook") | Some("last-release") | Some("due")
+                ))
+        {
+            return Err(Error::msg(
+                "A bare repository has no working tree to edit; only checks, e.g. `cargo cvm check`, `cargo cvm graph`, and `cargo cvm hook` run in it",
+            ));
+        }
+
+        // Subcommands that edit or publish the checkout must not see the manifests of `--head`;
+        if let (true, Some(subcommand)) = (args.is_present("head"), args.subcommand_name()) {
+            if !["baseline", "graph", "last-release", "due"].contains(&subcommand) {
+                return Err(Error::msg(format!(
+                    "--head cannot be used with `cargo cvm {}`, which works on the checkout",
+                    subcommand
+                )));
+            }
+        }
+
+        for key in config.unknown.keys() {
+            warning::warn(
+                strict,
+                format!("unknown key `{}` in {}", key, config::CONFIG_FILE),
+            )?;
+        }
+
+        let root_manifest = if bare {
+            let head = head.as_deref();
+            match read_revision_file(&repo, head, Path::new("Cargo.toml"))? {
+                Some(contents) => Some(contents),
+                None => {
+                    return Err(Error::msg(format!(
+                        "No Cargo.toml at the root of {}",
+                        head.unwrap_or("HEAD")
+                    )))
+                }
+            }
+        } else {
+            None
+        };
+        let mut workspaces = match &root_manifest {
+            Some(contents) => Self::workspace_members(contents, &dir)?,
+            None => Self::get_cargo_workspaces(dir.clone())?,
+        };
+        // Nested packages and repositories are found on disk, which a bare repository does not
+        // have;
+        let nested = if bare {
+            Vec::new()
+        } else {
+            // Members in a repository of their own are not versioned by this one's history;
+            workspaces.retain(|workspace| {
+                let path = dir.join(workspace);
+                path == dir || !is_nested_repo(&path)
+            });
+            let excluded = Self::workspace_excludes(&read_to_string(dir.join("Cargo.toml"))?)?;
+            Self::get_nested_packages(&dir, &workspaces, &excluded, strict)?
+        };
+
+        match config.nested_packages {
+            NestedPackages::Check => workspaces.extend(nested),
+            NestedPackages::Warn => {
+                for package in nested.iter() {
+                    warning::warn(strict, format!("nested package {:?} is not a workspace member and will not be checked; set `nested-packages` to `check` or `ignore` in .cvm.toml", package))?;
+                }
+            }
+            NestedPackages::Ignore => {}
+        }
+
+        // Pinning fingerprints implies only accepting pinned host keys by default;
+        let mut fingerprints = config.ssh_host_fingerprints.clone();
+        if let Some(pins) = args.values_of("ssh-host-fingerprint") {
+            fingerprints.extend(pins.map(String::from));
+        }
+        let policy = match args.value_of("ssh-host-key-policy") {
+            Some(policy) => HostKeyPolicy::try_from(policy)?,
+            None => config
+                .ssh_host_key_policy
+                .unwrap_or(if fingerprints.is_empty() {
+                    HostKeyPolicy::Default
+                } else {
+                    HostKeyPolicy::Pinned
+                }),
+        };
+        let host_keys = HostKeyVerifier {
+            policy,
+            fingerprints,
+            known_hosts: repo.path().join("cvm-known-hosts"),
+        };
+
+        // Follow cargo's `net.git-fetch-with-cli` unless a backend is given, which only changes
+        // how the target branches are fetched;
+        let git_backend = match args.value_of("git-backend") {
+            Some(backend) => GitBackend::try_from(backend)?,
+            None => GitBackend::default(),
+        };
+        let fetch_with_cli =
+            args.value_of("git-backend").is_none() && cargo_config.git_fetch_with_cli == Some(true);
+        if git_backend == GitBackend::Libgit2
+            && !fetch_with_cli
+            && (cargo_config.ssl_version.is_some() || cargo_config.check_revoke.is_some())
+        {
+            warning::warn(
+                strict,
+                "`http.ssl-version` and `http.check-revoke` of the cargo configuration are only applied when fetching with `git`, i.e. with --git-backend cli or `net.git-fetch-with-cli`",
+            )?;
+        }
+
+        if let Some(field) = config
+            .metadata_fields
+            .iter()
+            .find(|field| !METADATA_FIELDS.contains(&field.as_str()))
+        {
+            return Err(Error::msg(format!(
+                "Invalid metadata-fields entry {:?} in .cvm.toml; expected one of {}",
+                field,
+                METADATA_FIELDS.join(", ")
+            )));
+        }
+        if let Some(field) = config
+            .new_crate_fields
+            .iter()
+            .find(|field| !NEW_CRATE_FIELDS.contains(&field.as_str()))
+        {
+            return Err(Error::msg(format!(
+                "Invalid new-crate-fields entry {:?} in .cvm.toml; expected one of {}",
+                field,
+                NEW_CRATE_FIELDS.join(", ")
+            )));
+        }
+
+        let ignore = if bare {
+            match read_revision_file(&repo, None, Path::new(config::IGNORE_FILE))? {
+                Some(contents) => config::parse_ignore(&dir, &contents)?,
+                None => Gitignore::empty(),
+            }
+        } else {
+            config::load_ignore(&dir)?
+        };
+        let baseline = if bare {
+            match read_revision_file(&repo, None, Path::new(baseline::BASELINE_FILE))? {
+                Some(contents) => Baseline::parse(&contents, Path::new(baseline::BASELINE_FILE))?,
+                None => Baseline::default(),
+            }
+        } else {
+            Baseline::load(&dir)?
+        };
+        // CODEOWNERS is found relative to the repository root, not the workspace;
+        let codeowners = CodeOwners::find(|path| match repo.workdir() {
+            Some(root) if root.join(path).is_file() => {
+                Ok(Some(std::fs::read_to_string(root.join(path))?))
+            }
+            Some(_) => Ok(None),
+            None => read_revision_file(&repo, None, path),
+        })?;
+        let severity = config.severity_rules()?;
+        let release_train = config.release_train_rules()?;
+
+        let fetcher = Fetcher {
+            target_remote: args
+                .value_of("remote")
+                .map(String::from)
+                .or_else(|| config.remote.clone())
+                .unwrap_or_else(|| String::from("origin")),
+            anonymous: args.is_present("anonymous"),
+            non_interactive: args.is_present("non-interactive"),
+            proxy: args
+                .value_of("proxy")
+                .map(String::from)
+                .or_else(|| cargo_config.proxy.clone()),
+            ca_bundle: args
+                .value_of("ca-bundle")
+                .map(PathBuf::from)
+                .or_else(|| config.ca_bundle.clone())
+                .or_else(|| cargo_config.cainfo.clone()),
+            ssl_version: cargo_config.ssl_version.clone(),
+            check_revoke: cargo_config.check_revoke,
+            low_speed_timeout: cargo_config.timeout.map(Duration::from_secs),
+            host_keys,
+            fetch_timeout: args
+                .value_of("fetch-timeout")
+                .map(|secs| secs.parse().map(Duration::from_secs))
+                .transpose()?,
+            fetch_retries: match args.value_of("fetch-retries") {
+                Some(retries) => retries.parse()?,
+                None => cargo_config.retry.unwrap_or(3),
+            },
+            git_backend,
+            fetch_with_cli,
+            ssh_keys: args
+                .values_of("ssh-key")
+                .map(|keys| keys.map(PathBuf::from).collect())
+                .unwrap_or_default(),
+        };
+
+        let dry_run = if args.is_present("dry-run") {
+            Some(Rc::new(RecordingFs::over_disk()))
+        } else {
+            None
+        };
+        let fs: Rc<dyn FileSystem> = match &dry_run {
+            Some(recording) => recording.clone(),
+            None => Rc::new(DiskFs),
+        };
+
+        let artifact_dir = artifacts::artifact_dir(&dir, config.artifact_dir.as_deref());
+
+        let mut manager = Self {
+            semver: args.value_of("semver").unwrap_or("minor").try_into()?,
+            check: args.is_present("check"),
+            fix: args.is_present("fix") || args.subcommand_matches("plan").is_some(),
+            warn: args.is_present("warn"),
+            force: args.is_present("force"),
+            // A dry run writes nothing to commit;
+            commit: args.is_present("commit") && dry_run.is_none(),
+            commit_branch: args.value_of("commit-branch").map(String::from),
+            tip: args.is_present("tip"),
+            since,
+            head,
+            outside_cone: Self::get_outside_cone(&repo, &dir, &workspaces)?,
+            output: if args.is_present("template") {
+                OutputFormat::Template
+            } else {
+                args.value_of("output").unwrap_or("human").try_into()?
+            },
+            notify_webhook: args.value_of("notify-webhook").map(String::from),
+            metrics: args.value_of("metrics").map(PathBuf::from),
+            template: match args.value_of("template") {
+                Some(path) => Some(read_to_string(path).map_err(|e| {
+                    Error::msg(format!("Failed to read template {:?}: {}", path, e))
+                })?),
+                None => None,
+            },
+            target_branches: match args.values_of("branch") {
+                Some(branches) => branches.map(String::from).collect(),
+                None => vec![config
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| String::from("master"))],
+            },
+            git: Box::new(Libgit2Provider::new(repo, fetcher.clone())),
+            merge_bases: RefCell::new(BTreeMap::new()),
+            fetcher,
+            jobs: match args.value_of("jobs") {
+                Some(jobs) => jobs.parse::<usize>()?.max(1),
+                None => jobs::default_jobs(),
+            },
+            model: {
+                let _span = trace::span("parse", format!("{} member(s)", workspaces.len()));
+                match root_manifest {
+                    Some(contents) => WorkspaceModel::with_root_manifest(
+                        dir.clone(),
+                        workspaces,
+                        Some(contents),
+                        fs.clone(),
+                    )?,
+                    None => WorkspaceModel::new(dir.clone(), workspaces, fs.clone())?,
+                }
+            },
+            fs,
+            dry_run,
+            lock_timeout: Duration::from_secs(
+                args.value_of("lock-timeout").unwrap_or("30").parse()?,
+            ),
+            ignore,
+            codeowners,
+            severity,
+            release_train,
+            commit_filter: config.commits.clone(),
+            assets: config.assets.clone(),
+            scopes: config.scopes.clone(),
+            allow_equal: args.is_present("allow-equal"),
+            strict,
+            allow_version_jump: args.is_present("allow-version-jump"),
+            plan: args
+                .subcommand_matches("plan")
+                .map(|plan| match plan.value_of("out") {
+                    Some(out) => PathBuf::from(out),
+                    None => artifact_dir.join("plan.json"),
+                }),
+            artifact_dir,
+            baseline,
+            record_baseline: args.subcommand_matches("baseline").is_some(),
+            require_version: config.require_version,
+            lenient_versions: config.lenient_versions,
+            show_ok: args.is_present("show-ok"),
+            fix_path_deps: args.is_present("fix-path-deps"),
+            allow_dirty: args.is_present("allow-dirty"),
+            metadata_fields: config.metadata_fields.clone(),
+            new_crate_fields: config.new_crate_fields.clone(),
+            unified: args.is_present("unified")
+                || args.is_present("unified-crate")
+                || config.unified
+                || config.unified_crate.is_some(),
+            unified_crate: args
+                .value_of("unified-crate")
+                .map(String::from)
+                .or_else(|| config.unified_crate.clone()),
+            floors: config
+                .floors
+                .iter()
+                .map(|(krate, floor)| Ok((krate.clone(), parse_floor(krate, floor)?)))
+                .collect::<Result<BTreeMap<String, Version>, Error>>()?,
+            hooks: config.hooks.clone(),
+            changelog: config.changelog.clone(),
+            preflight: match args.value_of("preflight") {
+                Some(preflight) => Some(Preflight::try_from(preflight)?),
+                None => config.preflight,
+            },
+            bump_from_commits: args.is_present("bump-from-commits"),
+            // An unset label may leave the environment variable empty;
+            required_bump: match args
+                .value_of("required-bump")
+                .filter(|bump| !bump.is_empty())
+            {
+                Some(bump) => Some(bump.try_into()?),
+                None => None,
+            },
+        };
+        manager.load_tree_manifests()?;
+        manager.scopes = manager.resolve_scopes()?;
+        manager.floors = manager.resolve_floors()?;
+
+        Ok(manager)
+    }
+
+    /// Returns the members whose manifest is missing because they are outside the cone of a
+    /// sparse checkout, i.e. `core.sparseCheckout` is set;
+    fn get_outside_cone(
+        repo: &Repository,
+        dir: &Path,
+        workspaces: &[String],
+    ) -> Result<BTreeSet<String>, Error> {
+        // `git sparse-checkout` writes the setting to `config.worktree`, which libgit2 ignores;
+        let worktree_config = repo.path().join("config.worktree");
+        let sparse = [
+            Some(repo.config()?),
+            git2::Config::open(&worktree_config).ok(),
+        ]
+        .iter()
+        .flatten()
+        .any(|config| config.get_bool("core.sparseCheckout").unwrap_or(false));
+        if !sparse {
+            return Ok(BTreeSet::new());
+        }
+
+        Ok(workspaces
+            .iter()
+            .filter(|workspace| !dir.join(workspace).join("Cargo.toml").is_file())
+            .cloned()
+            .collect())
+    }
+
+    /// Returns true if the member in `workspace` is outside the sparse-checkout cone;
+    pub fn is_outside_cone(&self, workspace: &Path) -> bool {
+        self.outside_cone
+            .iter()
+            .any(|member| Path::new(member) == workspace)
+    }
+
+    /// Read member manifests from the tree of the current side instead of the disk: with
+    /// `--head` or in a bare repository, all of them, so that versions are those of the
+    /// revision, and otherwise those outside the sparse-checkout cone; members are still listed
+    /// from the checkout, and members missing from the tree keep their manifest on disk;
+    fn load_tree_manifests(&self) -> Result<(), Error> {
+        let all = self.head.is_some() || self.is_bare();
+        if !all && self.outside_cone.is_empty() {
+            return Ok(());
+        }
+
+        let tree = self.git.tree(self.head_commit()?)?;
+        for workspace in self.model.members().iter() {
+            if !all && !self.is_outside_cone(Path::new(workspace)) {
+                continue;
+            }
+            let cargo_toml = Path::new(workspace).join("Cargo.toml");
+            let manifest_path = self.get_repo_relative_path(&cargo_toml)?;
+            if let Some(contents) = self.read_tree_file(tree, &manifest_path)? {
+                self.model
+                    .update(&cargo_toml, String::from_utf8(contents)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the commit evaluated as the current side of comparisons, i.e. `--head` or HEAD;
+    pub fn head_commit(&self) -> Result<Oid, Error> {
+        match &self.head {
+            Some(rev) => self
+                .git
+                .resolve(rev)
+                .map_err(|e| Error::msg(format!("Invalid --head revision {:?}: {}", rev, e))),
+            None => self.git.resolve("HEAD"),
+        }
+    }
+
+    pub fn get_cargo_workspaces(dir: PathBuf) -> Result<Vec<String>, Error> {
+        let cargo_toml = dir.join("Cargo.toml");
+
+        if !cargo_toml.exists() {
+            eprintln!(
+                "{}",
+                tr(
+                    "no-manifest",
+                    &[("path", &format!("{:?}", cargo_toml.display()))]
+                )
+            );
+            std::process::exit(1)
+        }
+
+        Self::workspace_members(&read_to_string(&cargo_toml)?, &dir)
+    }
+
+    /// Returns the members listed by the root manifest `contents` of the workspace in `dir`,
+    /// including `dir` itself if the root manifest is a package, and leaving out the members
+    /// under a path of `workspace.exclude`;
+    pub fn workspace_members(contents: &str, dir: &Path) -> Result<Vec<String>, Error> {
+        let config = Manifest::from_str(contents)?;
+        let mut paths: Vec<String> = Vec::new();
+
+        if config.package.is_some() {
+            if let Some(path) = dir.to_str() {
+                paths.push(String::from(path));
+            }
+        }
+
+        if let Some(workspace) = config.workspace {
+            let excluded = workspace
+                .exclude
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<PathBuf>>();
+            paths.extend(workspace.members.into_iter().filter(|member| {
+                !excluded
+                    .iter()
+                    .any(|exclude| Path::new(member).starts_with(exclude))
+            }))
+        }
+
+        Ok(paths)
+    }
+
+    /// Returns the paths of `workspace.exclude` in the root manifest `contents`, relative to the
+    /// workspace root;
+    pub fn workspace_excludes(contents: &str) -> Result<Vec<PathBuf>, Error> {
+        Ok(Manifest::from_str(contents)?
+            .workspace
+            .map(|workspace| workspace.exclude.into_iter().map(PathBuf::from).collect())
+            .unwrap_or_default())
+    }
+
+    /// Recursively find packages inside the workspace members that are not members themselves,
+    /// e.g. examples with their own `Cargo.toml`, without searching directories under a path of
+    /// `excluded`, relative to `dir`, or nested repositories, i.e. directories with their own
+    /// `.git` such as submodules; unparsable manifests are skipped with a warning;
+    pub fn get_nested_packages(
+        dir: &Path,
+        workspaces: &[String],
+        excluded: &[PathBuf],
+        strict: bool,
+    ) -> Result<Vec<String>, Error> {
+        let members = workspaces
+            .iter()
+            .map(|workspace| dir.join(workspace))
+            .collect::<Vec<PathBuf>>();
+        let skipped = excluded
+            .iter()
+            .map(|exclude| dir.join(exclude))
+            .collect::<Vec<PathBuf>>();
+
+        let mut nested = Vec::new();
+
+        for workspace in workspaces.iter() {
+            Self::find_nested_packages(
+                &dir.join(workspace),
+                &members,
+                &skipped,
+                strict,
+                &mut nested,
+            )?;
+        }
+
+        nested.sort();
+        nested.dedup();
+
+        Ok(nested
+            .into_iter()
+            .map(|path| match path.strip_prefix(dir) {
+                Ok(relative) => relative.display().to_string(),
+                Err(_) => path.display().to_string(),
+            })
+            .collect())
+    }
+
+    fn find_nested_packages(
+        dir: &Path,
+        members: &[PathBuf],
+        skipped: &[PathBuf],
+        strict: bool,
+        nested: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        // Members outside the cone of a sparse checkout have no directory;
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Symlinked directories are not followed, since they may form a cycle;
+            if !entry.file_type()?.is_dir()
+                || members.contains(&path)
+                || skipped.iter().any(|skip| path.starts_with(skip))
+                || is_nested_repo(&path)
+            {
+                continue;
+            }
+
+            // Skip build output and hidden directories, e.g. `.git`;
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                if name == "target" || name.starts_with('.') {
+                    continue;
+                }
+            }
+
+            let cargo_toml = path.join("Cargo.toml");
+            if cargo_toml.is_file() {
+                match Manifest::from_str(&read_to_string(&cargo_toml)?) {
+                    Ok(manifest) if manifest.package.is_some() => nested.push(path.clone()),
+                    Ok(_) => {}
+                    // Parse errors span several lines with a snippet; the first names the position;
+                    Err(e) => warning::warn(
+                        strict,
+                        format!(
+                            "skipping unparsable manifest {:?}: {}",
+                            cargo_toml,
+                            e.to_string().lines().next().unwrap_or_default()
+                        ),
+                    )?,
+                }
+            }
+
+            Self::find_nested_packages(&path, members, skipped, strict, nested)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the edit bumping the package version of the workspace by `semver`, without
+    /// writing it;
+    pub fn prepare_bump(&self, workspace: PathBuf, semver: SemVer) -> Result<ManifestEdit, Error> {
+        let member = self.model.member(&workspace)?;
+        let cargo_toml = workspace.join("Cargo.toml");
+
+        if let Some(pkg) = &member.manifest.package {
+            let old_version = member.version(false)?;
+            let mut new_version = old_version.clone();
+            new_version.bump(semver);
+            new_version.raise_to(self.floors.get(&pkg.name));
+
+            // An inherited version is bumped in the root manifest, for all members inheriting it;
+            if member.inherits_version() {
+                return self.prepare_workspace_edit(&old_version, &new_version);
+            }
+
+            self.prepare_edit(
+                cargo_toml,
+                member.contents.clone(),
+                pkg.name.clone(),
+                &old_version,
+                &new_version,
+                "[package]",
+            )
+        } else {
+            eprintln!("invalid cargo file");
+            std::process::exit(1)
+        }
+    }
+
+    /// Returns `workspace.package.version` of the root manifest;
+    pub fn workspace_version(&self) -> Result<Version, Error> {
+        match self.model.workspace_version(false)? {
+            Some(version) => Ok(version),
+            None => Err(Error::msg(
+                "The root manifest does not set `workspace.package.version`",
+            )),
+        }
+    }
+
+    /// Compute the edit changing `workspace.package.version` in the root manifest from
+    /// `old_version` to `new_version`, without writing it;
+    pub fn prepare_workspace_edit(
+        &self,
+        old_version: &Version,
+        new_version: &Version,
+    ) -> Result<ManifestEdit, Error> {
+        let cargo_toml = self.model.root().join("Cargo.toml");
+        let config = fields::read_manifest(&*self.fs, &cargo_toml)?;
+
+        self.prepare_edit(
+            cargo_toml,
+            config,
+            String::from(WORKSPACE_PACKAGE),
+            old_version,
+            new_version,
+            "[workspace.package]",
+        )
+    }
+
+    /// Compute the edit changing the version in the `table` of the manifest `config`, i.e.
+    /// `[package]` or `[workspace.package]`, from `old_version` to `new_version`;
+    fn prepare_edit(
+        &self,
+        cargo_toml: PathBuf,
+        config: String,
+        name: String,
+        old_version: &Version,
+        new_version: &Version,
+        table: &str,
+    ) -> Result<ManifestEdit, Error> {
+        if let Some(dir) = cargo_toml.parent().filter(|dir| self.is_outside_cone(dir)) {
+            return Err(Error::msg(format!(
+                "{} is outside the sparse-checkout cone, so its version cannot be bumped; add it with `git sparse-checkout add {}`",
+                name,
+                dir.display()
+            )));
+        }
+
+        // The first instance must be the package version, and other instances, e.g. a
+        // dependency pinned at the same version, are left unchanged;
+        let occurrences = find_version_occurrences(&config, &old_version.to_string());
+        match (occurrences.first(), table_version_line(&config, table)) {
+            (Some(first), Some(line)) if *first == line => {}
+            _ => {
+                return Err(Error::msg(format!(
+                    "The first occurrence of version {} in {:?} is not the version in {}; update it manually",
+                    old_version,
+                    cargo_toml.display(),
+                    table
+                )))
+            }
+        }
+        for line in occurrences.iter().skip(1) {
+            self.warn(format!(
+                "version {} also appears on line {} of {:?} and will not be updated",
+                old_version,
+                line + 1,
+                cargo_toml.display()
+            ))?;
+        }
+
+        Ok(ManifestEdit {
+            name,
+            path: cargo_toml,
+            old_version: old_version.to_string(),
+            new_version: new_version.to_string(),
+            original: config,
+        })
+    }
+
+    /// Write all edited manifests and add them to the git index, or none of them: on any error,
+    /// the manifests already written are restored;
+    pub fn apply_edits(&self, edits: &[ManifestEdit]) -> Result<(), Error> {
+        // Nothing to stage, e.g. in a bare repository, which has no index to stage into;
+        if edits.is_empty() {
+            return Ok(());
+        }
+        let _span = trace::span("edit", format!("{} manifest(s)", edits.len()));
+
+        // Replace only the first instance of the old_version to the new_version;
+        // this will not replace dependency versions;
+        let updated = edits
+            .iter()
+            .map(|edit| {
+                edit.original
+                    .replacen(&edit.old_version, &edit.new_version, 1)
+            })
+            .collect::<Vec<String>>();
+
+        let mut written = Vec::new();
+        let result = edits
+            .iter()
+            .zip(updated.iter())
+            .try_for_each(|(edit, config)| {
+                self.fs.write(&edit.path, config)?;
+                written.push(edit);
+                Ok(())
+            })
+            .and_then(|()| self.stage(&self.written_paths(edits)?));
+
+        if let Err(e) = result {
+            for edit in written {
+                if let Err(restore) = self.fs.write(&edit.path, &edit.original) {
+                    eprintln!(
+                        "warning: failed to restore {:?}: {}",
+                        edit.path.display(),
+                        restore
+                    );
+                }
+            }
+            return Err(Error::msg(format!(
+                "Failed to apply the version bumps, restored the manifests: {}",
+                e
+            )));
+        }
+
+        for (edit, config) in edits.iter().zip(updated) {
+            self.model.update(&edit.path, config)?;
+            if self.dry_run.is_none() {
+                println!("version {} update added to git.", edit.new_version);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Paths of the files written for the edits, i.e. the targets of symlinked manifests, that
+    /// are inside the repository;
+    fn written_paths(&self, edits: &[ManifestEdit]) -> Result<Vec<PathBuf>, Error> {
+        let workdir = self.workdir()?;
+        let canonical_workdir = std::fs::canonicalize(&workdir)?;
+
+        let mut paths = Vec::new();
+        for edit in edits {
+            let path = std::fs::canonicalize(&edit.path)?;
+            if let Ok(relative) = path.strip_prefix(&canonical_workdir) {
+                paths.push(workdir.join(relative));
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Print the manifest edits kept in memory by `--dry-run`, as the lines they change;
+    fn print_dry_run(&self) {
+        let recording = match &self.dry_run {
+            Some(recording) => recording,
+            None => return,
+        };
+
+        for (path, contents) in recording.writes() {
+            let original = std::fs::read_to_string(&path).unwrap_or_default();
+            println!("would write {:?}", path.display());
+            for line in fs::changed_lines(&original, &contents) {
+                println!("  {}", line);
+            }
+        }
+    }
+
+    /// Stage the manifests a subcommand wrote and commit them with `msg` if `commit` is set; a
+    /// dry run prints the lines they would change instead, and stages and commits nothing;
+    fn finish_edits(&self, paths: &[PathBuf], commit: bool, msg: &str) -> Result<(), Error> {
+        if self.dry_run.is_some() {
+            self.print_dry_run();
+            return Ok(());
+        }
+
+        self.stage(paths)?;
+        if commit {
+            self.commit_changes(msg)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the target branches, up to `--jobs` at once;
+    pub fn fetch_targets(&self) -> Result<(), Error> {
+        // A bare repository holds the target branches, see `get_target_commit`;
+        if self.is_bare() {
+            return Ok(());
+        }
+
+        let git = &*self.git;
+
+        jobs::run(&self.all_branches()?, self.jobs, |branch| {
+            let _span = trace::span("fetch", branch.as_str());
+            git.fetch(branch)
+        })
+        .into_iter()
+        .collect()
+    }
+
+    /// Check that the target remote, its branches, and the commit signature are usable;
+    pub fn diagnose(&self) -> Vec<Check> {
+        let mut checks = self.diagnose_remote();
+
+        match self.new_signature() {
+            Ok(signature) => checks.push(Check::pass("signature resolvable", signature)),
+            Err(e) => checks.push(Check::fail(
+                "signature resolvable",
+                e.to_string(),
+                "set `git config user.name` and `git config user.email`",
+            )),
+        }
+
+        checks.extend(self.diagnose_tags());
+        checks
+    }
+
+    /// Check that the `tag-prefix` of each member that sets one matches its release tags;
+    fn diagnose_tags(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        for workspace in self.model.members().iter() {
+            let workspace = Path::new(workspace);
+            let name = match self.model.member(workspace) {
+                Ok(member) if fields::cvm_metadata(&member.doc, "tag-prefix").is_some() => {
+                    fields::get(&member.doc, "name", false).to_string()
+                }
+                _ => continue,
+            };
+            match self.release_tags(workspace) {
+                Ok(tags) if !tags.is_empty() => checks.push(Check::pass(
+                    "release tags found",
+                    format!(
+                        "{}: {} tag(s), latest {} at {}",
+                        name,
+                        tags.len(),
+                        tags[0].name,
+                        &tags[0].commit.to_string()[..7]
+                    ),
+                )),
+                Ok(_) => checks.push(Check::fail(
+                    "release tags found",
+                    format!("no tag of {} matches its tag-prefix", name),
+                    format!(
+                        "set `tag-prefix` in [package.metadata.cvm] of {:?} to the part of its tags before the version, e.g. `rust-v` for `rust-v0.9.1`",
+                        workspace.join("Cargo.toml")
+                    ),
+                )),
+                Err(e) => checks.push(Check::fail(
+                    "release tags found",
+                    e.to_string(),
+                    "set `tag-prefix` to a string or an array of strings",
+                )),
+            }
+        }
+
+        checks
+    }
+
+    /// Check that the target remote exists, is reachable with the credentials, and has the
+    /// target branches;
+    fn diagnose_remote(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        let repo = match self.repository() {
+            Ok(repo) => repo,
+            Err(e) => {
+                checks.push(Check::fail(
+                    "remote exists",
+                    e.to_string(),
+                    "run cargo cvm in a git repository",
+                ));
+                return checks;
+            }
+        };
+        let url = match repo.find_remote(&self.fetcher.target_remote) {
+            Ok(_) => {
+                let url = self.fetcher.target_url(&repo).unwrap_or_default();
+                checks.push(Check::pass(
+                    "remote exists",
+                    format!("{} → {}", self.fetcher.target_remote, url),
+                ));
+                url
+            }
+            Err(_) => {
+                let remotes = repo.remotes().map(|remotes| {
+                    remotes
+                        .iter()
+                        .flatten()
+                        .map(String::from)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                });
+                checks.push(Check::fail(
+                    "remote exists",
+                    format!("remote {:?} is not configured", self.fetcher.target_remote),
+                    format!(
+                        "pass --remote with one of the configured remotes ({}) or set `remote` in .cvm.toml",
+                        remotes.unwrap_or_default()
+                    ),
+                ));
+                return checks;
+            }
+        };
+
+        match self.fetcher.list_remote_branches(&repo) {
+            Ok(branches) => {
+                checks.push(Check::pass("remote reachable", url));
+                checks.push(Check::pass(
+                    "credentials usable",
+                    if self.fetcher.anonymous { "anonymous" } else { "accepted" },
+                ));
+
+                for branch in self.all_branches().unwrap_or_default().iter() {
+                    if branches.contains(branch) {
+                        checks.push(Check::pass("target branch exists", self.target_name(branch)));
+                    } else {
+                        checks.push(Check::fail(
+                            "target branch exists",
+                            format!("{} has no branch {:?}", self.fetcher.target_remote, branch),
+                            "pass --branch with an existing branch or set `branch` in .cvm.toml, e.g. with `cargo cvm init`",
+                        ));
+                    }
+                }
+            }
+            Err(e) => match e.downcast_ref::<git2::Error>() {
+                Some(git_error) if fetch::is_auth_error(git_error) => {
+                    checks.push(Check::pass("remote reachable", url));
+                    checks.push(Check::fail(
+                        "credentials usable",
+                        e.to_string(),
+                        "pass --ssh-key, start an ssh agent, or use --anonymous for public repositories",
+                    ));
+                }
+                Some(git_error) if git_error.class() == git2::ErrorClass::Callback => {
+                    checks.push(Check::fail(
+                        "remote reachable",
+                        e.to_string(),
+                        "increase --fetch-timeout or check the network connection",
+                    ))
+                }
+                _ => checks.push(Check::fail(
+                    "remote reachable",
+                    e.to_string(),
+                    "check the remote URL, network connection, and --proxy; or try --git-backend cli",
+                )),
+            },
+        }
+
+        checks
+    }
+
+    pub fn check_workspaces(&self) -> Result<(), Error> {
+        // A dry run or a plan leaves the working directory untouched;
+        let editing = (self.force || self.fix || self.fix_path_deps)
+            && self.plan.is_none()
+            && self.dry_run.is_none();
+        self.check_clean(editing && !self.allow_dirty)?;
+
+        // Comparing against an earlier revision does not need the target branch;
+        if self.since.is_none() {
+            self.fetch_targets()?;
+        }
+
+        // Refuse before editing anything rather than leave staged edits behind;
+        if (self.force || self.fix || self.fix_path_deps) && self.commit && self.plan.is_none() {
+            self.check_commit_target()?;
+        }
+
+        // Hold the repository lock while manifests may be edited and staged;
+        let lock = if (self.force || self.fix || self.fix_path_deps) && self.plan.is_none() {
+            Some(RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?)
+        } else {
+            None
+        };
+
+        let mut failed = false;
+        let mut new_outdated = false;
+        let mut state = FixState::default();
+        let mut reasons = Vec::new();
+        let mut report = Report::default();
+        let human = self.output == OutputFormat::Human;
+        // Checks print the crates grouped by status at the end, rather than as they are checked;
+        let grouped = human && self.check;
+        let inline = human && !self.check;
+        // With `--unified`, members share the version of one member, bumped once for all;
+        let unified = self.unified_version()?;
+        let mut unified_bump: Option<(SemVer, &str)> = None;
+
+        // Attribute the changed files to the members once per target branch, not per member;
+        let mut changes = BTreeMap::new();
+        for branch in self.all_branches()?.iter() {
+            changes.insert(branch.clone(), self.changed_src_files(branch)?);
+        }
+
+        // For each of the workspace directories, check if any files in the src directory have
+        // changed compared to each of the target branches;
+        for workspace in self.members_by_name().iter() {
+            report.crates.insert(
+                self.get_repo_relative_path(&Path::new(workspace).join("Cargo.toml"))?,
+                self.crate_name(Path::new(workspace)),
+            );
+            if let Some(frozen) = self.frozen_crate(Path::new(workspace))? {
+                if inline {
+                    eprintln!("{}", frozen);
+                }
+                for branch in self.member_branches(Path::new(workspace))?.iter() {
+                    if self.is_frozen_touched(Path::new(workspace), branch)? {
+                        self.warn(format!(
+                            "sources of frozen crate {} changed compared to {}",
+                            frozen.krate,
+                            self.target_name(branch)
+                        ))?;
+                    }
+                }
+                report.frozen.push(frozen);
+                continue;
+            }
+
+            // Unversioned crates are never published, so their version needs no bump;
+            if let Some(unversioned) = self.unversioned_crate(Path::new(workspace))? {
+                if unversioned.required {
+                    if !grouped {
+                        eprintln!("{}", unversioned);
+                    }
+                    failed = true;
+                } else if inline {
+                    eprintln!("{}", unversioned);
+                }
+                report.unversioned.push(unversioned);
+                continue;
+            }
+
+            let below_floor = self.below_floor(Path::new(workspace))?;
+            if let Some(below) = &below_floor {
+                if inline {
+                    eprintln!("{}", below);
+                }
+                failed |= self.check;
+            }
+
+            if let Some((source, version)) = &unified {
+                if source.as_ref() != Some(workspace) {
+                    if let Some(mismatch) = self.version_mismatch(Path::new(workspace), version)? {
+                        if inline {
+                            eprintln!("{}", mismatch);
+                        }
+                        failed |= self.check;
+                        report.mismatched.push(mismatch);
+                    }
+                }
+            }
+
+            let path_deps = self.unversioned_path_deps(Path::new(workspace))?;
+            if inline && !self.fix_path_deps {
+                path_deps.iter().for_each(|dep| eprintln!("{}", dep));
+            }
+            report.path_deps.extend(path_deps);
+
+            let cargo_toml =
+                self.get_repo_relative_path(&Path::new(workspace).join("Cargo.toml"))?;
+            let added = self.is_new_crate(Path::new(workspace))?;
+            if added {
+                report.added.push(cargo_toml.clone());
+                if let Some(incomplete) = self.check_new_crate_metadata(Path::new(workspace))? {
+                    if inline {
+                        eprintln!("{}", incomplete);
+                    }
+                    failed |= self.check;
+                    report.incomplete.push(incomplete);
+                }
+            }
+            if self.is_outside_cone(Path::new(workspace)) {
+                report.sparse.push(cargo_toml.clone());
+            }
+
+            let mut outdated = Vec::new();
+            for branch in self.member_branches(Path::new(workspace))?.iter() {
+                if let Some(krate) =
+                    self.is_version_outdated(PathBuf::from(workspace), branch, &changes[branch])?
+                {
+                    outdated.push(krate);
+                }
+
+                if let Some(violation) = self.check_release_train(Path::new(workspace), branch)? {
+                    let entry = BaselineEntry::violation(&violation);
+                    if self.baseline.contains(&entry) {
+                        if human {
+                            eprintln!("{}, tolerated by {}", violation, baseline::BASELINE_FILE);
+                        }
+                        report.tolerated.push(entry);
+                    } else {
+                        if human {
+                            eprintln!("{}", violation);
+                        }
+                        failed |= self.check;
+                    }
+                    report.violations.push(violation);
+                }
+
+                for regression in self.check_metadata(Path::new(workspace), branch)? {
+                    if inline {
+                        eprintln!("{}", regression);
+                    }
+                    failed |= self.check;
+                    report.metadata.push(regression);
+                }
+
+                if self.check {
+                    if let Some(missing) = self.check_changelog(Path::new(workspace), branch)? {
+                        // A shared changelog is reported once;
+                        if !report.changelog.iter().any(|reported| {
+                            reported.changelog == missing.changelog
+                                && reported.version == missing.version
+                        }) {
+                            if inline {
+                                eprintln!("{}", missing);
+                            }
+                            failed = true;
+                            report.changelog.push(missing);
+                        }
+                    }
+                }
+
+                // Strict checks reject bumps skipping versions, unless they are intentional;
+                if self.check && self.strict && !self.allow_version_jump {
+                    if let Some(jump) = self.check_version_jump(Path::new(workspace), branch)? {
+                        if human {
+                            eprintln!("{}", jump);
+                        }
+                        failed = true;
+                        report.jumps.push(jump);
+                    }
+                }
+
+                // Bumps without source changes create pointless releases, unless all members are
+                // bumped together;
+                if self.check && unified.is_none() {
+                    if let Some(bump) =
+                        self.check_unchanged_bump(Path::new(workspace), branch, &changes[branch])?
+                    {
+                        self.warn(bump.to_string())?;
+                        report.unchanged_bumps.push(bump);
+                    }
+                }
+            }
+
+            if outdated.is_empty() {
+                if self.force && unified.is_some() {
+                    unified_bump = unified_bump.max(Some((self.semver, "forced")));
+                } else if self.force {
+                    // force an update even if the workspace version is already updated;
+                    push_edit(
+                        &mut state.manifests,
+                        &mut reasons,
+                        self.prepare_bump(PathBuf::from(workspace), self.semver)?,
+                        "forced",
+                    )?;
+                } else if self.fix && below_floor.is_some() && unified.is_some() {
+                    unified_bump = unified_bump.max(Some((SemVer::Patch, "floor")));
+                } else if self.fix && below_floor.is_some() {
+                    // `prepare_bump` raises the version to the floor;
+                    push_edit(
+                        &mut state.manifests,
+                        &mut reasons,
+                        self.prepare_bump(PathBuf::from(workspace), SemVer::Patch)?,
+                        "floor",
+                    )?;
+                } else if !added
+                    && below_floor.is_none()
+                    && !report
+                        .mismatched
+                        .iter()
+                        .any(|mismatch| mismatch.cargo_toml == cargo_toml)
+                {
+                    report.up_to_date.push(cargo_toml);
+                }
+                report.below_floor.extend(below_floor);
+                continue;
+            }
+
+            if self.check {
+                // Only outdated crates missing from the baseline, and not waived by a commit
+                // message, fail the check;
+                let mut waived = false;
+                for krate in outdated.iter() {
+                    let entry = BaselineEntry::outdated(krate);
+                    if self.baseline.contains(&entry) {
+                        report.tolerated.push(entry);
+                    } else if waived {
+                        continue;
+                    } else if let Some(waiver) = self.find_waiver(Path::new(workspace))? {
+                        report.waivers.push(waiver);
+                        waived = true;
+                    } else {
+                        new_outdated = true;
+                    }
+                }
+                failed |= new_outdated;
+            } else if self.fix {
+                // A single bump must satisfy every target branch;
+                let semver = outdated
+                    .iter()
+                    .map(|krate| krate.suggested_semver)
+                    .max()
+                    .unwrap_or(self.semver);
+                if unified.is_some() {
+                    unified_bump = unified_bump.max(Some((semver, "outdated")));
+                } else {
+                    push_edit(
+                        &mut state.manifests,
+                        &mut reasons,
+                        self.prepare_bump(PathBuf::from(workspace), semver)?,
+                        "outdated",
+                    )?;
+                }
+            } else if self.warn {
+                if human {
+                    outdated
+                        .iter()
+                        .for_each(|krate| eprintln!("{}", krate.describe()));
+                }
+            } else if human {
+                outdated
+                    .iter()
+                    .for_each(|krate| println!("{}", krate.describe()));
+            }
+
+            report.outdated.extend(outdated);
+            report.below_floor.extend(below_floor);
+        }
+
+        // Bump all members to the same version, or align the mismatched ones with `--fix`;
+        if let Some((_, version)) = &unified {
+            let bump = match unified_bump {
+                Some((semver, reason)) => {
+                    let mut new_version = version.clone();
+                    new_version.bump(semver);
+                    Some((new_version, reason))
+                }
+                None if self.fix && !report.mismatched.is_empty() => {
+                    Some((version.clone(), "unified"))
+                }
+                None => None,
+            };
+
+            if let Some((mut new_version, reason)) = bump {
+                for workspace in self.model.members().iter() {
+                    if let Some(pkg) = &self.model.member(Path::new(workspace))?.manifest.package {
+                        new_version.raise_to(self.floors.get(&pkg.name));
+                    }
+                }
+                for edit in self.prepare_unified(&new_version)? {
+                    state.manifests.push(edit);
+                    reasons.push(reason);
+                }
+            }
+        }
+
+        // Bump, and list, the crates in an order they can be published in;
+        if state.manifests.len() > 1 {
+            let graph = self.member_graph(&BTreeSet::new())?;
+            let selected = graph
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| state.manifests.iter().any(|edit| edit.name == node.name))
+                .map(|(i, _)| i)
+                .collect();
+
+            let mut bumps = state
+                .manifests
+                .drain(..)
+                .zip(reasons.drain(..))
+                .collect::<Vec<_>>();
+            let mut ordered = Vec::new();
+            for i in graph.publish_order(&selected)? {
+                if let Some(at) = bumps
+                    .iter()
+                    .position(|(edit, _)| edit.name == graph.nodes[i].name)
+                {
+                    ordered.push(bumps.remove(at));
+                }
+            }
+            // Edits of no single crate, i.e. of `workspace.package.version`, come first;
+            for (edit, reason) in bumps.into_iter().chain(ordered) {
+                state.manifests.push(edit);
+                reasons.push(reason);
+            }
+        }
+
+        // Bump every crate or none of them, once all bumps are known;
+        let mut path_dep_edits = Vec::new();
+        if self.plan.is_none() {
+            self.require_changelog_entries(&state.manifests)?;
+            self.pre_fix(&state.manifests)?;
+            self.apply_edits(&state.manifests)?;
+            // Record the edits as soon as they are written, so that `cargo cvm undo` can restore
+            // them if a later step fails; a dry run wrote nothing to undo;
+            if !state.manifests.is_empty() && self.dry_run.is_none() {
+                state.save(&self.git.git_dir())?;
+            }
+            // Add the versions after bumping, so that they are the new ones;
+            if self.fix_path_deps {
+                path_dep_edits = self.fix_path_deps(&mut report.path_deps, &state.manifests)?;
+                if !path_dep_edits.is_empty() && self.dry_run.is_none() {
+                    FixState {
+                        commit: None,
+                        manifests: state
+                            .manifests
+                            .iter()
+                            .chain(&path_dep_edits)
+                            .cloned()
+                            .collect(),
+                    }
+                    .save(&self.git.git_dir())?;
+                }
+            }
+            self.print_dry_run();
+            if state.manifests.len() > 1 && human {
+                let names = state
+                    .manifests
+                    .iter()
+                    .map(|edit| edit.name.as_str())
+                    .collect::<Vec<&str>>();
+                println!("publish order: {}", names.join(" → "));
+            }
+        }
+        if self.check && report.path_deps.iter().any(|dep| !dep.fixed) {
+            failed = true;
+        }
+
+        for branch in self.all_branches()?.iter() {
+            let target = self.target_name(branch);
+            let outdated = report
+                .outdated
+                .iter()
+                .filter(|krate| krate.target == target)
+                .count();
+            report.targets.push(TargetStatus { target, outdated });
+        }
+
+        report.bumps = state
+            .manifests
+            .iter()
+            .zip(reasons.iter())
+            .map(|(edit, reason)| Bump {
+                krate: edit.name.clone(),
+                cargo_toml: edit.path.clone(),
+                old_version: edit.old_version.clone(),
+                new_version: edit.new_version.clone(),
+                reason: reason.to_string(),
+            })
+            .collect();
+
+        // A crate removed compared to several target branches is listed once;
+        if self.check {
+            for branch in self.target_branches().iter() {
+                for (cargo_toml, name) in self.removed_crates(branch)? {
+                    if !report.removed.contains(&cargo_toml) {
+                        report.removed.push(cargo_toml.clone());
+                    }
+                    report.crates.insert(cargo_toml, name);
+                }
+            }
+        }
+        if grouped {
+            report.print_grouped(self.show_ok);
+        }
+
+        // Relative to the repository root, as the findings are, so that they match;
+        let manifests = self
+            .members_by_name()
+            .iter()
+            .map(|workspace| self.get_repo_relative_path(&Path::new(workspace).join("Cargo.toml")))
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        report.print(
+            self.output,
+            self.template.as_deref(),
+            &self.root_dir()?,
+            &manifests,
+        )?;
+
+        if let Some(path) = &self.plan {
+            return self.write_plan(path, report.bumps);
+        }
+
+        if self.record_baseline {
+            let mut baseline = Baseline {
+                entries: report
+                    .outdated
+                    .iter()
+                    .map(BaselineEntry::outdated)
+                    .chain(report.violations.iter().map(BaselineEntry::violation))
+                    .collect(),
+            };
+            baseline.save(&std::env::current_dir()?)?;
+            println!(
+                "Recorded {} finding(s) in {}",
+                baseline.entries.len(),
+                baseline::BASELINE_FILE
+            );
+            return Ok(());
+        }
+
+        // Point out entries that can be burned down, e.g. after a crate was bumped;
+        let resolved = self
+            .baseline
+            .entries
+            .iter()
+            .filter(|entry| !report.tolerated.contains(entry))
+            .count();
+        if self.check && resolved > 0 && human {
+            eprintln!(
+                "{} entries of {} no longer apply; run `cargo cvm baseline` to remove them",
+                resolved,
+                baseline::BASELINE_FILE
+            );
+        }
+
+        if let Some(path) = &self.metrics {
+            metrics::write_textfile(path, &metrics::render(&report, &manifests))?;
+        }
+
+        // A failed notification must not fail the run;
+        if let Some(url) = &self.notify_webhook {
+            if let Err(e) = notify::notify(url, &report, &self.fetcher.net_config()) {
+                eprintln!("{}", tr("warning", &[("message", &e)]));
+            }
+        }
+
+        if failed {
+            // `exit` skips destructors, so release the lock and close the trace first;
+            drop(lock);
+            trace::finish();
+            if new_outdated {
+                eprintln!("{}", tr("outdated-found", &[]));
+            } else if !report.jumps.is_empty() {
+                eprintln!("{}", tr("jump-found", &[]));
+            } else if report.unversioned.iter().any(|krate| krate.required) {
+                eprintln!("{}", tr("unversioned-found", &[]));
+            } else if !report.below_floor.is_empty() {
+                eprintln!("{}", tr("below-floor-found", &[]));
+            } else if !report.mismatched.is_empty() {
+                eprintln!("{}", tr("mismatch-found", &[]));
+            } else if !report.metadata.is_empty() {
+                eprintln!("{}", tr("metadata-found", &[]));
+            } else if !report.incomplete.is_empty() {
+                eprintln!("{}", tr("incomplete-found", &[]));
+            } else if !report.changelog.is_empty() {
+                eprintln!("{}", tr("changelog-found", &[]));
+            } else if report.path_deps.iter().any(|dep| !dep.fixed) {
+                eprintln!("{}", tr("path-dep-found", &[]));
+            } else {
+                eprintln!("{}", tr("release-train-found", &[]));
+            }
+            std::process::exit(1)
+        }
+
+        // Refuse to leave a version behind the target branch, keeping the edits undoable;
+        if self.since.is_none() {
+            let mut regressions = Vec::new();
+            for workspace in self.members_by_name().iter() {
+                for branch in self.member_branches(Path::new(workspace))?.iter() {
+                    if let Some(regression) =
+                        self.check_version_regression(Path::new(workspace), branch)?
+                    {
+                        regressions.push(regression);
+                    }
+                }
+            }
+
+            if !regressions.is_empty() {
+                drop(lock);
+                regressions
+                    .iter()
+                    .for_each(|regression| eprintln!("{}", regression));
+                eprintln!("Found version behind the target branch, exiting process unsuccessfully");
+                std::process::exit(1)
+            }
+        }
+
+        // Nothing was written, so there is nothing to package, record, or undo;
+        if self.dry_run.is_some() {
+            return Ok(());
+        }
+
+        if !state.manifests.is_empty() {
+            self.preflight(&state)
+                .and_then(|()| self.post_fix(&state))?;
+        }
+
+        if (self.force || self.fix || self.fix_path_deps) && self.commit {
+            state.commit = Some(self.commit_changes("updated crate version(s)")?.to_string());
+        }
+        // After the version commit, so that the log names it;
+        if !state.manifests.is_empty() {
+            self.record_history(&state.manifests, &reasons)?;
+        }
+
+        // Record the version commit, so that `cargo cvm undo` drops it as well;
+        if state.commit.is_some() {
+            state.manifests.append(&mut path_dep_edits);
+            state.save(&self.git.git_dir())?;
+        }
+
+        Ok(())
+    }
+
+    /// Append the version changes to the audit log and stage it, naming HEAD, i.e. the version
+    /// commit if one was made;
+    pub fn record_history(&self, edits: &[ManifestEdit], reasons: &[&str]) -> Result<(), Error> {
+        let root = self.workdir()?;
+
+        let user = self.new_signature()?;
+        let commit = self.git.resolve("HEAD")?.to_string();
+        let timestamp = history::now_rfc3339();
+
+        // The log records crates, so an edit of `workspace.package.version` is recorded for
+        // each member inheriting it;
+        let mut entries = Vec::new();
+        for (edit, reason) in edits.iter().zip(reasons.iter()) {
+            for edit in self.member_edits(edit)? {
+                entries.push(HistoryEntry {
+                    krate: edit.name,
+                    old_version: edit.old_version,
+                    new_version: edit.new_version,
+                    reason: reason.to_string(),
+                    commit: commit.clone(),
+                    timestamp: timestamp.clone(),
+                    user: user.clone(),
+                });
+            }
+        }
+
+        history::append(&root, &entries)?;
+
+        self.git.add(&[PathBuf::from(history::HISTORY_FILE)])
+    }
+
+    /// Build the graph of the members, their versions, and their dependencies on each other,
+    /// marking the crates that are outdated compared to any of their target branches;
+    pub fn dependency_graph(&self) -> Result<Graph, Error> {
+        if self.since.is_none() {
+            self.fetch_targets()?;
+        }
+
+        let mut changes = BTreeMap::new();
+        for branch in self.all_branches()?.iter() {
+            changes.insert(branch.clone(), self.changed_src_files(branch)?);
+        }
+
+        // Frozen and unversioned crates are never outdated;
+        let mut outdated = BTreeSet::new();
+        for workspace in self.model.members().iter() {
+            if self.frozen_crate(Path::new(workspace))?.is_some()
+                || self.model.member(Path::new(workspace))?.omits_version()
+            {
+                continue;
+            }
+            for branch in self.member_branches(Path::new(workspace))?.iter() {
+                if self
+                    .is_version_outdated(PathBuf::from(workspace), branch, &changes[branch])?
+                    .is_some()
+                {
+                    outdated.insert(workspace.clone());
+                }
+            }
+        }
+
+        self.member_graph(&outdated)
+    }
+
+    /// Build the graph of the members with a package, marking the members in `outdated`;
+    fn member_graph(&self, outdated: &BTreeSet<String>) -> Result<Graph, Error> {
+        let mut members = Vec::new();
+        for workspace in self.members_by_name().iter() {
+            let member = self.model.member(Path::new(workspace))?;
+            let name = match &member.manifest.package {
+                Some(pkg) => pkg.name.clone(),
+                None => continue,
+            };
+
+            let version = if member.omits_version() {
+                String::from("unversioned")
+            } else {
+                self.get_workspace_version(Path::new(workspace))?
+                    .to_string()
+            };
+            members.push((
+                Node {
+                    name,
+                    version,
+                    outdated: outdated.contains(workspace),
+                },
+                deps::dependencies(&member.doc),
+            ));
+        }
+
+        Ok(Graph::new(members))
+    }
+
+    /// Returns the edit, or for an edit of `workspace.package.version` an edit per member that
+    /// inherits it, naming the member and its manifest, which is not rewritten itself;
+    fn member_edits(&self, edit: &ManifestEdit) -> Result<Vec<ManifestEdit>, Error> {
+        if edit.name != WORKSPACE_PACKAGE {
+            return Ok(vec![edit.clone()]);
+        }
+
+        let mut edits = Vec::new();
+        for workspace in self.model.members().iter() {
+            let member = self.model.member(Path::new(workspace))?;
+            if let (true, Some(pkg)) = (member.inherits_version(), member.manifest.package.as_ref())
+            {
+                edits.push(ManifestEdit {
+                    name: pkg.name.clone(),
+                    path: member.path.clone(),
+                    old_version: edit.old_version.clone(),
+                    new_version: edit.new_version.clone(),
+                    original: member.contents.clone(),
+                });
+            }
+        }
+        Ok(edits)
+    }
+
+    /// Returns the edits of the bumped crates, see `member_edits`, each once and in an order
+    /// they can be published in, for the hooks and publishing, which deal with crates;
+    fn crate_edits(&self, edits: &[ManifestEdit]) -> Result<Vec<ManifestEdit>, Error> {
+        let mut crates: Vec<ManifestEdit> = Vec::new();
+        for edit in edits.iter() {
+            for edit in self.member_edits(edit)? {
+                if !crates.iter().any(|other| other.name == edit.name) {
+                    crates.push(edit);
+                }
+            }
+        }
+        if crates.len() < 2 {
+            return Ok(crates);
+        }
+
+        let graph = self.member_graph(&BTreeSet::new())?;
+        let selected = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| crates.iter().any(|edit| edit.name == node.name))
+            .map(|(i, _)| i)
+            .collect();
+        let mut ordered = Vec::new();
+        for i in graph.publish_order(&selected)? {
+            if let Some(at) = crates
+                .iter()
+                .position(|edit| edit.name == graph.nodes[i].name)
+            {
+                ordered.push(crates.remove(at));
+            }
+        }
+        ordered.append(&mut crates);
+        Ok(ordered)
+    }
+
+    /// Run `cargo package` for the bumped crates if `--preflight` is set, so that packaging
+    /// errors, e.g. missing files, surface before the bumps are committed rather than when
+    /// publishing; on failure, the caller keeps the edits for `cargo cvm undo`;
+    ///
+    /// Cargo 1.83 and later package the crates together, resolving their dependencies on each
+    /// other's new, unpublished versions; older versions package them one by one, which fails
+    /// for a crate depending on the new version of another.
+    fn preflight(&self, state: &FixState) -> Result<(), Error> {
+        let preflight = match self.preflight {
+            Some(preflight) => preflight,
+            None => return Ok(()),
+        };
+
+        // An edit of `workspace.package.version` bumps the members inheriting it;
+        let packages = self
+            .crate_edits(&state.manifests)?
+            .into_iter()
+            .map(|edit| edit.name)
+            .collect::<Vec<String>>();
+
+        let groups = if cargo_minor_version().is_some_and(|minor| minor >= 83) {
+            vec![packages]
+        } else {
+            packages.into_iter().map(|package| vec![package]).collect()
+        };
+
+        let mut failed = Vec::new();
+        for group in groups.iter().filter(|group| !group.is_empty()) {
+            let mut command = cargo_command();
+            // The bumped manifests are not committed yet;
+            command.current_dir(self.model.root());
+            command.args(["package", "--allow-dirty", "--quiet"]);
+            for package in group.iter() {
+                command.args(["--package", package]);
+            }
+            if preflight == Preflight::NoVerify {
+                command.arg("--no-verify");
+            }
+
+            let output = command
+                .output()
+                .map_err(|e| Error::msg(format!("Failed to run `cargo package`: {}", e)))?;
+            if !output.status.success() {
+                eprintln!(
+                    "packaging {} failed:\n{}",
+                    group.join(", "),
+                    String::from_utf8_lossy(&output.stderr).trim_end()
+                );
+                failed.extend(group.iter().map(String::as_str));
+            }
+        }
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::msg(format!(
+            "Packaging failed for {}; the bumps are not committed, run `cargo cvm undo` to revert them",
+            failed.join(", ")
+        )))
+    }
+
+    /// Fail before any manifest is written if the changelog of a crate about to be bumped has no
+    /// entry for its new version, see `[changelog]` in `.cvm.toml`; an edit of
+    /// `workspace.package.version` requires an entry in the changelog of the workspace root;
+    fn require_changelog_entries(&self, edits: &[ManifestEdit]) -> Result<(), Error> {
+        if !self.changelog.require {
+            return Ok(());
+        }
+
+        let mut missing = Vec::new();
+        for edit in edits.iter() {
+            let dir = edit.path.parent().unwrap_or_else(|| Path::new(""));
+            let path = dir.join(&self.changelog.file);
+            let contents = self
+                .fs
+                .read(&path)
+                .map(|contents| String::from_utf8_lossy(&contents).into_owned())
+                .unwrap_or_default();
+            if !self.changelog.has_entry(&contents, &edit.new_version) {
+                let path = path.strip_prefix(self.model.root()).unwrap_or(&path);
+                missing.push(format!(
+                    "{} {} in {:?}",
+                    edit.name,
+                    edit.new_version,
+                    path.display()
+                ));
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::msg(tr(
+            "changelog-missing",
+            &[("entries", &missing.join(", "))],
+        )))
+    }
+
+    /// Run `hooks.pre-fix` for each crate about to be bumped, failing the run before any manifest
+    /// is written if it fails for any of them;
+    fn pre_fix(&self, edits: &[ManifestEdit]) -> Result<(), Error> {
+        let failed = self.run_hook("pre-fix", self.hooks.pre_fix.as_deref(), edits)?;
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::msg(tr(
+            "pre-fix-failed",
+            &[("crates", &failed.join(", "))],
+        )))
+    }
+
+    /// Run `hooks.post-fix` for each bumped crate before the bumps are committed; on failure,
+    /// the caller keeps the edits for `cargo cvm undo`, as after a failed preflight;
+    fn post_fix(&self, state: &FixState) -> Result<(), Error> {
+        let failed = self.run_hook("post-fix", self.hooks.post_fix.as_deref(), &state.manifests)?;
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::msg(tr(
+            "post-fix-failed",
+            &[("crates", &failed.join(", "))],
+        )))
+    }
+
+    /// Run the hook `command` in the workspace root for each edit, with the variables of the
+    /// edit substituted and set as `CVM_*` environment variables, returning the crates it
+    /// failed for; nothing runs in a dry run, since no manifest is written;
+    fn run_hook(
+        &self,
+        hook: &str,
+        command: Option<&str>,
+        edits: &[ManifestEdit],
+    ) -> Result<Vec<String>, Error> {
+        let command = match command {
+            Some(command) if self.dry_run.is_none() => command,
+            _ => return Ok(Vec::new()),
+        };
+        let root = self.model.root();
+
+        let mut failed = Vec::new();
+        for edit in self.crate_edits(edits)?.iter() {
+            let manifest = edit.path.strip_prefix(root).unwrap_or(&edit.path);
+            let manifest = manifest.display().to_string();
+            let vars = [
+                ("crate", edit.name.as_str()),
+                ("old_version", edit.old_version.as_str()),
+                ("new_version", edit.new_version.as_str()),
+                ("manifest", manifest.as_str()),
+            ];
+            let script = Hooks::substitute(command, &vars);
+
+            let mut shell = shell_command(&script);
+            shell.current_dir(root);
+            for (name, value) in vars.iter() {
+                shell.env(Hooks::variable(name), value);
+            }
+            let output = shell.output().map_err(|e| {
+                Error::msg(tr(
+                    "hook-not-run",
+                    &[
+                        ("hook", &hook),
+                        ("script", &format!("{:?}", script)),
+                        ("error", &e),
+                    ],
+                ))
+            })?;
+            if !output.status.success() {
+                let printed = format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                eprintln!(
+                    "{}",
+                    tr(
+                        "hook-failed",
+                        &[
+                            ("hook", &hook),
+                            ("crate", &edit.name),
+                            ("version", &edit.new_version),
+                            ("script", &script),
+                        ],
+                    )
+                );
+                if !printed.trim().is_empty() {
+                    eprintln!("{}", printed.trim_end());
+                }
+                failed.push(edit.name.clone());
+            }
+        }
+
+        Ok(failed)
+    }
+
+    /// Publish the crates bumped by the most recent --fix or --force run in their bump order,
+    /// i.e. dependencies first, waiting after each until the registry index lists it, so that
+    /// publishing its dependents resolves it; crates whose version is already listed are
+    /// skipped, so that a failed run can be resumed; `index` selects a registry other than
+    /// crates.io, for both `cargo publish` and the polling;
+    pub fn publish(
+        &self,
+        index: Option<&str>,
+        timeout: Duration,
+        interval: Duration,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let state = match FixState::load(&self.git.git_dir())? {
+            Some(state) => state,
+            None => {
+                eprintln!("No fix run to publish the crates of.");
+                std::process::exit(1)
+            }
+        };
+        let registry = SparseIndex::new(
+            index.unwrap_or(CRATES_IO_INDEX),
+            &self.fetcher.net_config(),
+            Some(&self.artifact_dir.join("index")),
+        )?;
+
+        // Manifests whose path dependencies were given versions were not bumped;
+        let edits = state
+            .manifests
+            .into_iter()
+            .filter(|edit| edit.old_version != edit.new_version)
+            .collect::<Vec<ManifestEdit>>();
+        // An edit of `workspace.package.version` publishes the members inheriting it;
+        let edits = self.crate_edits(&edits)?;
+        for (i, edit) in edits.iter().enumerate() {
+            if is_unpublished(&self.model.manifest(&edit.path)?.doc) {
+                println!("{} sets `publish = false`, skipping", edit.name);
+                continue;
+            }
+            if registry
+                .versions(&edit.name)?
+                .iter()
+                .any(|version| version == &edit.new_version)
+            {
+                println!(
+                    "{} {} is already published, skipping",
+                    edit.name, edit.new_version
+                );
+                continue;
+            }
+
+            let mut args = vec![String::from("publish"), String::from("--manifest-path")];
+            args.push(edit.path.display().to_string());
+            // `cargo publish` takes the sparse index with its `sparse+` prefix;
+            if let Some(index) = index {
+                args.push(String::from("--index"));
+                args.push(format!("sparse+{}", index.trim_start_matches("sparse+")));
+            }
+            if dry_run {
+                args.push(String::from("--dry-run"));
+            }
+            println!("publishing {} {}", edit.name, edit.new_version);
+            let status = cargo_command()
+                .args(&args)
+                .status()
+                .map_err(|e| Error::msg(format!("Failed to run `cargo publish`: {}", e)))?;
+            if !status.success() {
+                return Err(Error::msg(format!(
+                    "`cargo {}` failed with {}",
+                    args.join(" "),
+                    status
+                )));
+            }
+
+            // The last crate has no dependents left to publish;
+            if !dry_run && i + 1 < edits.len() {
+                println!(
+                    "waiting for {} {} to appear in {}",
+                    edit.name,
+                    edit.new_version,
+                    index.unwrap_or(CRATES_IO_INDEX)
+                );
+                registry.wait_for(&edit.name, &edit.new_version, timeout, interval)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the recorded version changes, optionally for a single crate;
+    pub fn print_history(&self, krate: Option<&str>) -> Result<(), Error> {
+        let root = self.workdir()?;
+
+        // The log records crate names; a member path names the crate of the member, while
+        // names of removed crates are kept as given;
+        let krate = krate.map(|krate| match self.member_named(krate) {
+            Ok(workspace) => self.crate_name(&workspace),
+            Err(_) => krate.to_string(),
+        });
+        for entry in history::read(&root, krate.as_deref())? {
+            println!(
+                "{} {} {} -> {} ({}) on {:.7} by {}",
+                entry.timestamp,
+                entry.krate,
+                entry.old_version,
+                entry.new_version,
+                entry.reason,
+                entry.commit,
+                entry.user
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Render release notes for the crates bumped by the most recent fix run, listing the
+    /// commits since the base commit that touched each crate and their authors;
+    pub fn release_notes(&self, template: Option<&str>) -> Result<String, Error> {
+        let state = match FixState::load(&self.git.git_dir())? {
+            Some(state) => state,
+            None => {
+                eprintln!("No fix run to generate release notes for.");
+                std::process::exit(1)
+            }
+        };
+
+        let base = self.get_base_commit(&self.target_branches()[0])?;
+        let mut release_notes = ReleaseNotes::default();
+
+        for edit in state.manifests.iter() {
+            let path = self.get_repo_relative_path(&edit.path)?;
+            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+            let mut commits = Vec::new();
+            let mut contributors: Vec<String> = Vec::new();
+
+            for commit in self.git.log(self.head_commit()?, base)? {
+                // Skip the commit created by `--commit` for the bump itself;
+                if state.commit.as_deref() == Some(commit.id.to_string().as_str())
+                    || !self.commit_touches(&commit, dir)?
+                {
+                    continue;
+                }
+
+                if !contributors.contains(&commit.author) {
+                    contributors.push(commit.author.clone());
+                }
+
+                commits.push(CommitNote {
+                    id: commit.short_id,
+                    subject: commit.summary,
+                    author: commit.author,
+                });
+            }
+
+            release_notes.crates.push(CrateNotes {
+                name: edit.name.clone(),
+                old_version: edit.old_version.clone(),
+                new_version: edit.new_version.clone(),
+                path: path.display().to_string(),
+                commits,
+                contributors,
+            });
+        }
+
+        notes::render(&release_notes, template)
+    }
+
+    /// Returns who new commits are by, as `user.name <user.email>`;
+    pub fn new_signature(&self) -> Result<String, Error> {
+        self.git.signature()
+    }
+
+    /// Fail if HEAD is detached and no `--commit-branch` says where to commit;
+    pub fn check_commit_target(&self) -> Result<(), Error> {
+        if self.git.head_detached()? && self.commit_branch.is_none() {
+            return Err(Error::msg(
+                "HEAD is detached, e.g. in a CI checkout; pass --commit-branch <branch> to commit onto a branch, or omit --commit to only stage the changes",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Point HEAD at `--commit-branch` if it is detached, creating the branch at the checked-out
+    /// commit; the index and working tree are kept, so the next commit lands on the branch;
+    fn attach_head(&self) -> Result<(), Error> {
+        self.check_commit_target()?;
+        let branch = match &self.commit_branch {
+            Some(branch) if self.git.head_detached()? => branch,
+            _ => return Ok(()),
+        };
+
+        self.git.attach_head(branch)
+    }
+
+    pub fn commit_changes(&self, msg: &str) -> Result<Oid, Error> {
+        let _span = trace::span("commit", msg.lines().next().unwrap_or_default());
+        self.attach_head()?;
+
+        let new_commit = self.git.commit(msg)?;
+
+        println!("commit {:?} includes version updates", new_commit);
+        Ok(new_commit)
+    }
+
+    /// Revert the manifest edits of the most recent fix run and unstage them;
+    pub fn undo(&self) -> Result<(), Error> {
+        let _lock = RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?;
+
+        let state = match FixState::load(&self.git.git_dir())? {
+            Some(state) => state,
+            None => {
+                eprintln!("No fix run to undo.");
+                std::process::exit(1)
+            }
+        };
+
+        // Drop the version commit, keeping its changes so that they can be restored below;
+        if let Some(commit) = &state.commit {
+            if self.git.resolve("HEAD")?.to_string() != *commit {
+                eprintln!(
+                    "HEAD has moved since commit {} was created; refusing to undo.",
+                    commit
+                );
+                std::process::exit(1)
+            }
+            self.git.reset_soft(self.git.resolve("HEAD^")?)?;
+        }
+
+        let paths = self
+            .written_paths(&state.manifests)?
+            .iter()
+            .map(|path| self.get_repo_relative_path(path))
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+
+        // Unstage the manifests;
+        self.git.unstage(&paths)?;
+
+        // Edits adding versions to path dependencies changed no version;
+        let reverted = state
+            .manifests
+            .iter()
+            .filter(|edit| edit.old_version != edit.new_version)
+            .map(|edit| ManifestEdit {
+                name: edit.name.clone(),
+                path: edit.path.clone(),
+                old_version: edit.new_version.clone(),
+                new_version: edit.old_version.clone(),
+                original: String::new(),
+            })
+            .collect::<Vec<ManifestEdit>>();
+        self.record_history(&reverted, &vec!["undo"; reverted.len()])?;
+
+        for edit in state.manifests.iter() {
+            self.fs.write(&edit.path, &edit.original)?;
+            self.model.update(&edit.path, edit.original.clone())?;
+            if edit.old_version == edit.new_version {
+                println!("restored {:?}", edit.path.display());
+            } else {
+                println!(
+                    "restored version {} (was {}) in {:?}",
+                    edit.old_version,
+                    edit.new_version,
+                    edit.path.display()
+                );
+            }
+        }
+
+        FixState::clear(&self.git.git_dir())?;
+
+        Ok(())
+    }
+
+    /// Print the `[package]` field `key` of every member, then set it to `new` across the
+    /// workspace if given; otherwise report members declaring different values, failing if
+    /// `strict` is set; members that do not declare the field do not conflict;
+    ///
+    /// Members inheriting the field keep inheriting it, and `[workspace.package]` is updated
+    /// instead.
+    pub fn manage_package_field(
+        &self,
+        key: &str,
+        new: Option<&str>,
+        commit: bool,
+        strict: bool,
+    ) -> Result<(), Error> {
+        let root = std::env::current_dir()?.join("Cargo.toml");
+        let mut docs = BTreeMap::new();
+        docs.insert(root.clone(), fields::load(&*self.fs, &root)?);
+        let workspace_value = fields::get(&docs[&root], key, true);
+
+        // Members grouped by the value they declare or inherit;
+        let mut values: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut members = Vec::new();
+
+        for workspace in self.model.members().iter() {
+            let path = std::env::current_dir()?.join(workspace).join("Cargo.toml");
+            if !docs.contains_key(&path) {
+                docs.insert(path.clone(), fields::load(&*self.fs, &path)?);
+            }
+
+            let value = fields::get(&docs[&path], key, false);
+            let resolved = match &value {
+                FieldValue::Inherited => workspace_value.clone(),
+                value => value.clone(),
+            };
+            let name = self.get_repo_relative_path(Path::new(workspace))?;
+
+            println!("{}: {} = {}", name.display(), key, value);
+            if resolved != FieldValue::Missing {
+                values
+                    .entry(resolved.to_string())
+                    .or_default()
+                    .push(name.display().to_string());
+            }
+            members.push((path, name, value, resolved));
+        }
+
+        let new = match new {
+            Some(new) => new,
+            None if values.len() > 1 => {
+                for (value, names) in values.iter() {
+                    eprintln!("{} = {}: {}", key, value, names.join(", "));
+                }
+                if strict {
+                    eprintln!("Members declare conflicting `{}` values", key);
+                    std::process::exit(1)
+                }
+                eprintln!("Members declare different `{}` values", key);
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+
+        let _lock = RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?;
+        let mut changed = Vec::new();
+
+        let inherited = members
+            .iter()
+            .any(|(_, _, value, _)| *value == FieldValue::Inherited);
+        if inherited || workspace_value != FieldValue::Missing {
+            if let Some(doc) = docs.get_mut(&root) {
+                if fields::set(doc, key, new, true)? {
+                    println!("[workspace.package]: {} {} → {}", key, workspace_value, new);
+                    changed.push(root.clone());
+                }
+            }
+        }
+
+        for (path, name, value, resolved) in members.iter() {
+            if *value == FieldValue::Inherited {
+                continue;
+            }
+            if let Some(doc) = docs.get_mut(path) {
+                if fields::set(doc, key, new, false)? {
+                    println!("{}: {} {} → {}", name.display(), key, resolved, new);
+                    changed.push(path.clone());
+                }
+            }
+        }
+
+        if changed.is_empty() {
+            println!("All members already have {} = {}", key, new);
+            return Ok(());
+        }
+
+        changed.sort();
+        changed.dedup();
+        for path in changed.iter() {
+            fields::save(&*self.fs, &docs[path], path)?;
+        }
+        self.finish_edits(&changed, commit, &format!("set {} to {}", key, new))
+    }
+
+    /// Report external dependencies that members require at different versions, and if `fix`
+    /// is set, align them to the version in `[workspace.dependencies]` or else the highest;
+    pub fn align_dependencies(&self, fix: bool, commit: bool) -> Result<(), Error> {
+        let root = std::env::current_dir()?.join("Cargo.toml");
+        let root_doc = fields::load(&*self.fs, &root)?;
+        let mut docs = BTreeMap::new();
+
+        // Requirements of each package, with the manifest declaring them;
+        let mut packages: BTreeMap<String, Vec<(PathBuf, PathBuf, Requirement)>> = BTreeMap::new();
+
+        for workspace in self.model.members().iter() {
+            let path = std::env::current_dir()?.join(workspace).join("Cargo.toml");
+            let name = self.get_repo_relative_path(Path::new(workspace))?;
+            let member = self.model.member(Path::new(workspace))?;
+
+            for requirement in deps::requirements(&member.doc) {
+                packages
+                    .entry(requirement.package.clone())
+                    .or_default()
+                    .push((path.clone(), name.clone(), requirement));
+            }
+            docs.insert(path, member.doc.clone());
+        }
+
+        let _lock = if fix {
+            Some(RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?)
+        } else {
+            None
+        };
+        let mut misaligned = false;
+        let mut changed = Vec::new();
+
+        for (package, requirements) in packages.iter() {
+            let mut versions = requirements
+                .iter()
+                .map(|(_, _, requirement)| requirement.version.as_str())
+                .collect::<Vec<&str>>();
+            versions.sort_by(|a, b| deps::compare(a, b));
+            versions.dedup();
+
+            if versions.len() < 2 {
+                continue;
+            }
+            misaligned = true;
+
+            let declared = requirements
+                .iter()
+                .map(|(_, name, requirement)| {
+                    format!(
+                        "{} ({} {})",
+                        requirement.version,
+                        name.display(),
+                        requirement.table
+                    )
+                })
+                .collect::<Vec<String>>();
+            println!("{}: {}", package, declared.join(", "));
+
+            if !fix {
+                continue;
+            }
+
+            let workspace_version = deps::workspace_version(&root_doc, package);
+            let target = match &workspace_version {
+                Some(version) => version.as_str(),
+                None => versions[versions.len() - 1],
+            };
+
+            for (path, name, requirement) in requirements.iter() {
+                if requirement.version == target {
+                    continue;
+                }
+                if let Some(doc) = docs.get_mut(path) {
+                    deps::set_version(doc, requirement, target);
+                    println!(
+                        "{}: {} {} → {}",
+                        name.display(),
+                        requirement.key,
+                        requirement.version,
+                        target
+                    );
+                    changed.push(path.clone());
+                }
+            }
+        }
+
+        if !misaligned {
+            println!("All dependency versions are aligned");
+            return Ok(());
+        }
+
+        if !fix {
+            eprintln!("Found misaligned dependency versions; run with --fix to align them");
+            std::process::exit(1)
+        }
+
+        changed.sort();
+        changed.dedup();
+        for path in changed.iter() {
+            fields::save(&*self.fs, &docs[path], path)?;
+        }
+        self.finish_edits(&changed, commit, "align dependency versions")
+    }
+
+    /// Move external dependencies declared by at least `min_members` members into the root
+    /// `[workspace.dependencies]`, replacing them with `workspace = true` references;
+    pub fn hoist_dependencies(&self, min_members: usize, commit: bool) -> Result<(), Error> {
+        let root = std::env::current_dir()?.join("Cargo.toml");
+        let mut docs = BTreeMap::new();
+        docs.insert(root.clone(), fields::load(&*self.fs, &root)?);
+
+        if docs[&root].get("workspace").is_none() {
+            eprintln!("`cargo cvm hoist-deps` must be run in the root of a workspace");
+            std::process::exit(1)
+        }
+
+        // Requirements of each package, with the manifest declaring them;
+        let mut packages: BTreeMap<String, Vec<(PathBuf, PathBuf, Requirement)>> = BTreeMap::new();
+
+        for workspace in self.model.members().iter() {
+            let path = std::env::current_dir()?.join(workspace).join("Cargo.toml");
+            let name = self.get_repo_relative_path(Path::new(workspace))?;
+            if !docs.contains_key(&path) {
+                docs.insert(path.clone(), fields::load(&*self.fs, &path)?);
+            }
+
+            for requirement in deps::requirements(&docs[&path]) {
+                packages
+                    .entry(requirement.package.clone())
+                    .or_default()
+                    .push((path.clone(), name.clone(), requirement));
+            }
+        }
+
+        let _lock = RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?;
+        let mut changed = Vec::new();
+
+        for (package, requirements) in packages.iter() {
+            let mut members = requirements
+                .iter()
+                .map(|(_, name, _)| name)
+                .collect::<Vec<&PathBuf>>();
+            members.dedup();
+            if members.len() < min_members {
+                continue;
+            }
+
+            // `workspace = true` references the dependency by its key, so renames must match;
+            if requirements
+                .iter()
+                .any(|(_, _, requirement)| requirement.key != *package)
+            {
+                self.warn(format!(
+                    "skipping {}, which is renamed by some members",
+                    package
+                ))?;
+                continue;
+            }
+
+            // Default features can only be disabled in `[workspace.dependencies]`;
+            let default_features = requirements
+                .iter()
+                .map(|(path, _, requirement)| {
+                    !deps::disables_default_features(&docs[path], requirement)
+                })
+                .collect::<Vec<bool>>();
+            if default_features
+                .iter()
+                .any(|enabled| *enabled != default_features[0])
+            {
+                self.warn(format!(
+                    "skipping {}, whose default features are only disabled by some members",
+                    package
+                ))?;
+                continue;
+            }
+
+            // Referencing the workspace version would silently change a member requiring
+            // another one;
+            let root_doc = &docs[&root];
+            if let Some(version) = deps::workspace_version(root_doc, package) {
+                let differing = requirements
+                    .iter()
+                    .filter(|(_, _, requirement)| {
+                        deps::compare(&requirement.version, &version) != Ordering::Equal
+                    })
+                    .map(|(_, name, requirement)| {
+                        format!("{} requires {}", name.display(), requirement.version)
+                    })
+                    .collect::<Vec<String>>();
+                if !differing.is_empty() {
+                    self.warn(format!(
+                        "skipping {}, which [workspace.dependencies] sets to {} while {}; align the versions first, e.g. with `cargo cvm align-deps --fix`",
+                        package,
+                        version,
+                        differing.join(", ")
+                    ))?;
+                    continue;
+                }
+            } else {
+                let version = requirements
+                    .iter()
+                    .map(|(_, _, requirement)| requirement.version.as_str())
+                    .max_by(|a, b| deps::compare(a, b))
+                    .unwrap_or_default()
+                    .to_string();
+
+                if let Some(doc) = docs.get_mut(&root) {
+                    deps::add_workspace_dependency(doc, package, &version, default_features[0]);
+                }
+                println!("[workspace.dependencies]: {} = {}", package, version);
+                changed.push(root.clone());
+            }
+
+            for (path, name, requirement) in requirements.iter() {
+                if let Some(doc) = docs.get_mut(path) {
+                    deps::inherit(doc, requirement);
+                    println!(
+                        "{}: {} {} → workspace",
+                        name.display(),
+                        requirement.key,
+                        requirement.version
+                    );
+                    changed.push(path.clone());
+                }
+            }
+        }
+
+        if changed.is_empty() {
+            println!("No dependencies to hoist");
+            return Ok(());
+        }
+
+        changed.sort();
+        changed.dedup();
+        for path in changed.iter() {
+            fields::save(&*self.fs, &docs[path], path)?;
+        }
+        self.finish_edits(
+            &changed,
+            commit,
+            "hoist dependencies into [workspace.dependencies]",
+        )
+    }
+
+    /// Write the bumps that `--fix` would apply to a plan file for `cargo cvm apply`;
+    pub fn write_plan(&self, path: &Path, bumps: Vec<Bump>) -> Result<(), Error> {
+        let bumps = bumps
+            .into_iter()
+            .map(|bump| {
+                Ok(Bump {
+                    cargo_toml: self.get_repo_relative_path(&bump.cargo_toml)?,
+                    ..bump
+                })
+            })
+            .collect::<Result<Vec<Bump>, Error>>()?;
+
+        for bump in bumps.iter() {
+            println!(
+                "plan: {} {} → {} in {:?} ({})",
+                bump.krate,
+                bump.old_version,
+                bump.new_version,
+                bump.cargo_toml.display(),
+                bump.reason
+            );
+        }
+
+        let plan = Plan {
+            head: self.git.resolve("HEAD")?.to_string(),
+            bumps,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        plan.save(path)?;
+        println!(
+            "wrote plan with {} bump(s) to {:?}; apply it with `cargo cvm apply {}`",
+            plan.bumps.len(),
+            path.display(),
+            path.display()
+        );
+
+        Ok(())
+    }
+
+    /// Apply the bumps of a plan written by `cargo cvm plan`, refusing if HEAD or any of the
+    /// versions changed since;
+    pub fn apply_plan(&self, path: &Path, commit: bool, allow_dirty: bool) -> Result<(), Error> {
+        self.check_clean(!allow_dirty)?;
+        let lock = RepoLock::acquire(&self.git.git_dir(), self.lock_timeout)?;
+        let plan = Plan::load(path)?;
+        let root = self.workdir()?;
+
+        if commit {
+            self.check_commit_target()?;
+        }
+
+        let head = self.git.resolve("HEAD")?.to_string();
+        if head != plan.head {
+            drop(lock);
+            eprintln!(
+                "HEAD has moved since the plan was written on {}; run `cargo cvm plan` again.",
+                plan.head
+            );
+            std::process::exit(1)
+        }
+
+        // Check every bump before editing any manifest;
+        let mut state = FixState::default();
+        for bump in plan.bumps.iter() {
+            let cargo_toml = root.join(&bump.cargo_toml);
+            let (version, contents, table) = if bump.krate == WORKSPACE_PACKAGE {
+                (
+                    self.workspace_version()?,
+                    fields::read_manifest(&*self.fs, &cargo_toml)?,
+                    "[workspace.package]",
+                )
+            } else {
+                let member = self.model.manifest(&cargo_toml)?;
+                (member.version(false)?, member.contents.clone(), "[package]")
+            };
+
+            if version.to_string() != bump.old_version {
+                drop(lock);
+                eprintln!(
+                    "version of {} is {}, but the plan expects {}; run `cargo cvm plan` again.",
+                    bump.krate, version, bump.old_version
+                );
+                std::process::exit(1)
+            }
+
+            let new_version: Version = bump.new_version.clone().try_into()?;
+            if let Some(floor) = self.floors.get(&bump.krate) {
+                if new_version < *floor {
+                    drop(lock);
+                    eprintln!(
+                        "the plan bumps {} to {}, below its floor {} in .cvm.toml; run `cargo cvm plan` again.",
+                        bump.krate, new_version, floor
+                    );
+                    std::process::exit(1)
+                }
+            }
+
+            state.manifests.push(self.prepare_edit(
+                cargo_toml,
+                contents,
+                bump.krate.clone(),
+                &version,
+                &new_version,
+                table,
+            )?);
+        }
+
+        self.require_changelog_entries(&state.manifests)?;
+        self.pre_fix(&state.manifests)?;
+        self.apply_edits(&state.manifests)?;
+
+        // Nothing was written, so there is nothing to package, record, commit, or undo;
+        if self.dry_run.is_some() {
+            self.print_dry_run();
+            return Ok(());
+        }
+
+        if !state.manifests.is_empty() {
+            // Record the edits as soon as they are written, so that `cargo cvm undo` can restore
+            // them if a later step fails;
+            state.save(&self.git.git_dir())?;
+
+            self.preflight(&state)
+                .and_then(|()| self.post_fix(&state))?;
+            let reasons = plan
+                .bumps
+                .iter()
+                .map(|bump| bump.reason.as_str())
+                .collect::<Vec<&str>>();
+
+            if commit {
+                state.commit = Some(self.commit_changes("updated crate version(s)")?.to_string());
+                // Record the version commit, so that `cargo cvm undo` drops it as well;
+                state.save(&self.git.git_dir())?;
+            }
+            self.record_history(&state.manifests, &reasons)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the prefixes of the release tags of the crate in `workspace`, set by
+    /// `tag-prefix` in `[package.metadata.cvm]` as a string or an array of strings, e.g. for
+    /// tags like `rust-v0.9.1`, or else `tags::default_prefixes`;
+    pub fn tag_prefixes(&self, workspace: &Path) -> Result<Vec<String>, Error> {
+        let doc = &self.model.member(workspace)?.doc;
+
+        match fields::cvm_metadata(doc, "tag-prefix") {
+            Some(item) => {
+                let prefixes = match item.as_str() {
+                    Some(prefix) => Some(vec![prefix.to_string()]),
+                    None => item.as_array().and_then(|prefixes| {
+                        prefixes
+                            .iter()
+                            .map(|prefix| prefix.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>()
+                    }),
+                };
+                prefixes.ok_or_else(|| {
+                    Error::msg(format!(
+                        "Invalid tag-prefix in {:?}; expected a string or an array of strings",
+                        workspace.join("Cargo.toml")
+                    ))
+                })
+            }
+            None => Ok(tags::default_prefixes(
+                fields::get(doc, "name", false).to_string().as_str(),
+                self.get_repo_relative_path(workspace)?
+                    .as_os_str()
+                    .is_empty(),
+            )),
+        }
+    }
+
+    /// Returns the release tags of the crate in `workspace`, highest version first;
+    pub fn release_tags(&self, workspace: &Path) -> Result<Vec<ReleaseTag>, Error> {
+        let prefixes = self.tag_prefixes(workspace)?;
+        let mut release_tags = Vec::new();
+
+        for (name, commit) in self.git.tags()? {
+            if let Some(version) = tags::tag_version(&name, &prefixes) {
+                release_tags.push(ReleaseTag {
+                    name,
+                    version,
+                    commit,
+                });
+            }
+        }
+
+        release_tags.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(release_tags)
+    }
+
+    /// Returns the directory of the member named `krate`, or of the member at the path `krate`,
+    /// e.g. `crates/foo-rs`, since package and directory names often differ;
+    fn member_named(&self, krate: &str) -> Result<PathBuf, Error> {
+        let path = Path::new(krate.strip_prefix("./").unwrap_or(krate));
+        for workspace in self.model.members().iter() {
+            if self.crate_name(Path::new(workspace)) == krate {
+                return Ok(PathBuf::from(workspace));
+            }
+        }
+        for workspace in self.model.members().iter() {
+            if Path::new(workspace) == path || Path::new(workspace).join("Cargo.toml") == path {
+                return Ok(PathBuf::from(workspace));
+            }
+        }
+
+        Err(Error::msg(tr(
+            "no-member",
+            &[("name", &format!("{:?}", krate))],
+        )))
+    }
+
+    /// Returns the member directory of each conventional commit scope, which `.cvm.toml` maps to
+    /// a member directory or to a crate name; scopes mapped to neither print a warning;
+    fn resolve_scopes(&self) -> Result<BTreeMap<String, PathBuf>, Error> {
+        let mut scopes = BTreeMap::new();
+        for (scope, member) in self.scopes.iter() {
+            let dir = match self.member_named(&member.to_string_lossy()) {
+                Ok(dir) => dir,
+                Err(_) => {
+                    self.warn(format!(
+                        "scope `{}` maps to {:?}, which is not a workspace member",
+                        scope, member
+                    ))?;
+                    member.clone()
+                }
+            };
+            scopes.insert(scope.clone(), dir);
+        }
+
+        Ok(scopes)
+    }
+
+    /// Returns the floors of `.cvm.toml` by crate name, which `[floors]` gives by crate name or
+    /// by member directory; floors of neither print a warning;
+    fn resolve_floors(&self) -> Result<BTreeMap<String, Version>, Error> {
+        let mut floors = BTreeMap::new();
+        for (krate, floor) in self.floors.iter() {
+            let name = match self.member_named(krate) {
+                Ok(dir) => self.crate_name(&dir),
+                Err(_) => {
+                    self.warn(format!(
+                        "the floor of {:?} in .cvm.toml names no workspace member",
+                        krate
+                    ))?;
+                    krate.clone()
+                }
+            };
+            floors.insert(name, floor.clone());
+        }
+
+        Ok(floors)
+    }
+
+    /// Returns the last release of the crate named `krate` by its highest release tag, and the
+    /// number of commits since the tag that changed the crate, or None if it has no tags;
+    pub fn last_release(&self, krate: &str) -> Result<Option<LastRelease>, Error> {
+        let workspace = self.member_named(krate)?;
+        let tag = match self.release_tags(&workspace)?.into_iter().next() {
+            Some(tag) => tag,
+            None => return Ok(None),
+        };
+        let commit = self.git.find_commit(tag.commit)?;
+        let commits_since = self.unreleased_commits(&workspace, &tag)?.len();
+
+        Ok(Some(LastRelease {
+            krate: self.crate_name(&workspace),
+            version: tag.version.to_string(),
+            tag: tag.name,
+            commit: commit.short_id,
+            date: history::format_rfc3339(commit.time.max(0) as u64),
+            commits_since,
+        }))
+    }
+
+    /// Returns the commits since the release tag that changed the crate in `workspace`;
+    fn unreleased_commits(
+        &self,
+        workspace: &Path,
+        tag: &ReleaseTag,
+    ) -> Result<Vec<CommitInfo>, Error> {
+        let crate_dir = self.get_repo_relative_path(workspace)?;
+
+        let mut commits = Vec::new();
+        for commit in self.git.log(self.head_commit()?, tag.commit)? {
+            if self.commit_touches(&commit, &crate_dir)? {
+                commits.push(commit);
+            }
+        }
+
+        Ok(commits)
+    }
+
+    /// Print the crates whose changes since their last release tag exceed the thresholds, the
+    /// longest waiting first, followed by the crates without release tags; frozen and
+    /// unpublished crates are left out, see `cargo cvm due`;
+    pub fn print_due(&self, thresholds: &Thresholds) -> Result<(), Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let mut due_crates = Vec::new();
+        let mut untagged = Vec::new();
+
+        for workspace in self.members_by_name().iter() {
+            let workspace = Path::new(workspace);
+            let member = self.model.member(workspace)?;
+            if is_unpublished(&member.doc) || self.frozen_crate(workspace)?.is_some() {
+                continue;
+            }
+
+            let name = fields::get(&member.doc, "name", false).to_string();
+            let tag = match self.release_tags(workspace)?.into_iter().next() {
+                Some(tag) => tag,
+                None => {
+                    untagged.push(name);
+                    continue;
+                }
+            };
+
+            let commits = self.unreleased_commits(workspace, &tag)?;
+            let oldest = match commits.iter().map(|commit| commit.time).min() {
+                Some(oldest) => oldest.max(0) as u64,
+                None => continue,
+            };
+            let age = now.saturating_sub(oldest);
+
+            if thresholds.is_due(commits.len(), age) {
+                due_crates.push(DueCrate {
+                    krate: name,
+                    version: tag.version.to_string(),
+                    tag: tag.name,
+                    commits: commits.len(),
+                    oldest: history::format_rfc3339(oldest),
+                    age_days: due::days(age),
+                });
+            }
+        }
+
+        due::prioritize(&mut due_crates);
+
+        if self.output == OutputFormat::Json {
+            let report = serde_json::json!({ "due": due_crates, "untagged": untagged });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        if due_crates.is_empty() {
+            println!("{}", tr("nothing-due", &[]));
+        } else {
+            println!("{}", tr("due-heading", &[("count", &due_crates.len())]));
+            for krate in due_crates.iter() {
+                println!("  {}", krate);
+            }
+        }
+
+        if !untagged.is_empty() {
+            println!(
+                "{}",
+                tr(
+                    "untagged",
+                    &[("count", &untagged.len()), ("crates", &untagged.join(", "))]
+                )
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Print the last release of the crate named `krate`, see `last_release`;
+    pub fn print_last_release(&self, krate: &str) -> Result<(), Error> {
+        let release = match self.last_release(krate)? {
+            Some(release) => release,
+            None => {
+                let workspace = self.member_named(krate)?;
+                eprintln!(
+                    "No release tag of {} found; tags must start with one of {}, see `tag-prefix`.",
+                    self.crate_name(&workspace),
+                    self.tag_prefixes(&workspace)?.join(", ")
+                );
+                std::process::exit(1)
+            }
+        };
+
+        match self.output {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&release)?),
+            _ => println!("{}", release),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the crate if it opted out of version checks with `frozen = true` in
+    /// `[package.metadata.cvm]`;
+    pub fn frozen_crate(&self, workspace: &Path) -> Result<Option<FrozenCrate>, Error> {
+        let cargo_toml = workspace.join("Cargo.toml");
+        let doc = &self.model.member(workspace)?.doc;
+
+        if fields::cvm_metadata(doc, "frozen").and_then(toml_edit::Item::as_bool) != Some(true) {
+            return Ok(None);
+        }
+
+        Ok(Some(FrozenCrate {
+            krate: fields::get(doc, "name", false).to_string(),
+            cargo_toml: self.get_repo_relative_path(&cargo_toml)?,
+        }))
+    }
+
+    /// Returns the crate if its manifest omits `package.version`;
+    pub fn unversioned_crate(&self, workspace: &Path) -> Result<Option<UnversionedCrate>, Error> {
+        let member = self.model.member(workspace)?;
+
+        match &member.manifest.package {
+            Some(pkg) if member.omits_version() => Ok(Some(UnversionedCrate {
+                krate: pkg.name.clone(),
+                cargo_toml: self.get_repo_relative_path(&workspace.join("Cargo.toml"))?,
+                required: self.require_version,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the first waiver of the crate in `workspace` in the messages of the commits since
+    /// the base of any of its target branches, see `commits::waivers`;
+    pub fn find_waiver(&self, workspace: &Path) -> Result<Option<Waiver>, Error> {
+        let krate = match &self.model.member(workspace)?.manifest.package {
+            Some(pkg) => pkg.name.clone(),
+            None => return Ok(None),
+        };
+
+        for branch in self.member_branches(workspace)?.iter() {
+            for commit in self
+                .git
+                .log(self.head_commit()?, self.get_base_commit(branch)?)?
+            {
+                if let Some((_, reason)) = commits::waivers(&commit.message)
+                    .into_iter()
+                    .find(|(waived, _)| waived.as_deref().is_none_or(|name| name == krate))
+                {
+                    return Ok(Some(Waiver {
+                        krate,
+                        // The same path as `OutdatedCrate::cargo_toml`, to match the crate;
+                        cargo_toml: self.get_repo_relative_path(&workspace.join("Cargo.toml"))?,
+                        commit: commit.short_id,
+                        reason,
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the crate in `workspace` if its version is below its floor in `.cvm.toml`;
+    pub fn below_floor(&self, workspace: &Path) -> Result<Option<BelowFloor>, Error> {
+        let member = self.model.member(workspace)?;
+        let floor = match member.manifest.package.as_ref().and_then(|pkg| {
+            self.floors
+                .get(&pkg.name)
+                .map(|floor| (pkg.name.clone(), floor))
+        }) {
+            Some(floor) => floor,
+            None => return Ok(None),
+        };
+
+        let version = member.version(false)?;
+        if version >= *floor.1 {
+            return Ok(None);
+        }
+
+        Ok(Some(BelowFloor {
+            krate: floor.0,
+            cargo_toml: self.get_repo_relative_path(&workspace.join("Cargo.toml"))?,
+            version,
+            floor: floor.1.clone(),
+        }))
+    }
+
+    /// Returns the member whose version governs all members with `--unified`, or None for
+    /// `workspace.package.version`, and the version;
+    pub fn unified_version(&self) -> Result<Option<(Option<String>, Version)>, Error> {
+        if !self.unified {
+            return Ok(None);
+        }
+
+        let member = match &self.unified_crate {
+            Some(name) => Some(
+                self.member_named(name)
+                    .map(|dir| dir.display().to_string())
+                    .map_err(|_| {
+                        Error::msg(format!(
+                            "--unified-crate {} is not a workspace member",
+                            name
+                        ))
+                    })?,
+            ),
+            None => self
+                .model
+                .members()
+                .iter()
+                .find(|workspace| Path::new(workspace) == self.model.root())
+                .cloned(),
+        };
+        let member = match member {
+            Some(member) => member,
+            // A virtual workspace may still set the version its members inherit;
+            None => match self.model.workspace_version(self.lenient_versions)? {
+                Some(version) => return Ok(Some((None, version))),
+                None => return Err(Error::msg(
+                    "--unified takes the version of the root package, or else `workspace.package.version`, but the root manifest has neither; name the crate governing the version with --unified-crate",
+                )),
+            },
+        };
+
+        let version = self
+            .model
+            .version(Path::new(&member), self.lenient_versions)?;
+        Ok(Some((Some(member), version)))
+    }
+
+    /// Returns the crate in `workspace` if its version differs from the `unified` version;
+    pub fn version_mismatch(
+        &self,
+        workspace: &Path,
+        unified: &Version,
+    ) -> Result<Option<VersionMismatch>, Error> {
+        let member = self.model.member(workspace)?;
+        let version = member.version(self.lenient_versions)?;
+        match &member.manifest.package {
+            Some(pkg) if version != *unified => Ok(Some(VersionMismatch {
+                krate: pkg.name.clone(),
+                cargo_toml: self.get_repo_relative_path(&workspace.join("Cargo.toml"))?,
+                version,
+                unified: unified.clone(),
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Compute the edits setting `workspace.package.version`, if any, and every member
+    /// declaring its own version, other than frozen ones, to `new_version`, without writing
+    /// them; members inheriting the version follow the root manifest;
+    pub fn prepare_unified(&self, new_version: &Version) -> Result<Vec<ManifestEdit>, Error> {
+        let mut edits = Vec::new();
+        if let Some(old_version) = self.model.workspace_version(false)? {
+            if old_version != *new_version {
+                edits.push(self.prepare_workspace_edit(&old_version, new_version)?);
+            }
+        }
+
+        for workspace in self.model.members().iter() {
+            let workspace = Path::new(workspace);
+            if self.frozen_crate(workspace)?.is_some()
+                || self.unversioned_crate(workspace)?.is_some()
+            {
+                continue;
+            }
+
+            let member = self.model.member(workspace)?;
+            let pkg = match &member.manifest.package {
+                Some(pkg) => pkg,
+                None => continue,
+            };
+            let cargo_toml = workspace.join("Cargo.toml");
+            let old_version = member.version(false)?;
+            if member.inherits_version() || old_version == *new_version {
+                continue;
+            }
+
+            // Both versions of the root manifest cannot be edited at once;
+            if edits.iter().any(|edit| edit.path == member.path) {
+                return Err(Error::msg(format!(
+                    "{:?} sets both `package.version` and `workspace.package.version`; let the root package inherit the version with `version.workspace = true`",
+                    cargo_toml.display()
+                )));
+            }
+            edits.push(self.prepare_edit(
+                cargo_toml,
+                member.contents.clone(),
+                pkg.name.clone(),
+                &old_version,
+                new_version,
+                "[package]",
+            )?);
+        }
+
+        Ok(edits)
+    }
+
+    /// Returns the path dependencies of the crate in `workspace` that lack a version, unless
+    /// the crate is not published anyway;
+    pub fn unversioned_path_deps(&self, workspace: &Path) -> Result<Vec<PathDependency>, Error> {
+        let member = self.model.member(workspace)?;
+        if is_unpublished(&member.doc) {
+            return Ok(Vec::new());
+        }
+
+        let krate = fields::get(&member.doc, "name", false).to_string();
+        let cargo_toml = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+
+        Ok(deps::unversioned_path_dependencies(&member.doc)
+            .into_iter()
+            .map(|(requirement, path)| PathDependency {
+                krate: krate.clone(),
+                cargo_toml: cargo_toml.clone(),
+                dependency: requirement.package,
+                table: requirement.table.to_string(),
+                path,
+                fixed: false,
+            })
+            .collect())
+    }
+
+    /// Add the current version of the dependency to each of the path dependencies, staging
+    /// the manifests; dependencies whose version cannot be read, e.g. because they omit it,
+    /// are left as they are; returns the edits of the changed manifests not among `bumps`,
+    /// keeping their versions, so that `cargo cvm undo` restores them, too;
+    fn fix_path_deps(
+        &self,
+        path_deps: &mut [PathDependency],
+        bumps: &[ManifestEdit],
+    ) -> Result<Vec<ManifestEdit>, Error> {
+        let mut changed = Vec::new();
+        let mut edits = Vec::new();
+
+        for workspace in self.model.members().iter() {
+            let cargo_toml = std::env::current_dir()?.join(workspace).join("Cargo.toml");
+            let relative = self.get_repo_relative_path(&cargo_toml)?;
+            if !path_deps.iter().any(|dep| dep.cargo_toml == relative) {
+                continue;
+            }
+
+            let mut doc = self.model.member(Path::new(workspace))?.doc.clone();
+            for (requirement, path) in deps::unversioned_path_dependencies(&doc.clone()) {
+                let dependency = self
+                    .model
+                    .manifest(&Path::new(workspace).join(&path).join("Cargo.toml"))?;
+                if dependency.omits_version() {
+                    continue;
+                }
+                let version = match dependency.version(self.lenient_versions) {
+                    Ok(version) => version.to_string(),
+                    Err(_) => continue,
+                };
+
+                deps::set_version(&mut doc, &requirement, &version);
+                println!(
+                    "{}: {} = {{ path = {:?}, version = {:?} }}",
+                    relative.display(),
+                    requirement.key,
+                    path,
+                    version
+                );
+                for dep in path_deps.iter_mut() {
+                    if dep.cargo_toml == relative && dep.dependency == requirement.package {
+                        dep.fixed = true;
+                    }
+                }
+                if !changed.contains(&cargo_toml) {
+                    changed.push(cargo_toml.clone());
+                }
+            }
+
+            if changed.contains(&cargo_toml) {
+                // The edit of a bumped manifest already restores its contents before the bump;
+                let bumped = bumps.iter().any(|edit| {
+                    self.get_repo_relative_path(&edit.path).ok().as_ref() == Some(&relative)
+                });
+                if !bumped {
+                    let member = self.model.member(Path::new(workspace))?;
+                    let version = member
+                        .version(self.lenient_versions)
+                        .map(|version| version.to_string())
+                        .unwrap_or_default();
+                    edits.push(ManifestEdit {
+                        name: self.crate_name(Path::new(workspace)),
+                        path: cargo_toml.clone(),
+                        old_version: version.clone(),
+                        new_version: version,
+                        original: member.contents.clone(),
+                    });
+                }
+
+                fields::save(&*self.fs, &doc, &cargo_toml)?;
+                self.model
+                    .update(&cargo_toml, fields::read_manifest(&*self.fs, &cargo_toml)?)?;
+            }
+        }
+
+        self.stage(&changed)?;
+        Ok(edits)
+    }
+
+    /// Returns true if the crate in `workspace` does not exist in the tree it is compared
+    /// against, i.e. it was added on the current branch;
+    pub fn is_new_crate(&self, workspace: &Path) -> Result<bool, Error> {
+        let branch = match self.member_branches(workspace)?.into_iter().next() {
+            Some(branch) => branch,
+            None => return Ok(false),
+        };
+        let (target_tree, _) = self.get_comparison_trees(&branch)?;
+        let manifest_path = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+
+        Ok(self.read_tree_file(target_tree, &manifest_path)?.is_none())
+    }
+
+    /// Returns the `package.name` of the member in `workspace`, or its directory if the manifest
+    /// has no name, e.g. a virtual workspace manifest;
+    pub fn crate_name(&self, workspace: &Path) -> String {
+        self.model
+            .member(workspace)
+            .ok()
+            .and_then(|member| member.manifest.package.as_ref().map(|pkg| pkg.name.clone()))
+            .unwrap_or_else(|| workspace.display().to_string())
+    }
+
+    /// Returns the members sorted by crate name, then by path, so that reports list the crates
+    /// in the same order on every machine and run;
+    fn members_by_name(&self) -> Vec<String> {
+        let mut members = self
+            .model
+            .members()
+            .iter()
+            .map(|workspace| (self.crate_name(Path::new(workspace)), workspace.clone()))
+            .collect::<Vec<_>>();
+        members.sort();
+        members
+            .into_iter()
+            .map(|(_, workspace)| workspace)
+            .collect()
+    }
+
+    /// Returns the manifests of packages that exist in the tree compared against `branch` but
+    /// were deleted on the current branch, relative to the repository root, with their crate
+    /// names;
+    pub fn removed_crates(&self, branch: &str) -> Result<BTreeMap<PathBuf, String>, Error> {
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+
+        let mut removed = BTreeMap::new();
+        // Deleted packages are no longer members, so any manifest of the repository may be one;
+        let pathspecs = [String::from("*Cargo.toml")];
+        for change in self.git.diff(target_tree, current_tree, &pathspecs)? {
+            let path = match change {
+                Change::Deleted(path) => path,
+                _ => continue,
+            };
+            if path.file_name() != Some(std::ffi::OsStr::new("Cargo.toml")) {
+                continue;
+            }
+
+            // Only packages count, not e.g. a removed virtual workspace manifest;
+            let package = self
+                .read_tree_file(target_tree, &path)?
+                .and_then(|contents| Manifest::from_slice(&contents).ok())
+                .and_then(|manifest| manifest.package);
+            if let Some(package) = package {
+                removed.insert(path, package.name);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Returns true if the sources of the frozen crate in `workspace` differ from the target
+    /// branch;
+    pub fn is_frozen_touched(&self, workspace: &Path, branch: &str) -> Result<bool, Error> {
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+        let src_dir = self.get_repo_relative_path(&workspace.join("src"))?;
+
+        self.trees_differ(target_tree, current_tree, &src_dir)
+    }
+
+    /// Returns the tracked files with uncommitted changes, relative to the repository root;
+    pub fn dirty_files(&self) -> Result<Vec<String>, Error> {
+        if self.is_bare() {
+            return Ok(Vec::new());
+        }
+
+        // libgit2 reports the files of members outside the sparse-checkout cone as deleted;
+        let outside_cone = self
+            .outside_cone
+            .iter()
+            .map(|member| self.get_repo_relative_path(Path::new(member)))
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+
+        Ok(self
+            .git
+            .dirty_files()?
+            .iter()
+            .filter(|change| match change {
+                Change::Deleted(path) => !outside_cone.iter().any(|dir| path.starts_with(dir)),
+                _ => true,
+            })
+            .map(|change| change.path().display().to_string())
+            .collect())
+    }
+
+    /// Fail if tracked files have uncommitted changes and `refuse` is set, since editing
+    /// manifests would mix them with the version bumps, e.g. in the version commit; like
+    /// `cargo publish`, see `--allow-dirty`. Otherwise they are only a warning;
+    fn check_clean(&self, refuse: bool) -> Result<(), Error> {
+        let dirty = self.dirty_files()?;
+        if dirty.is_empty() {
+            return Ok(());
+        }
+
+        let files = dirty.join(", ");
+        if refuse {
+            return Err(Error::msg(tr("dirty-refused", &[("files", &files)])));
+        }
+
+        self.warn(tr("dirty", &[("files", &files)]))
+    }
+
+    /// Print `message` as a warning, or fail with it if `--strict` is set;
+    fn warn(&self, message: impl Into<String>) -> Result<(), Error> {
+        warning::warn(self.strict, message)
+    }
+
+    /// Write a starter `.cvm.toml` based on the workspace, and optionally a GitHub Actions
+    /// workflow running `cargo cvm check`, refusing to overwrite existing files unless `force`;
+    pub fn init(&self, github_actions: bool, force: bool) -> Result<(), Error> {
+        let dir = std::env::current_dir()?;
+        let remote = self.fetcher.target_remote.as_str();
+        let repo = self.repository()?;
+        let branch =
+            init::default_branch(&repo, remote).unwrap_or_else(|| self.target_branches[0].clone());
+
+        let members = self
+            .model
+            .members()
+            .iter()
+            .map(|workspace| {
+                self.get_repo_relative_path(Path::new(workspace))
+                    .map(|path| path.display().to_string())
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let mut files = vec![(
+            dir.join(config::CONFIG_FILE),
+            init::config_template(&branch, remote, &members, &init::tag_patterns(&repo)),
+        )];
+        if github_actions {
+            let workdir = self.workdir()?;
+            files.push((
+                workdir.join(".github/workflows/cvm.yml"),
+                init::workflow_template(&branch),
+            ));
+        }
+
+        for (path, _) in files.iter() {
+            if path.exists() && !force {
+                eprintln!(
+                    "{:?} already exists; use --force to overwrite it",
+                    path.display()
+                );
+                std::process::exit(1)
+            }
+        }
+
+        for (path, contents) in files.iter() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+            println!("wrote {:?}", path.display());
+        }
+
+        // Keep the generated files out of the repository, unless they are ignored already, e.g.
+        // by `/target`;
+        let ignored = self
+            .get_repo_relative_path(&self.artifact_dir)
+            .and_then(|path| Ok(repo.is_path_ignored(path)?))
+            .unwrap_or(false);
+        let fragment = artifacts::gitignore_fragment(&dir, &self.artifact_dir).filter(|_| !ignored);
+        if let Some(fragment) = fragment {
+            let path = dir.join(artifacts::GITIGNORE_FILE);
+            let gitignore = if path.exists() {
+                read_to_string(&path)?
+            } else {
+                String::new()
+            };
+            if let Some(contents) = artifacts::with_fragment(&gitignore, &fragment) {
+                std::fs::write(&path, contents)?;
+                println!("updated {:?}", path.display());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the files to the git index;
+    pub fn stage(&self, paths: &[PathBuf]) -> Result<(), Error> {
+        // The files on disk are unchanged by a dry run;
+        if self.dry_run.is_some() {
+            return Ok(());
+        }
+
+        let paths = paths
+            .iter()
+            .map(|path| self.get_repo_relative_path(path))
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+
+        self.git.add(&paths)
+    }
+
+    /// Returns the path relative to the root of the repository's working directory;
+    pub fn get_repo_relative_path(&self, path: &std::path::Path) -> Result<PathBuf, Error> {
+        // Members may be symlinks, e.g. to vendored crates, while git reports the paths of the
+        // files they link to;
+        let path = fs::resolve(&self.model.root().join(path));
+        let root = fs::resolve(&self.root_dir()?);
+
+        path.strip_prefix(&root)
+            .map(Path::to_path_buf)
+            .map_err(|_| {
+                Error::msg(format!(
+                    "{:?} is outside the repository at {:?}",
+                    path.display(),
+                    root.display()
+                ))
+            })
+    }
+
+    /// Returns the root of the working directory, or the git directory of a bare repository,
+    /// which stands in for it while checking;
+    pub fn root_dir(&self) -> Result<PathBuf, Error> {
+        Ok(self.git.workdir().unwrap_or_else(|| self.git.git_dir()))
+    }
+
+    /// Returns the root of the working directory, failing in a bare repository;
+    fn workdir(&self) -> Result<PathBuf, Error> {
+        self.git
+            .workdir()
+            .ok_or_else(|| Error::msg("Repository has no working directory"))
+    }
+
+    /// Returns true in a bare repository, e.g. on a server, which has no working tree;
+    pub fn is_bare(&self) -> bool {
+        self.git.workdir().is_none()
+    }
+
+    /// Open the repository, e.g. to inspect its remotes and configuration, which
+    /// `GitProvider` leaves to libgit2;
+    fn repository(&self) -> Result<Repository, Error> {
+        Ok(Repository::open(self.git.git_dir())?)
+    }
+
+    /// Returns the branches to compare against; only the first when comparing against an
+    /// earlier revision with `--since`;
+    pub fn target_branches(&self) -> &[String] {
+        if self.since.is_some() {
+            &self.target_branches[..1]
+        } else {
+            &self.target_branches
+        }
+    }
+
+    /// Returns the branches the crate in `workspace` is compared against: the branch set by
+    /// `branch` in its `[package.metadata.cvm]`, e.g. a backport crate maintained against
+    /// `release/2.x`, otherwise the target branches;
+    pub fn member_branches(&self, workspace: &Path) -> Result<Vec<String>, Error> {
+        if self.since.is_none() {
+            let member = self.model.member(workspace)?;
+            if let Some(branch) =
+                fields::cvm_metadata(&member.doc, "branch").and_then(toml_edit::Item::as_str)
+            {
+                return Ok(vec![branch.to_string()]);
+            }
+        }
+
+        Ok(self.target_branches().to_vec())
+    }
+
+    /// Returns the target branches followed by the branches set by members, each once;
+    pub fn all_branches(&self) -> Result<Vec<String>, Error> {
+        let mut branches = self.target_branches().to_vec();
+
+        for workspace in self.model.members().iter() {
+            for branch in self.member_branches(Path::new(workspace))? {
+                if !branches.contains(&branch) {
+                    branches.push(branch);
+                }
+            }
+        }
+
+        Ok(branches)
+    }
+
+    /// Returns the name of the revision `branch` resolves to, e.g. `origin/master`;
+    pub fn target_name(&self, branch: &str) -> String {
+        match &self.since {
+            Some(since) => since.clone(),
+            None if self.is_bare() => branch.to_string(),
+            None => format!("{}/{}", self.fetcher.target_remote, branch),
+        }
+    }
+
+    /// Returns the most restrictive `[release-train]` rule matching the target branch, if any;
+    pub fn allowed_semver(&self, branch: &str) -> Option<(&str, SemVer)> {
+        if self.since.is_some() {
+            return None;
+        }
+
+        self.release_train
+            .iter()
+            .filter(|(_, glob, _)| glob.is_match(branch))
+            .map(|(pattern, _, semver)| (pattern.as_str(), *semver))
+            .min_by_key(|(_, semver)| *semver)
+    }
+
+    /// Returns a violation if the crate's version was bumped by more than the release train of
+    /// the target branch allows;
+    pub fn check_release_train(
+        &self,
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Option<ReleaseTrainViolation>, Error> {
+        let (rule, allowed) = match self.allowed_semver(branch) {
+            Some(allowed) => allowed,
+            None => return Ok(None),
+        };
+
+        let cargo_toml = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+
+        let (old_version, new_version): (Version, Version) = match (
+            self.get_tree_manifest(target_tree, &cargo_toml)?,
+            self.get_tree_manifest(current_tree, &cargo_toml)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => {
+                match (
+                    self.tree_version(&old_manifest),
+                    self.tree_version(&new_manifest),
+                ) {
+                    (Ok(old_version), Ok(new_version)) => (old_version, new_version),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        match old_version.bump_level(&new_version) {
+            Some(semver) if semver > allowed => Ok(Some(ReleaseTrainViolation {
+                krate: self.crate_name(workspace),
+                cargo_toml,
+                old_version,
+                new_version,
+                semver,
+                allowed,
+                rule: rule.to_string(),
+                target: self.target_name(branch),
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the bump of the crate on the current branch if it skips versions, e.g.
+    /// `1.2.3 → 1.5.0` or `1.2.3 → 3.0.0`;
+    pub fn check_version_jump(
+        &self,
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Option<VersionJump>, Error> {
+        let cargo_toml = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+
+        let (old_version, new_version): (Version, Version) = match (
+            self.get_tree_manifest(target_tree, &cargo_toml)?,
+            self.get_tree_manifest(current_tree, &cargo_toml)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => {
+                match (
+                    self.tree_version(&old_manifest),
+                    self.tree_version(&new_manifest),
+                ) {
+                    (Ok(old_version), Ok(new_version)) => (old_version, new_version),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        // Unchanged and lowered versions are left to the regression check;
+        if new_version <= old_version || old_version.next_versions().contains(&new_version) {
+            return Ok(None);
+        }
+
+        Ok(Some(VersionJump {
+            krate: self.crate_name(workspace),
+            cargo_toml,
+            old_version,
+            new_version,
+            target: self.target_name(branch),
+        }))
+    }
+
+    /// Returns the `metadata-fields` of the crate that were removed or changed compared to
+    /// `branch`, comparing inherited fields by the values they resolve to in each tree;
+    pub fn check_metadata(
+        &self,
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Vec<MetadataRegression>, Error> {
+        if self.metadata_fields.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let manifest_path = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+        let (old_manifest, new_manifest) = match (
+            self.get_tree_manifest(target_tree, &manifest_path)?,
+            self.get_tree_manifest(current_tree, &manifest_path)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => (old_manifest, new_manifest),
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut regressions = Vec::new();
+        for field in self.metadata_fields.iter() {
+            // Adding a field is never a regression;
+            let old_value = match metadata_field(&old_manifest, field) {
+                Some(old_value) => old_value,
+                None => continue,
+            };
+            let new_value = metadata_field(&new_manifest, field);
+
+            if new_value.as_ref() != Some(&old_value) {
+                regressions.push(MetadataRegression {
+                    krate: self.crate_name(workspace),
+                    cargo_toml: manifest_path.clone(),
+                    field: field.clone(),
+                    old_value,
+                    new_value,
+                    target: self.target_name(branch),
+                });
+            }
+        }
+
+        Ok(regressions)
+    }
+
+    /// Returns the `new-crate-fields` that the manifest of the new crate in `workspace` does not
+    /// set, resolving inherited fields; a `license-file` stands in for `license`, e.g. for a
+    /// license without an SPDX identifier; crates that are never published are left out;
+    pub fn check_new_crate_metadata(
+        &self,
+        workspace: &Path,
+    ) -> Result<Option<IncompleteCrate>, Error> {
+        if self.new_crate_fields.is_empty() {
+            return Ok(None);
+        }
+
+        let member = self.model.member(workspace)?;
+        if is_unpublished(&member.doc) {
+            return Ok(None);
+        }
+        let is_set = |field: &str| {
+            metadata_field(&member.manifest, field).is_some()
+                || field == "license" && metadata_field(&member.manifest, "license-file").is_some()
+        };
+        let missing = self
+            .new_crate_fields
+            .iter()
+            .filter(|field| !is_set(field))
+            .cloned()
+            .collect::<Vec<String>>();
+        if missing.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(IncompleteCrate {
+            krate: self.crate_name(workspace),
+            cargo_toml: self.get_repo_relative_path(&workspace.join("Cargo.toml"))?,
+            missing,
+        }))
+    }
+
+    /// Returns the bump of the crate on the current branch compared to `branch` if the changelog
+    /// of the crate has no entry for the new version, read from the current tree;
+    pub fn check_changelog(
+        &self,
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Option<MissingChangelog>, Error> {
+        if !self.changelog.require {
+            return Ok(None);
+        }
+
+        let cargo_toml = workspace.join("Cargo.toml");
+        let manifest_path = self.get_repo_relative_path(&cargo_toml)?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+
+        let (old_version, new_version): (Version, Version) = match (
+            self.get_tree_manifest(target_tree, &manifest_path)?,
+            self.get_tree_manifest(current_tree, &manifest_path)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => {
+                match (
+                    self.tree_version(&old_manifest),
+                    self.tree_version(&new_manifest),
+                ) {
+                    (Ok(old_version), Ok(new_version)) => (old_version, new_version),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        if new_version <= old_version {
+            return Ok(None);
+        }
+
+        // Members inheriting the workspace version share the changelog of the workspace root, as
+        // `--fix` bumps them together;
+        let dir = if self.model.member(workspace)?.inherits_version() {
+            self.model.root()
+        } else {
+            workspace
+        };
+        let changelog = self.get_repo_relative_path(&dir.join(&self.changelog.file))?;
+        let contents = self
+            .read_tree_file(current_tree, &changelog)?
+            .map(|contents| String::from_utf8_lossy(&contents).into_owned())
+            .unwrap_or_default();
+        if self
+            .changelog
+            .has_entry(&contents, &new_version.to_string())
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(MissingChangelog {
+            krate: self.crate_name(workspace),
+            cargo_toml: manifest_path,
+            changelog,
+            version: new_version,
+            target: self.target_name(branch),
+        }))
+    }
+
+    /// Returns the bump of the crate on the current branch if none of its sources changed
+    /// compared to `branch`; `changes` are the changed files returned by `changed_src_files`;
+    pub fn check_unchanged_bump(
+        &self,
+        workspace: &Path,
+        branch: &str,
+        changes: &MemberChanges,
+    ) -> Result<Option<UnchangedBump>, Error> {
+        let cargo_toml = self.get_repo_relative_path(&workspace.join("Cargo.toml"))?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+
+        let (old_manifest, mut new_manifest) = match (
+            self.get_tree_manifest(target_tree, &cargo_toml)?,
+            self.get_tree_manifest(current_tree, &cargo_toml)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => (old_manifest, new_manifest),
+            _ => return Ok(None),
+        };
+        let (old_version, new_version) = match (
+            self.tree_version(&old_manifest),
+            self.tree_version(&new_manifest),
+        ) {
+            (Ok(old_version), Ok(new_version)) => (old_version, new_version),
+            _ => return Ok(None),
+        };
+
+        if new_version <= old_version {
+            return Ok(None);
+        }
 
-        Ok(Self {
-            semver: args.value_of("semver").unwrap_or("minor").try_into()?,
-            check: args.is_present("check"),
-            fix: args.is_present("fix"),
-            warn: args.is_present("warn"),
-            force: args.is_present("force"),
-            commit: args.is_present("commit"),
-            target_branch: args.value_of("branch").unwrap_or("master").to_string(),
-            target_remote: args.value_of("remote").unwrap_or("origin").to_string(),
-            workspaces: Self::get_cargo_workspaces(dir)?,
-            ssh_key_path: args
-                .value_of("ssh-key")
-                .unwrap_or(&ssh_key_path)
-                .to_string(),
-            repo,
-        })
+        // Other changes to the manifest, e.g. to dependencies, are worth a release too;
+        if let (Some(old_package), Some(new_package)) =
+            (&old_manifest.package, &mut new_manifest.package)
+        {
+            new_package.version = old_package.version.clone();
+        }
+        if new_manifest != old_manifest {
+            return Ok(None);
+        }
+
+        let workdir = self.root_dir()?;
+        let src_dir = self.get_repo_relative_path(&workspace.join("src"))?;
+        let changed = changes
+            .get(&src_dir)
+            .into_iter()
+            .flatten()
+            .any(|path| !self.is_ignored(&workdir.join(path)));
+
+        if changed {
+            return Ok(None);
+        }
+
+        Ok(Some(UnchangedBump {
+            krate: self.crate_name(workspace),
+            cargo_toml,
+            old_version,
+            new_version,
+            target: self.target_name(branch),
+        }))
     }
 
-    pub fn get_cargo_workspaces(dir: PathBuf) -> Result<Vec<String>, Error> {
-        let mut cargo_toml = dir;
-        cargo_toml.push("Cargo.toml");
+    /// Returns (target, current) trees based on target and current branch;
+    pub fn get_comparison_trees(&self, branch: &str) -> Result<(Oid, Oid), Error> {
+        let target_branch_tree = self.git.tree(self.get_base_commit(branch)?)?;
+        let current_branch_tree = self.git.tree(self.head_commit()?)?;
+        Ok((target_branch_tree, current_branch_tree))
+    }
 
-        if !cargo_toml.exists() {
-            eprintln!("`cargo cvm` must be run in a directory containing a `Cargo.toml` file.\nFile does not exist at: {:?}", cargo_toml.display());
-            std::process::exit(1)
+    /// Returns the commit the current branch is compared against; this is the merge-base of
+    /// both branches unless `--tip` is set, so that commits which landed on the target branch
+    /// after branching off are not attributed to the current branch;
+    pub fn get_base_commit(&self, branch: &str) -> Result<Oid, Error> {
+        if let Some(since) = &self.since {
+            // Compare against an earlier revision of the current branch, e.g. `HEAD~3`;
+            return self.git.resolve(since);
         }
 
-        let config: Manifest = toml::from_str(&read_to_string(&cargo_toml)?)?;
-        let mut paths: Vec<String> = Vec::new();
+        let target = self.get_target_commit(branch)?;
 
-        if config.package.is_some() {
-            let dir = std::env::current_dir()?;
-            if let Some(path) = dir.to_str() {
-                paths.push(String::from(path));
-            }
+        if self.tip {
+            return Ok(target);
         }
 
-        if let Some(workspace) = config.workspace {
-            paths.extend(workspace.members.into_iter())
+        let head = self.head_commit()?;
+        if let Some(base) = self.merge_bases.borrow().get(&(target, head)) {
+            return Ok(*base);
         }
 
-        Ok(paths)
+        let base = self.git.merge_base(target, head)?;
+        self.merge_bases.borrow_mut().insert((target, head), base);
+        Ok(base)
     }
 
-    pub fn bump_version(&self, workspace: PathBuf) -> Result<(), Error> {
-        let mut cargo_toml = workspace;
-        cargo_toml.push("Cargo.toml");
+    /// Returns the commit at the tip of the remote-tracking target branch;
+    pub fn get_target_commit(&self, branch: &str) -> Result<Oid, Error> {
+        // A bare repository, e.g. on a server, holds the target branch itself;
+        if self.is_bare() {
+            return self
+                .git
+                .resolve(&format!("refs/heads/{}", branch))
+                .map_err(|e| {
+                    Error::msg(format!(
+                        "Target branch {} does not exist in this bare repository: {}",
+                        branch, e
+                    ))
+                });
+        }
 
-        let config = read_to_string(&cargo_toml)?;
-        if let Some(pkg) = toml::from_str::<Manifest>(&config)?.package {
-            let old_version: Version = pkg.version.try_into()?;
-            let mut new_version = old_version.clone();
-            new_version.bump(self.semver.clone());
+        self.git.resolve(&format!(
+            "refs/remotes/{}/{}",
+            self.fetcher.target_remote, branch
+        ))
+    }
 
-            // Replace only the first instance of the old_version to the new_version;
-            // this will not replace dependency versions;
-            let updated_config =
-                config.replacen(&old_version.to_string(), &new_version.to_string(), 1);
+    /// Returns a regression if the crate's version was changed on the current branch but is
+    /// not ahead of the tip of the target branch;
+    ///
+    /// Equal versions are allowed with `--allow-equal` if the crate's sources are unchanged
+    /// compared to the target branch, e.g. after the same bump landed there.
+    pub fn check_version_regression(
+        &self,
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Option<VersionRegression>, Error> {
+        let cargo_toml = workspace.join("Cargo.toml");
+        let manifest_path = self.get_repo_relative_path(&cargo_toml)?;
+        let target_tree = self.git.tree(self.get_target_commit(branch)?)?;
 
-            // Remove the old version of the file;
-            remove_file(&cargo_toml)?;
+        let target_version: Version = match self.get_tree_manifest(target_tree, &manifest_path)? {
+            Some(manifest) => match self.tree_version(&manifest) {
+                Ok(version) => version,
+                Err(_) => return Ok(None),
+            },
+            // The crate is new, so any version is ahead of the target branch;
+            None => return Ok(None),
+        };
+        let version = self.get_workspace_version(workspace)?;
 
-            // Update the new version;
-            let mut file = File::create(&cargo_toml)?;
-            file.write_all(updated_config.as_bytes())?;
+        if version > target_version {
+            return Ok(None);
+        }
 
-            // Add changes to the git index;
-            self.git_add_version_update(cargo_toml, new_version.to_string())?;
+        // A version left unchanged on the current branch merges cleanly with the target branch;
+        let base_tree = self.git.tree(self.get_base_commit(branch)?)?;
+        let base_version = self
+            .get_tree_manifest(base_tree, &manifest_path)?
+            .and_then(|manifest| self.tree_version(&manifest).ok());
+        if base_version.as_ref() == Some(&version) {
+            return Ok(None);
+        }
 
-            Ok(())
-        } else {
-            eprintln!("invalid cargo file");
-            std::process::exit(1)
+        if version == target_version {
+            let src_dir = self.get_repo_relative_path(&workspace.join("src"))?;
+            let current_tree = self.git.tree(self.head_commit()?)?;
+            if self.allow_equal && !self.trees_differ(target_tree, current_tree, &src_dir)? {
+                return Ok(None);
+            }
         }
+
+        Ok(Some(VersionRegression {
+            cargo_toml,
+            version,
+            target_version,
+            target: self.target_name(branch),
+        }))
+    }
+
+    /// Returns true if files in `dir`, relative to the repository root, differ between the trees;
+    pub fn trees_differ(&self, old: Oid, new: Oid, dir: &Path) -> Result<bool, Error> {
+        let pathspecs = [dir.display().to_string()];
+        Ok(!self.git.diff(old, new, &pathspecs)?.is_empty())
     }
 
-    pub fn git_add_version_update(
+    /// Returns the highest bump level inferred from the conventional commit messages since the
+    /// base commit which are scoped to the crate in `workspace`, or which have no configured
+    /// scope and changed its sources;
+    pub fn get_commits_semver(
         &self,
-        cargo_toml: PathBuf,
-        version: String,
-    ) -> Result<(), Error> {
-        let mut index = self.repo.index()?;
+        workspace: &Path,
+        branch: &str,
+    ) -> Result<Option<SemVer>, Error> {
+        let crate_dir = self.get_repo_relative_path(workspace)?;
+        let src_dir = crate_dir.join("src");
 
-        if let Some(strip_path) = index.path() {
-            if let Some(path) = strip_path.to_str() {
-                if let Some(file_path) = cargo_toml.to_str() {
-                    let root_path = &path.replace(".git/index", "");
-                    let relative_file = file_path.replace(root_path, "");
-                    index.add_path(PathBuf::from(relative_file).as_path())?;
+        let mut required: Option<SemVer> = None;
 
-                    // Update the index for the repo;
-                    self.repo.checkout_index(Some(&mut index), None)?;
+        for commit in self
+            .git
+            .log(self.head_commit()?, self.get_base_commit(branch)?)?
+        {
+            // Merge and bot commits must not skew the inferred bump level, if configured;
+            if self.commit_filter.is_ignored(&commit) {
+                continue;
+            }
 
-                    println!("version {} update added to git.", version);
+            if let Some(semver) = commits::infer_semver(&commit.message) {
+                if Some(semver) > required && self.is_commit_for(&commit, &crate_dir, &src_dir)? {
+                    required = Some(semver);
                 }
             }
         }
 
-        Ok(())
+        Ok(required)
     }
 
-    pub fn fetch_target(&self) -> Result<(), Error> {
-        let mut callbacks = git2::RemoteCallbacks::new();
-        callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            git2::Cred::ssh_key(
-                username_from_url.unwrap_or_default(),
-                None,
-                std::path::Path::new(&self.ssh_key_path),
-                None,
-            )
-        });
-
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
+    /// Returns true if the commit counts for the crate in `crate_dir`, relative to the repository
+    /// root: commits with configured scopes count for the mapped crates only, and other commits
+    /// for the crates whose sources in `src_dir` they changed;
+    fn is_commit_for(
+        &self,
+        commit: &CommitInfo,
+        crate_dir: &Path,
+        src_dir: &Path,
+    ) -> Result<bool, Error> {
+        let mut scoped = false;
 
-        match self.repo.find_remote(&self.target_remote) {
-            Ok(mut remote) => {
-                remote.fetch(&[&self.target_branch], Some(&mut fetch_options), None)?;
-                Ok(())
-            }
-            Err(e) => {
-                eprint!(
-                    "Failed to find target remote host: {:?}; Error: {:?}",
-                    &self.target_remote, e
-                );
-                let remotes = self.repo.remotes()?;
-                let remotes = &remotes
-                    .iter()
-                    .map(|remote| remote.unwrap_or(""))
-                    .collect::<Vec<&str>>();
-                println!("\nAvailable Remotes: {:?}", remotes);
-                eprintln!("Remote does not exist; try again with an available remote.");
-                std::process::exit(1)
+        for scope in commits::scopes(&commit.message) {
+            if let Some(dir) = self.scopes.get(scope) {
+                if self.get_repo_relative_path(dir)? == crate_dir {
+                    return Ok(true);
+                }
+                scoped = true;
             }
         }
+
+        Ok(!scoped && self.commit_touches(commit, src_dir)?)
     }
 
-    pub fn check_workspaces(&self) -> Result<(), Error> {
-        self.fetch_target()?;
+    /// Returns the most recent commit since the base commit that changed any of the files of the
+    /// crate in `workspace`, see `OutdatedCrate::last_change`;
+    fn last_change(
+        &self,
+        workspace: &Path,
+        files: &[ChangedFile],
+        branch: &str,
+    ) -> Result<Option<LastChange>, Error> {
+        let crate_dir = self.get_repo_relative_path(workspace)?;
+        let pathspecs = files
+            .iter()
+            .map(|file| crate_dir.join(&file.path).display().to_string())
+            .collect::<Vec<String>>();
 
-        let mut failed = false;
+        for commit in self
+            .git
+            .log(self.head_commit()?, self.get_base_commit(branch)?)?
+        {
+            if !self.git.commit_changes(commit.id, &pathspecs)?.is_empty() {
+                return Ok(Some(LastChange {
+                    commit: commit.short_id,
+                    author: commit.author,
+                    subject: commit.summary,
+                }));
+            }
+        }
 
-        // For each of the workspace directories, check if any files in the src directory have changed;
-        for workspace in self.workspaces.iter() {
-            if let Some((version, cargo_toml)) =
-                self.is_version_outdated(PathBuf::from(workspace))?
-            {
-                let msg = format!(
-                    "version {} is not updated for changes in workspace Cargo.toml file: {:?}",
-                    version, cargo_toml
-                );
+        Ok(None)
+    }
 
-                if self.check {
-                    eprintln!("{}", msg.clone());
-                    // set failed to true;
-                    failed = true;
-                } else if self.fix {
-                    self.bump_version(PathBuf::from(workspace))?;
-                } else if self.warn {
-                    eprintln!("{}", &msg);
-                } else {
-                    println!("{}", &msg);
+    /// Returns the owners of the files of the crate in `workspace` in CODEOWNERS, in the order
+    /// of the files;
+    fn owners(&self, workspace: &Path, files: &[ChangedFile]) -> Result<Vec<String>, Error> {
+        let crate_dir = self.get_repo_relative_path(workspace)?;
+        let mut owners: Vec<String> = Vec::new();
+
+        for file in files.iter() {
+            for owner in self.codeowners.owners(&crate_dir.join(&file.path)) {
+                if !owners.contains(owner) {
+                    owners.push(owner.clone());
                 }
-            } else if self.force {
-                // force an update even if the workspace version is already updated;
-                self.bump_version(PathBuf::from(workspace))?;
             }
         }
 
-        if failed {
-            eprintln!("Found outdated version, exiting process unsuccessfully");
-            std::process::exit(1)
-        }
+        Ok(owners)
+    }
 
-        if (self.force || self.fix) && self.commit {
-            self.commit_changes("updated crate version(s)")?;
-        }
+    /// Returns true if the commit changed files in `dir` compared to its first parent;
+    pub fn commit_touches(&self, commit: &CommitInfo, dir: &Path) -> Result<bool, Error> {
+        let pathspecs = [dir.display().to_string()];
+        Ok(!self.git.commit_changes(commit.id, &pathspecs)?.is_empty())
+    }
 
-        Ok(())
+    /// Returns the manifest at `manifest_path`, relative to the repository root, in the tree,
+    /// resolving fields inherited from the workspace manifest in the same tree;
+    pub fn get_tree_manifest(
+        &self,
+        tree: Oid,
+        manifest_path: &Path,
+    ) -> Result<Option<Manifest>, Error> {
+        self.model.tree_manifest(tree, manifest_path, || {
+            self.load_tree_manifest(tree, manifest_path)
+        })
     }
 
-    pub fn new_signature(&self) -> Result<git2::Signature, Error> {
-        let config = self.repo.config()?;
+    fn load_tree_manifest(
+        &self,
+        tree: Oid,
+        manifest_path: &Path,
+    ) -> Result<Option<Manifest>, Error> {
+        let contents = match self.read_tree_file(tree, manifest_path)? {
+            Some(contents) => contents,
+            None => return Ok(None),
+        };
 
-        let name = config.get_entry("user.name")?;
-        let email = config.get_entry("user.email")?;
+        let root_path = self.get_repo_relative_path(Path::new("Cargo.toml"))?;
+        let root = self.read_tree_file(tree, &root_path)?;
 
-        let sig = git2::Signature::now(
-            name.value().unwrap_or_default(),
-            email.value().unwrap_or_default(),
-        )?;
+        let workdir = self.root_dir()?;
+        Ok(Some(parse_tree_manifest(
+            &contents,
+            &workdir.join(manifest_path),
+            root.as_deref(),
+            self.model.root(),
+        )?))
+    }
 
-        Ok(sig)
+    /// Read a file of the tree, following symlinks within the tree, e.g. manifests symlinked
+    /// from a vendor directory; None if it does not exist or links outside the repository;
+    pub fn read_tree_file(&self, tree: Oid, path: &Path) -> Result<Option<Vec<u8>>, Error> {
+        self.git.read_file(tree, path)
     }
 
-    pub fn commit_changes(&self, msg: &str) -> Result<(), Error> {
-        let mut index = self.repo.index()?;
-        let oid = index.write_tree()?;
-        let tree = self.repo.find_tree(oid)?;
-        let sig = self.new_signature()?;
-        let parent_commit = self.repo.head()?.peel_to_commit()?;
-        let new_commit =
-            self.repo
-                .commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&parent_commit])?;
+    /// Returns the version of the workspace's `Cargo.toml`, resolving a version inherited from
+    /// the workspace root;
+    pub fn get_workspace_version(&self, workspace: &Path) -> Result<Version, Error> {
+        self.model.version(workspace, self.lenient_versions)
+    }
 
-        println!("commit {:?} includes version updates", new_commit);
-        Ok(())
+    /// Returns the package version of a manifest read from a git tree, to be compared;
+    fn tree_version(&self, manifest: &Manifest) -> Result<Version, Error> {
+        manifest_version(manifest, self.lenient_versions)
     }
 
-    /// Returns (target, current) trees based on target and current branch;
-    pub fn get_comparison_trees(&self) -> Result<(Tree, Tree), Error> {
-        let remote = format!("{}/{}", self.target_remote, self.target_branch);
-
-        let target_branch_tree = self
-            .repo
-            .find_branch(&remote, BranchType::Remote)?
-            .into_reference()
-            .peel_to_tree()?;
-        let current_branch_tree = self.repo.head()?.peel_to_tree()?;
-        Ok((target_branch_tree, current_branch_tree))
+    /// Returns true if the path matches a pattern in `.cvmignore`;
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if !path.starts_with(self.ignore.path()) {
+            return false;
+        }
+
+        self.ignore
+            .matched_path_or_any_parents(path, path.is_dir())
+            .is_ignore()
     }
 
-    pub fn get_version_comparison(
-        &self,
-        old_oid: git2::Oid,
-        new_oid: git2::Oid,
-    ) -> Result<(Version, Version), Error> {
-        let old_manifest: Manifest = toml::from_slice(self.repo.find_blob(old_oid)?.content())?;
-        let new_manifest: Manifest = toml::from_slice(self.repo.find_blob(new_oid)?.content())?;
+    /// Returns true if the changed file at `path`, relative to the repository root, is an asset
+    /// by its extension, or by its contents with `binary` set in `[assets]`; a deleted file is
+    /// read from the target tree;
+    fn is_asset(&self, path: &Path, target_tree: Oid, current_tree: Oid) -> Result<bool, Error> {
+        if self.assets.has_asset_extension(path) {
+            return Ok(true);
+        }
+        if !self.assets.binary {
+            return Ok(false);
+        }
 
-        let old_version: Version = old_manifest.try_into()?;
-        let new_version: Version = new_manifest.try_into()?;
+        let contents = match self.git.read_file(current_tree, path)? {
+            Some(contents) => Some(contents),
+            None => self.git.read_file(target_tree, path)?,
+        };
+        Ok(contents.is_some_and(|contents| config::is_binary(&contents)))
+    }
 
-        Ok((old_version, new_version))
+    /// Attribute a changed file to the `[severity]` rule requiring the highest bump level;
+    /// files that match no rule require the `--semver` level;
+    pub fn classify_changed_file(&self, path: PathBuf) -> ChangedFile {
+        let (rule, semver) = self
+            .severity
+            .iter()
+            .filter(|(_, glob, _)| glob.is_match(&path))
+            .max_by_key(|(_, _, semver)| *semver)
+            .map(|(pattern, _, semver)| (pattern.clone(), *semver))
+            .unwrap_or_else(|| (String::from("src/**"), self.semver));
+
+        ChangedFile { path, rule, semver }
     }
 
-    pub fn get_workspace_version(workspace: PathBuf) -> Result<Version, Error> {
-        let mut cargo_toml = workspace;
-        cargo_toml.push("Cargo.toml");
-        let config: Manifest = toml::from_str(&read_to_string(&cargo_toml)?)?;
-        Ok(config.try_into()?)
+    /// Returns the highest bump level required by the changed files;
+    pub fn get_required_semver(&self, changed_files: &[ChangedFile]) -> SemVer {
+        changed_files
+            .iter()
+            .map(|file| file.semver)
+            .max()
+            .unwrap_or(self.semver)
+    }
+
+    /// Returns the files changed compared to `branch`, relative to the repository root, by the
+    /// src directory of the member containing them;
+    ///
+    /// The diff is limited to the members' src directories with pathspecs, so files outside
+    /// them, e.g. documentation or other languages in a monorepo, are not diffed at all. Each
+    /// changed file is then looked up once in a prefix tree of the src directories, so
+    /// attributing `d` changed files to `m` members takes O(d × path depth) rather than
+    /// O(d × m) time.
+    pub fn changed_src_files(&self, branch: &str) -> Result<MemberChanges, Error> {
+        let _span = trace::span("diff", branch);
+
+        let mut members = PathTrie::default();
+        let mut pathspecs = Vec::new();
+        for workspace in self.model.members().iter() {
+            let src_dir = self.get_repo_relative_path(&Path::new(workspace).join("src"))?;
+            pathspecs.push(src_dir.display().to_string());
+            members.insert(&src_dir.clone(), src_dir);
+        }
+
+        let mut changes = MemberChanges::new();
+        // No pathspecs would diff the whole repository;
+        if pathspecs.is_empty() {
+            return Ok(changes);
+        }
+
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+        let diff = self.git.diff(target_tree, current_tree, &pathspecs)?;
+
+        for change in diff {
+            if let Some(src_dir) = members.longest_prefix(change.path()) {
+                changes
+                    .entry(src_dir.clone())
+                    .or_default()
+                    .push(change.path().to_path_buf());
+            }
+        }
+
+        Ok(changes)
     }
 
+    /// Returns the crate in `workspace` if its sources changed compared to `branch` without a
+    /// version bump; `changes` are the changed files returned by `changed_src_files`;
     pub fn is_version_outdated(
         &self,
         workspace: PathBuf,
-    ) -> Result<Option<(Version, PathBuf)>, Error> {
+        branch: &str,
+        changes: &MemberChanges,
+    ) -> Result<Option<OutdatedCrate>, Error> {
         let mut src_dir = workspace.clone();
         let mut cargo_toml = workspace.clone();
 
@@ -414,76 +5162,435 @@ impl Manager {
         src_dir.push("src");
         cargo_toml.push("Cargo.toml");
 
-        if !src_dir.exists() || !src_dir.is_dir() || !cargo_toml.exists() || !cargo_toml.is_file() {
-            eprintln!("src directory does not exist at {:?}", src_dir.display());
+        // With `--head`, or outside the sparse-checkout cone, the crate is read from git trees,
+        // and the checkout may lack it;
+        if self.head.is_none()
+            && !self.is_bare()
+            && !self.is_outside_cone(&workspace)
+            && (!src_dir.exists()
+                || !src_dir.is_dir()
+                || !cargo_toml.exists()
+                || !cargo_toml.is_file())
+        {
+            eprintln!(
+                "{}",
+                tr(
+                    "no-src-dir",
+                    &[("path", &format!("{:?}", src_dir.display()))]
+                )
+            );
             std::process::exit(1)
         }
 
-        let (target_tree, current_tree) = self.get_comparison_trees()?;
-
-        let diff = self
-            .repo
-            .diff_tree_to_tree(Some(&target_tree), Some(&current_tree), None)?;
+        let (target_tree, current_tree) = self.get_comparison_trees(branch)?;
+        let workdir = self.root_dir()?;
+        // Changed paths are those of the files a symlinked member links to;
+        let crate_dir = workdir.join(self.get_repo_relative_path(&workspace)?);
 
         let mut no_changes = true;
         let mut src_files_changed = false;
+        let mut changed_files = Vec::new();
         let mut version_is_updated = false;
-        let mut outdated_version: Version = Self::get_workspace_version(workspace)?;
-
-        diff.foreach(
-            &mut |delta, _value| {
-                let old_file = delta.old_file();
-                let new_file = delta.new_file();
-
-                if let Some(path) = new_file.path() {
-                    if let Some(uri) = PathBuf::from(path).to_str() {
-                        if let Some(repo_path) = self.repo.path().to_str() {
-                            let mut path = PathBuf::from(repo_path.replace("/.git", ""));
-                            path.push(uri);
-                            if let Some(dir) = src_dir.to_str() {
-                                if let Some(file) = path.to_str() {
-                                    if file.contains(dir) {
-                                        src_files_changed = true;
-                                        no_changes = false;
-                                    }
-                                }
-                            }
+        let mut bump_level = None;
+        let mut outdated_version: Version = self.get_workspace_version(&workspace)?;
 
-                            if cargo_toml == path {
-                                if let Ok((old_version, new_version)) =
-                                    self.get_version_comparison(old_file.id(), new_file.id())
-                                {
-                                    version_is_updated = new_version > old_version;
-
-                                    if !version_is_updated {
-                                        outdated_version = new_version;
-                                    } else {
-                                        outdated_version = old_version;
-                                    }
-                                }
-                            }
-                        }
+        let changed = changes.get(&self.get_repo_relative_path(&src_dir)?);
+        for repo_path in changed.into_iter().flatten() {
+            let path = workdir.join(repo_path);
+            if self.is_ignored(&path) {
+                continue;
+            }
+
+            let asset = self.is_asset(repo_path, target_tree, current_tree)?;
+            if asset && self.assets.changes == AssetChanges::Ignore {
+                continue;
+            }
+
+            src_files_changed = true;
+            no_changes = false;
+
+            if let Ok(relative) = path.strip_prefix(&crate_dir) {
+                changed_files.push(if asset {
+                    ChangedFile {
+                        path: relative.to_path_buf(),
+                        rule: String::from("[assets]"),
+                        semver: SemVer::Patch,
+                    }
+                } else {
+                    self.classify_changed_file(relative.to_path_buf())
+                });
+            }
+        }
+        changed_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // Compare the version against the same tree, so that bumps which already landed on the
+        // target branch are not reported;
+        let manifest_path = self.get_repo_relative_path(&cargo_toml)?;
+
+        match (
+            self.get_tree_manifest(target_tree, &manifest_path)?,
+            self.get_tree_manifest(current_tree, &manifest_path)?,
+        ) {
+            (Some(old_manifest), Some(new_manifest)) => {
+                if let (Ok(old_version), Ok(new_version)) = (
+                    self.tree_version(&old_manifest),
+                    self.tree_version(&new_manifest),
+                ) {
+                    version_is_updated = new_version > old_version;
+                    bump_level = old_version.bump_level(&new_version);
+
+                    if !version_is_updated {
+                        outdated_version = new_version;
+                    } else {
+                        outdated_version = old_version;
                     }
                 }
+            }
+            // The crate is new, so there is no previous version to bump;
+            _ => version_is_updated = true,
+        }
 
-                true
-            },
-            None,
-            None,
-            None,
-        )?;
+        // A bump below the level required by `--required-bump` leaves the crate outdated;
+        let below_required = match (self.required_bump, bump_level) {
+            (Some(required), Some(level)) => level < required,
+            _ => false,
+        };
 
-        if src_files_changed && version_is_updated || no_changes {
+        if src_files_changed && version_is_updated && !below_required || no_changes {
             Ok(None)
         } else {
-            Ok(Some((outdated_version, cargo_toml)))
+            // Suggest the highest level required by the changed files or the commit messages;
+            // `--fix` only bumps by the commit messages with `--bump-from-commits`;
+            let files_semver = self.get_required_semver(&changed_files);
+            let commits_semver = if self.fix && !self.bump_from_commits {
+                None
+            } else {
+                self.get_commits_semver(&workspace, branch)?
+            };
+
+            let (suggested_semver, reason) = match commits_semver {
+                Some(semver) if semver >= files_semver => {
+                    (semver, String::from(commits::describe(semver)))
+                }
+                _ => match changed_files
+                    .iter()
+                    .find(|file| file.semver == files_semver)
+                {
+                    Some(file) => (
+                        files_semver,
+                        format!("{} matched `{}`", file.path.display(), file.rule),
+                    ),
+                    None => (files_semver, String::from("default level")),
+                },
+            };
+
+            // Suggest at least the level required by `--required-bump`, e.g. from a pull request
+            // label;
+            let (suggested_semver, reason) = match self.required_bump {
+                Some(required) if required > suggested_semver || below_required => (
+                    required.max(suggested_semver),
+                    match bump_level {
+                        Some(level) if below_required => {
+                            format!("{} bump found, but --required-bump is {}", level, required)
+                        }
+                        _ => format!("--required-bump is {}", required),
+                    },
+                ),
+                _ => (suggested_semver, reason),
+            };
+
+            // Never suggest more than the release train of the target branch allows;
+            let (suggested_semver, reason) = match self.allowed_semver(branch) {
+                Some((rule, allowed)) if suggested_semver > allowed => (
+                    allowed,
+                    format!("{}, capped by release train `{}`", reason, rule),
+                ),
+                _ => (suggested_semver, reason),
+            };
+
+            let mut suggested_version = outdated_version.clone();
+            suggested_version.bump(suggested_semver);
+            if let Some(pkg) = &self.model.member(&workspace)?.manifest.package {
+                suggested_version.raise_to(self.floors.get(&pkg.name));
+            }
+
+            Ok(Some(OutdatedCrate {
+                krate: self.crate_name(&workspace),
+                version: outdated_version,
+                last_change: self.last_change(&workspace, &changed_files, branch)?,
+                owners: self.owners(&workspace, &changed_files)?,
+                cargo_toml: manifest_path,
+                changed_files,
+                suggested_version,
+                suggested_semver,
+                reason,
+                target: self.target_name(branch),
+            }))
+        }
+    }
+}
+
+/// Add the edit with its reason, keeping only the higher of two edits of the same version, e.g.
+/// of `workspace.package.version` for several members inheriting it; two versions of the same
+/// manifest, i.e. of the root package and of `[workspace.package]`, cannot be edited at once;
+fn push_edit<'a>(
+    edits: &mut Vec<ManifestEdit>,
+    reasons: &mut Vec<&'a str>,
+    edit: ManifestEdit,
+    reason: &'a str,
+) -> Result<(), Error> {
+    if edits
+        .iter()
+        .any(|other| other.path == edit.path && other.name != edit.name)
+    {
+        return Err(Error::msg(format!(
+            "{:?} sets both `package.version` and `workspace.package.version`; let the root package inherit the version with `version.workspace = true`",
+            edit.path.display()
+        )));
+    }
+    match edits
+        .iter()
+        .position(|other| other.path == edit.path && other.name == edit.name)
+    {
+        Some(at) => {
+            let new_version: Version = edit.new_version.clone().try_into()?;
+            let other_version: Version = edits[at].new_version.clone().try_into()?;
+            if new_version > other_version {
+                edits[at] = edit;
+                reasons[at] = reason;
+            }
+        }
+        None => {
+            edits.push(edit);
+            reasons.push(reason);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the command running `script` in the shell, i.e. `sh`, or `cmd` on Windows;
+fn shell_command(script: &str) -> std::process::Command {
+    if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    } else {
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+}
+
+/// Returns a command running the cargo that runs `cargo cvm`, or else `cargo` on the path;
+fn cargo_command() -> std::process::Command {
+    std::process::Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Returns the minor version of `cargo`, e.g. 83 for `cargo 1.83.0 (5ffbef321 2024-10-29)`;
+fn cargo_minor_version() -> Option<u32> {
+    let output = cargo_command().arg("--version").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)?
+        .split('.')
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Returns the zero-based lines of the manifest containing `version` as a whole version, i.e.
+/// not as part of another one, e.g. `0.1.0` in `10.1.0` or `0.1.0-rc`;
+pub fn find_version_occurrences(manifest: &str, version: &str) -> Vec<usize> {
+    let is_version_char = |c: char| c.is_ascii_alphanumeric() || ".-+".contains(c);
+
+    manifest
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.match_indices(version).any(|(start, _)| {
+                let before = line[..start].chars().next_back();
+                let after = line[start + version.len()..].chars().next();
+                !before.is_some_and(is_version_char) && !after.is_some_and(is_version_char)
+            })
+        })
+        .map(|(n, _)| n)
+        .collect()
+}
+
+/// Returns the zero-based line of the `version` key in the `[package]` table;
+pub fn package_version_line(manifest: &str) -> Option<usize> {
+    table_version_line(manifest, "[package]")
+}
+
+/// Returns the zero-based line of the `version` key in `table`, e.g. `[workspace.package]`;
+pub fn table_version_line(manifest: &str, table: &str) -> Option<usize> {
+    let mut in_package = false;
+
+    // A byte order mark does not change the line numbers;
+    let manifest = manifest.trim_start_matches('\u{feff}');
+    for (n, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_package = line == table;
+        } else if in_package
+            && line.starts_with("version")
+            && line["version".len()..].trim_start().starts_with('=')
+        {
+            return Some(n);
         }
     }
+
+    None
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{Baseline, CommitFilter, GitBackend, OutputFormat};
+    use crate::testing::TempDir;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::convert::TryInto;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_version_occurrences() {
+        let manifest =
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n\n[dependencies]\nbar = \"0.1.0\"\n";
+        assert_eq!(
+            super::find_version_occurrences(manifest, "0.1.0"),
+            vec![2, 5]
+        );
+        assert_eq!(super::package_version_line(manifest), Some(2));
+
+        let manifest =
+            "[dependencies]\nbar = \"0.1.0\"\n\n[package]\nname = \"foo\"\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            super::find_version_occurrences(manifest, "0.1.0"),
+            vec![1, 5]
+        );
+        assert_eq!(super::package_version_line(manifest), Some(5));
+
+        // Other versions containing it are not occurrences;
+        let manifest = "[package]\nversion = \"0.1.0\"\n\n[dependencies]\nbar = \"10.1.0\"\nbaz = \"0.1.0-rc\"\nqux = \"=0.1.0\"\n";
+        assert_eq!(
+            super::find_version_occurrences(manifest, "0.1.0"),
+            vec![1, 6]
+        );
+
+        let manifest = "\u{feff}[package]\nname = \"foo\"\nversion = \"0.1.0\"\n";
+        assert_eq!(super::package_version_line(manifest), Some(2));
+
+        let manifest =
+            "[workspace]\nmembers = [\"foo\"]\n\n[workspace.package]\nversion = \"0.1.0\"\n";
+        assert_eq!(super::package_version_line(manifest), None);
+        assert_eq!(
+            super::table_version_line(manifest, "[workspace.package]"),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_next_versions() -> Result<(), Box<dyn std::error::Error>> {
+        let version: super::Version = String::from("1.2.3").try_into()?;
+        let next = version
+            .next_versions()
+            .iter()
+            .map(|version| version.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(next, vec!["2.0.0", "1.3.0", "1.2.4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_field() -> Result<(), Box<dyn std::error::Error>> {
+        let manifest = cargo_toml::Manifest::from_str(
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\nlicense-file = \"LICENSE\"\nrepository.workspace = true\n",
+        )?;
+
+        assert_eq!(
+            super::metadata_field(&manifest, "license").as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(
+            super::metadata_field(&manifest, "license-file").as_deref(),
+            Some("LICENSE")
+        );
+        // Unresolved inheritance and unset fields have no value;
+        assert_eq!(super::metadata_field(&manifest, "repository"), None);
+        assert_eq!(super::metadata_field(&manifest, "description"), None);
+
+        // Inheriting the same value from the root manifest of the tree changes nothing;
+        let root = b"[workspace]\nmembers = [\"foo\"]\n\n[workspace.package]\nlicense = \"MIT\"\n";
+        let inherited = super::parse_tree_manifest(
+            b"[package]\nname = \"foo\"\nversion = \"0.1.0\"\nlicense.workspace = true\n",
+            Path::new("/repo/foo/Cargo.toml"),
+            Some(root),
+            Path::new("/repo"),
+        )?;
+        assert_eq!(
+            super::metadata_field(&inherited, "license").as_deref(),
+            Some("MIT")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_floor() -> Result<(), Box<dyn std::error::Error>> {
+        let floor = super::parse_floor("foo", ">=2.0.0")?;
+        assert_eq!(floor, super::parse_floor("foo", "2.0.0")?);
+        assert!(super::parse_floor("foo", "^2.0.0")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid floor \"^2.0.0\" for foo in .cvm.toml"));
+
+        let mut version: super::Version = String::from("1.4.2").try_into()?;
+        version.bump(super::SemVer::Patch);
+        version.raise_to(Some(&floor));
+        assert_eq!(version.to_string(), "2.0.0");
+        version.bump(super::SemVer::Patch);
+        version.raise_to(Some(&floor));
+        assert_eq!(version.to_string(), "2.0.1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_version() -> Result<(), Box<dyn std::error::Error>> {
+        use super::Version;
+
+        let error = |version: &str| Version::parse(version, false).unwrap_err().to_string();
+        assert_eq!(
+            error("1.2.3.4"),
+            "Invalid version \"1.2.3.4\": expected `major.minor.patch`, found 4 components; set `lenient-versions = true` in .cvm.toml to compare such versions"
+        );
+        assert_eq!(
+            error("1.x.3"),
+            "Invalid version \"1.x.3\": the minor component \"x\" is not a number"
+        );
+        assert_eq!(
+            error("1.2"),
+            "Invalid version \"1.2\": expected `major.minor.patch`, found 2 component(s)"
+        );
+        assert!(error("1.2.3-").contains("the pre-release \"\""));
+
+        // Pre-releases precede the release, and extra components are kept when lenient;
+        let pre = Version::parse("1.2.3-foo", false)?;
+        let release = Version::parse("1.2.3+build.5", false)?;
+        let extra = Version::parse("1.2.3.4", true)?;
+        assert_eq!(pre.to_string(), "1.2.3-foo");
+        assert_eq!(extra.to_string(), "1.2.3.4");
+        assert!(pre < release);
+        assert!(release < extra);
+        assert!(Version::parse("1.2.3-alpha.2", false)? < Version::parse("1.2.3-alpha.10", false)?);
+        assert_eq!(release, Version::parse("1.2.3", false)?);
+
+        let mut bumped = extra;
+        bumped.bump(super::SemVer::Patch);
+        assert_eq!(bumped.to_string(), "1.2.4");
+        Ok(())
+    }
 
     fn dummy_manager() -> Result<super::Manager, Box<dyn std::error::Error>> {
         let dir = std::env::current_dir()?;
@@ -491,7 +5598,41 @@ mod tests {
         println!("Current directory: {:?}", dir);
 
         let repo = git2::Repository::discover(dir.clone())?;
-        let ssh_key_path = format!("{}/.ssh/id_rsa", std::env::var("HOME")?);
+        let fetcher = test_fetcher(repo.path());
+        let git = super::Libgit2Provider::new(repo, fetcher);
+        manager_in(dir, Box::new(git))
+    }
+
+    /// Returns a fetcher of `origin` with the default settings;
+    fn test_fetcher(git_dir: &std::path::Path) -> super::Fetcher {
+        super::Fetcher {
+            target_remote: String::from("origin"),
+            anonymous: false,
+            non_interactive: false,
+            proxy: None,
+            ca_bundle: None,
+            ssl_version: None,
+            check_revoke: None,
+            low_speed_timeout: None,
+            host_keys: super::HostKeyVerifier {
+                policy: super::HostKeyPolicy::Default,
+                fingerprints: Vec::new(),
+                known_hosts: git_dir.join("cvm-known-hosts"),
+            },
+            fetch_timeout: None,
+            fetch_retries: 0,
+            git_backend: GitBackend::Libgit2,
+            fetch_with_cli: false,
+            ssh_keys: Vec::new(),
+        }
+    }
+
+    /// Build a manager of the workspace in `dir` with the default settings, on `git`;
+    fn manager_in(
+        dir: PathBuf,
+        git: Box<dyn super::GitProvider>,
+    ) -> Result<super::Manager, Box<dyn std::error::Error>> {
+        let fs: Rc<dyn super::FileSystem> = Rc::new(super::DiskFs);
 
         Ok(super::Manager {
             semver: String::from("minor").try_into()?,
@@ -500,22 +5641,238 @@ mod tests {
             warn: true,
             force: false,
             commit: false,
-            target_remote: String::from("origin"),
-            target_branch: String::from("master"),
-            workspaces: super::Manager::get_cargo_workspaces(dir)?,
-            ssh_key_path,
-            repo,
+            commit_branch: None,
+            tip: false,
+            since: None,
+            head: None,
+            outside_cone: BTreeSet::new(),
+            output: OutputFormat::Human,
+            template: None,
+            notify_webhook: None,
+            metrics: None,
+            target_branches: vec![String::from("master")],
+            fetcher: test_fetcher(&git.git_dir()),
+            git,
+            merge_bases: std::cell::RefCell::new(BTreeMap::new()),
+            jobs: 1,
+            model: super::WorkspaceModel::new(
+                dir.clone(),
+                super::Manager::get_cargo_workspaces(dir.clone())?,
+                fs.clone(),
+            )?,
+            fs,
+            dry_run: None,
+            lock_timeout: std::time::Duration::from_secs(30),
+            ignore: super::config::load_ignore(&dir)?,
+            codeowners: super::CodeOwners::default(),
+            severity: Vec::new(),
+            release_train: Vec::new(),
+            commit_filter: CommitFilter::default(),
+            assets: super::Assets::default(),
+            scopes: BTreeMap::new(),
+            allow_equal: false,
+            required_bump: None,
+            bump_from_commits: false,
+            strict: false,
+            allow_version_jump: false,
+            plan: None,
+            artifact_dir: std::env::temp_dir().join("cvm-test-artifacts"),
+            baseline: Baseline::default(),
+            record_baseline: false,
+            require_version: false,
+            lenient_versions: false,
+            show_ok: false,
+            preflight: None,
+            hooks: super::Hooks::default(),
+            changelog: super::Changelog::default(),
+            fix_path_deps: false,
+            allow_dirty: false,
+            metadata_fields: Vec::new(),
+            new_crate_fields: Vec::new(),
+            floors: BTreeMap::new(),
+            unified: false,
+            unified_crate: None,
         })
     }
 
+    #[test]
+    fn test_excluded_members() -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = TempDir::new("exclude")?;
+        let dir = tmp.path();
+        let manifest = "[workspace]\nmembers = [\"foo\", \"vendor/bar\"]\nexclude = [\"vendor\"]\n";
+        let package = "[package]\nname = \"nested\"\nversion = \"0.1.0\"\n";
+        for nested in ["foo/examples/demo", "foo/vendor/baz", "foo/third_party/qux"].iter() {
+            std::fs::create_dir_all(dir.join(nested))?;
+            std::fs::write(dir.join(nested).join("Cargo.toml"), package)?;
+        }
+        // A submodule's `.git` is a file pointing into the parent's;
+        std::fs::write(
+            dir.join("foo/third_party/qux/.git"),
+            "gitdir: ../../../.git/modules/qux\n",
+        )?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("..", dir.join("foo/examples/demo/parent"))?;
+
+        let members = super::Manager::workspace_members(manifest, dir)?;
+        assert_eq!(members, vec!["foo"]);
+
+        let excluded = vec![PathBuf::from("foo/vendor")];
+        assert_eq!(
+            super::Manager::get_nested_packages(dir, &members, &excluded, false)?,
+            vec!["foo/examples/demo"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_crate_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mgr = dummy_manager()?;
+        let dir = std::env::current_dir()?;
+        assert!(mgr.check_new_crate_metadata(&dir)?.is_none());
+
+        mgr.new_crate_fields = ["description", "license", "categories", "homepage"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        let incomplete = mgr.check_new_crate_metadata(&dir)?.unwrap();
+        assert_eq!(incomplete.krate, "cargo-cvm");
+        assert_eq!(incomplete.missing, vec!["homepage"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_unpublished() -> Result<(), Box<dyn std::error::Error>> {
+        let unpublished = |manifest: &str| -> Result<bool, toml_edit::TomlError> {
+            Ok(super::is_unpublished(&manifest.parse()?))
+        };
+        assert!(!unpublished("[package]\nname = \"a\"\n")?);
+        assert!(unpublished("[package]\nname = \"a\"\npublish = false\n")?);
+        assert!(unpublished("[package]\nname = \"a\"\npublish = []\n")?);
+        assert!(!unpublished(
+            "[package]\nname = \"a\"\npublish = [\"internal\"]\n"
+        )?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_is_workspace_updated() -> Result<(), Box<dyn std::error::Error>> {
         let mgr = dummy_manager()?;
 
         let dir = std::env::current_dir()?;
 
-        assert_eq!(mgr.is_version_outdated(dir)?.is_some(), false);
+        let changes = mgr.changed_src_files("master")?;
+        assert!(mgr.is_version_outdated(dir, "master", &changes)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_provider() -> Result<(), Box<dyn std::error::Error>> {
+        let manifest = |version: &str| {
+            format!(
+                "[package]\nname = \"cargo-cvm\"\nversion = \"{}\"\nedition = \"2018\"\n",
+                version
+            )
+        };
+        // The target branch is a major version ahead of the version on disk;
+        let tmp = TempDir::new("memory-git")?;
+        let dir = tmp.path().to_path_buf();
+        std::fs::create_dir_all(dir.join("src"))?;
+        std::fs::write(dir.join("Cargo.toml"), manifest("0.1.0"))?;
+        std::fs::write(dir.join("src/main.rs"), "fn main() { run() }")?;
+        let ahead = super::Version::parse("1.0.0", false)?;
+        let target = manifest(&ahead.to_string());
+
+        let mut git = crate::git::memory::MemoryGit::default();
+        git.workdir = Some(dir.clone());
+        let base = git.commit_files(
+            "refs/remotes/origin/master",
+            None,
+            &[
+                ("Cargo.toml", &target),
+                ("src/main.rs", "fn main() {}"),
+                ("old/Cargo.toml", &manifest("0.1.0")),
+            ],
+        );
+        git.commit_files(
+            "HEAD",
+            Some(base),
+            &[
+                ("Cargo.toml", &target),
+                ("src/main.rs", "fn main() { run() }"),
+                ("README.md", ""),
+            ],
+        );
+        let log = git.log.clone();
+
+        let mgr = manager_in(dir.clone(), Box::new(git))?;
+
+        mgr.fetch_targets()?;
+        assert_eq!(log.lock().unwrap().fetched, vec!["master"]);
+
+        let changes = mgr.changed_src_files("master")?;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes.values().next(),
+            Some(&vec![PathBuf::from("src/main.rs")])
+        );
+        assert_eq!(
+            mgr.removed_crates("master")?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![(PathBuf::from("old/Cargo.toml"), String::from("cargo-cvm"))]
+        );
+
+        // Only the commit on top of the target branch is listed, with the files it changed;
+        let head = mgr.head_commit()?;
+        let commits = mgr.git.log(head, mgr.get_base_commit("master")?)?;
+        assert_eq!(
+            commits.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+            vec![head]
+        );
+        assert!(mgr.commit_touches(&commits[0], std::path::Path::new("src"))?);
+        assert!(!mgr.commit_touches(&commits[0], std::path::Path::new("docs"))?);
+
+        // The version on disk is behind the target branch;
+        let regression = mgr.check_version_regression(&dir, "master")?;
+        assert_eq!(
+            regression.map(|regression| regression.target_version),
+            Some(ahead)
+        );
+
+        mgr.stage(&[PathBuf::from("Cargo.toml")])?;
+        assert_eq!(
+            log.lock().unwrap().staged,
+            vec![PathBuf::from("Cargo.toml")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unified_version() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mgr = dummy_manager()?;
+        assert!(mgr.unified_version()?.is_none());
+
+        // The root package governs the version, unless a crate is named;
+        mgr.unified = true;
+        let (source, version) = mgr.unified_version()?.ok_or("no unified version")?;
+        let source = source.ok_or("no root package")?;
+        assert_eq!(std::path::Path::new(&source), std::env::current_dir()?);
+        assert!(mgr.version_mismatch(source.as_ref(), &version)?.is_none());
+
+        let mut other = version.clone();
+        other.bump(super::SemVer::Major);
+        let mismatch = mgr
+            .version_mismatch(source.as_ref(), &other)?
+            .ok_or("no mismatch")?;
+        assert_eq!(mismatch.krate, "cargo-cvm");
 
+        mgr.unified_crate = Some(String::from("missing"));
+        assert!(mgr.unified_version().is_err());
         Ok(())
     }
 
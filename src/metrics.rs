@@ -0,0 +1,113 @@
+use crate::report::Report;
+use anyhow::Error;
+use std::fmt::Write as _;
+use std::fs::{rename, write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Render the report in the Prometheus text exposition format; `manifests` are the manifests
+/// of every checked crate, so that up-to-date crates are reported as well;
+pub fn render(report: &Report, manifests: &[PathBuf]) -> String {
+    let mut out = String::new();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, usize)>| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    };
+
+    // A crate outdated against several target branches is counted once;
+    let crates = manifests
+        .iter()
+        .map(|manifest| {
+            let outdated = report
+                .outdated
+                .iter()
+                .any(|krate| krate.cargo_toml == *manifest);
+            (
+                format!(
+                    "{{manifest=\"{}\"}}",
+                    escape(&manifest.display().to_string())
+                ),
+                outdated as usize,
+            )
+        })
+        .collect::<Vec<(String, usize)>>();
+
+    gauge(
+        "cvm_outdated_crates",
+        "Number of crates whose sources changed without a version bump.",
+        vec![(
+            String::new(),
+            crates.iter().map(|(_, outdated)| outdated).sum(),
+        )],
+    );
+    gauge(
+        "cvm_release_train_violations",
+        "Number of crates bumped by more than the release train allows.",
+        vec![(String::new(), report.violations.len())],
+    );
+    gauge(
+        "cvm_frozen_crates",
+        "Number of crates skipped because their version is frozen.",
+        vec![(String::new(), report.frozen.len())],
+    );
+    gauge(
+        "cvm_bumped_crates",
+        "Number of crate versions changed by the run.",
+        vec![(String::new(), report.bumps.len())],
+    );
+    gauge(
+        "cvm_crate_outdated",
+        "Whether the crate is outdated compared to any target branch.",
+        crates,
+    );
+    gauge(
+        "cvm_last_run_timestamp_seconds",
+        "Unix time of the run.",
+        vec![(String::new(), timestamp as usize)],
+    );
+
+    out
+}
+
+/// Write the metrics to `path`, replacing it atomically so that collectors such as the
+/// node_exporter textfile collector never read a partial file;
+pub fn write_textfile(path: &Path, metrics: &str) -> Result<(), Error> {
+    let tmp = path.with_extension("tmp");
+    write(&tmp, metrics)
+        .map_err(|e| Error::msg(format!("Failed to write metrics to {:?}: {}", tmp, e)))?;
+    rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Escape a label value;
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_render() {
+        let metrics = super::render(
+            &Report::default(),
+            &[PathBuf::from("crates/foo/Cargo.toml")],
+        );
+
+        assert!(metrics.contains("# TYPE cvm_outdated_crates gauge\ncvm_outdated_crates 0\n"));
+        assert!(metrics.contains("cvm_crate_outdated{manifest=\"crates/foo/Cargo.toml\"} 0\n"));
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Error;
+use serde::Serialize;
+use tera::{Context, Tera};
+
+/// Template used when no `--template` is given, rendering Markdown;
+pub const DEFAULT_TEMPLATE: &str = r#"{% for crate in crates -%}
+## {{ crate.name }} {{ crate.new_version }}
+
+{% for commit in crate.commits -%}
+- {{ commit.subject }} ({{ commit.id }})
+{% endfor %}
+{%- if crate.contributors %}
+Contributors: {{ crate.contributors | join(sep=", ") }}
+{% endif %}
+{% endfor -%}
+"#;
+
+/// Data model passed to release note templates;
+#[derive(Debug, Default, Serialize)]
+pub struct ReleaseNotes {
+    pub crates: Vec<CrateNotes>,
+}
+
+/// Release notes of a single bumped crate;
+#[derive(Debug, Serialize)]
+pub struct CrateNotes {
+    pub name: String,
+    pub old_version: String,
+    pub new_version: String,
+    /// Manifest path, relative to the repository root;
+    pub path: String,
+    /// Commits since the base commit that touched the crate, newest first;
+    pub commits: Vec<CommitNote>,
+    /// Authors of the commits, in order of their first commit;
+    pub contributors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitNote {
+    /// Abbreviated commit id;
+    pub id: String,
+    pub subject: String,
+    pub author: String,
+}
+
+/// Render the notes through a Tera template, or the default template;
+pub fn render(notes: &ReleaseNotes, template: Option<&str>) -> Result<String, Error> {
+    let context = Context::from_serialize(notes)?;
+
+    Tera::one_off(template.unwrap_or(DEFAULT_TEMPLATE), &context, false).map_err(|e| {
+        Error::msg(format!(
+            "Failed to render release notes: {}",
+            describe_template_error(&e)
+        ))
+    })
+}
+
+/// Tera reports the cause of a template error in its source chain, e.g. a missing variable;
+pub fn describe_template_error(e: &tera::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(e);
+
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommitNote, CrateNotes, ReleaseNotes};
+
+    #[test]
+    fn test_render_default_template() -> Result<(), Box<dyn std::error::Error>> {
+        let notes = ReleaseNotes {
+            crates: vec![CrateNotes {
+                name: String::from("foo"),
+                old_version: String::from("0.1.0"),
+                new_version: String::from("0.2.0"),
+                path: String::from("crates/foo/Cargo.toml"),
+                commits: vec![CommitNote {
+                    id: String::from("81de83f"),
+                    subject: String::from("feat: add g"),
+                    author: String::from("tester"),
+                }],
+                contributors: vec![String::from("tester")],
+            }],
+        };
+
+        assert_eq!(
+            super::render(&notes, None)?,
+            "## foo 0.2.0\n\n- feat: add g (81de83f)\n\nContributors: tester\n\n"
+        );
+        assert_eq!(
+            super::render(
+                &notes,
+                Some("{{ crates.0.name }}@{{ crates.0.new_version }}")
+            )?,
+            "foo@0.2.0"
+        );
+        Ok(())
+    }
+}
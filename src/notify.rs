@@ -0,0 +1,101 @@
+use crate::cargo_config::CargoNetConfig;
+use crate::http;
+use crate::report::Report;
+use anyhow::Error;
+use serde::Serialize;
+use std::time::Duration;
+
+/// JSON body posted to the webhook; `text` makes the summary readable in chat tools such as
+/// Slack, and the remaining fields carry the report for other consumers;
+#[derive(Debug, Serialize)]
+pub struct Notification<'a> {
+    pub text: String,
+    #[serde(flatten)]
+    pub report: &'a Report,
+}
+
+/// Returns a one-line summary of the outdated crates and applied bumps;
+pub fn summary(report: &Report) -> String {
+    let mut parts = Vec::new();
+
+    if !report.outdated.is_empty() {
+        let crates = report
+            .outdated
+            .iter()
+            .map(|krate| {
+                if krate.owners.is_empty() {
+                    format!("{} ({})", krate.cargo_toml.display(), krate.version)
+                } else {
+                    // Chat tools notify the owners mentioned by their handle;
+                    format!(
+                        "{} ({}, {})",
+                        krate.cargo_toml.display(),
+                        krate.version,
+                        krate.owners.join(" ")
+                    )
+                }
+            })
+            .collect::<Vec<String>>();
+        parts.push(format!(
+            "{} outdated crate(s): {}",
+            report.outdated.len(),
+            crates.join(", ")
+        ));
+    }
+
+    if !report.bumps.is_empty() {
+        let bumps = report
+            .bumps
+            .iter()
+            .map(|bump| format!("{} {} → {}", bump.krate, bump.old_version, bump.new_version))
+            .collect::<Vec<String>>();
+        parts.push(format!("bumped {}", bumps.join(", ")));
+    }
+
+    format!("cargo cvm: {}", parts.join("; "))
+}
+
+/// POST the report to the webhook if any crate is outdated or was bumped;
+pub fn notify(url: &str, report: &Report, net: &CargoNetConfig) -> Result<(), Error> {
+    if report.outdated.is_empty() && report.bumps.is_empty() {
+        return Ok(());
+    }
+
+    let agent = http::agent_builder(Duration::from_secs(30), net)?;
+
+    let notification = Notification {
+        text: summary(report),
+        report,
+    };
+
+    agent
+        .build()
+        .post(url)
+        .send_json(&notification)
+        .map_err(|e| Error::msg(format!("Failed to notify webhook: {}", e)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::{Bump, Report};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_summary() {
+        let mut report = Report::default();
+        report.bumps.push(Bump {
+            krate: String::from("foo"),
+            cargo_toml: PathBuf::from("crates/foo/Cargo.toml"),
+            old_version: String::from("0.1.0"),
+            new_version: String::from("0.2.0"),
+            reason: String::from("outdated"),
+        });
+
+        assert_eq!(
+            super::summary(&report),
+            "cargo cvm: bumped foo 0.1.0 → 0.2.0"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Error;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+/// Locations of the CODEOWNERS file relative to the repository root, in the order GitHub looks
+/// for it, followed by GitLab's;
+pub const LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// Owners of the files of the repository, by gitignore-style pattern, as in a CODEOWNERS file;
+#[derive(Default)]
+pub struct CodeOwners {
+    rules: Vec<(Gitignore, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Read the first CODEOWNERS file found, with `read` returning the contents of a path
+    /// relative to the repository root if it exists; no file means no owners;
+    pub fn find(read: impl Fn(&Path) -> Result<Option<String>, Error>) -> Result<Self, Error> {
+        for location in LOCATIONS.iter() {
+            if let Some(contents) = read(Path::new(location))? {
+                return Self::parse(&contents, location);
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    /// Parse the lines of a CODEOWNERS file, i.e. a pattern followed by owners, e.g.
+    /// `/crates/parser/ @org/parser-team`; a pattern without owners leaves its files unowned;
+    ///
+    /// GitLab's `[Section]` headers are skipped, and their rules merged.
+    pub fn parse(contents: &str, file: &str) -> Result<Self, Error> {
+        let mut rules = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let pattern = words.next().unwrap_or_default();
+            let owners = words
+                .take_while(|word| !word.starts_with('#'))
+                .map(String::from)
+                .collect::<Vec<String>>();
+
+            let mut builder = GitignoreBuilder::new("");
+            builder.add_line(None, pattern).map_err(|e| {
+                Error::msg(format!("Invalid pattern {:?} in {}: {}", pattern, file, e))
+            })?;
+            rules.push((builder.build()?, owners));
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Returns the owners of the file at `path`, relative to the repository root; the last
+    /// matching pattern takes precedence;
+    pub fn owners(&self, path: &Path) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matched_path_or_any_parents(path, false).is_ignore())
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeOwners;
+    use std::path::Path;
+
+    #[test]
+    fn test_owners() -> Result<(), anyhow::Error> {
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             * @org/core\n\
+             \n\
+             [Parser]\n\
+             /crates/parser/ @org/parser-team @jane # reviews API changes\n\
+             Cargo.toml @org/release\n\
+             /crates/vendored/\n",
+            "CODEOWNERS",
+        )?;
+
+        assert_eq!(
+            owners.owners(Path::new("crates/cli/src/main.rs")),
+            ["@org/core"]
+        );
+        assert_eq!(
+            owners.owners(Path::new("crates/parser/src/lib.rs")),
+            ["@org/parser-team", "@jane"]
+        );
+        // Unanchored patterns match at any depth;
+        assert_eq!(
+            owners.owners(Path::new("crates/parser/Cargo.toml")),
+            ["@org/release"]
+        );
+        assert!(owners
+            .owners(Path::new("crates/vendored/Cargo.toml"))
+            .is_empty());
+        assert!(CodeOwners::default()
+            .owners(Path::new("Cargo.toml"))
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Prefix tree over path components, mapping a path to the value of its longest inserted
+/// ancestor, e.g. a changed file to the member directory containing it;
+///
+/// Lookups take time proportional to the depth of the path, independent of the number of
+/// inserted paths.
+#[derive(Debug)]
+pub struct PathTrie<T> {
+    value: Option<T>,
+    children: HashMap<OsString, PathTrie<T>>,
+}
+
+impl<T> Default for PathTrie<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl<T> PathTrie<T> {
+    /// Insert `path`, replacing the value of an identical path;
+    pub fn insert(&mut self, path: &Path, value: T) {
+        let mut node = self;
+        for component in path.components() {
+            node = node
+                .children
+                .entry(component.as_os_str().to_os_string())
+                .or_default();
+        }
+        node.value = Some(value);
+    }
+
+    /// Returns the value of the longest inserted path that `path` starts with, matching
+    /// whole components only, i.e. `crates/foo` does not contain `crates/foobar`;
+    pub fn longest_prefix(&self, path: &Path) -> Option<&T> {
+        let mut node = self;
+        let mut found = node.value.as_ref();
+
+        for component in path.components() {
+            node = match node.children.get(component.as_os_str()) {
+                Some(child) => child,
+                None => break,
+            };
+            found = node.value.as_ref().or(found);
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathTrie;
+    use std::path::Path;
+
+    #[test]
+    fn test_longest_prefix() {
+        let mut trie = PathTrie::default();
+        trie.insert(Path::new("crates/foo/src"), "foo");
+        trie.insert(Path::new("crates/foo/src/nested/src"), "nested");
+
+        assert_eq!(
+            trie.longest_prefix(Path::new("crates/foo/src/lib.rs")),
+            Some(&"foo")
+        );
+        assert_eq!(
+            trie.longest_prefix(Path::new("crates/foo/src/nested/src/lib.rs")),
+            Some(&"nested")
+        );
+        assert_eq!(
+            trie.longest_prefix(Path::new("crates/foo/srcs/lib.rs")),
+            None
+        );
+        assert_eq!(
+            trie.longest_prefix(Path::new("crates/foo/Cargo.toml")),
+            None
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::report::Bump;
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+/// Version edits computed by `cargo cvm plan`, to be reviewed before `cargo cvm apply`;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Plan {
+    /// Commit the plan was computed on; applying it on another commit is refused;
+    pub head: String,
+    /// Bumps of the plan, with manifest paths relative to the repository root;
+    pub bumps: Vec<Bump>,
+}
+
+impl Plan {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let plan = read_to_string(path)
+            .map_err(|e| Error::msg(format!("Failed to read plan {:?}: {}", path.display(), e)))?;
+
+        serde_json::from_str(&plan)
+            .map_err(|e| Error::msg(format!("Invalid plan {:?}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_plan_format() -> Result<(), Box<dyn std::error::Error>> {
+        let plan: super::Plan = serde_json::from_str(
+            r#"{
+  "head": "8e76d09",
+  "bumps": [
+    {
+      "crate": "foo",
+      "cargo_toml": "crates/foo/Cargo.toml",
+      "old_version": "0.1.0",
+      "new_version": "0.2.0",
+      "reason": "outdated"
+    }
+  ]
+}"#,
+        )?;
+
+        assert_eq!(plan.bumps[0].krate, "foo");
+        assert_eq!(plan.bumps[0].new_version, "0.2.0");
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use crate::cargo_config::CargoNetConfig;
+use crate::http;
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{create_dir_all, read_to_string, rename, write};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Sparse index of crates.io;
+pub const CRATES_IO_INDEX: &str = "https://index.crates.io/";
+
+/// Number of times a request is retried after the index answered that it is rate limited;
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Longest wait honored from a `Retry-After` header;
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Index file as cached on disk, revalidated with its `ETag` or `Last-Modified` header;
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CachedFile {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    contents: String,
+}
+
+/// A line of a crate's index file, one per published version;
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    vers: String,
+}
+
+/// Returns the path of a crate's file in a registry index, e.g. `se/rd/serde`;
+pub fn index_path(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Client of a sparse registry index, e.g. `https://index.crates.io/`;
+///
+/// Index files are cached on disk and revalidated with conditional requests, which the index
+/// answers with `304 Not Modified` without counting them against its rate limits.
+pub struct SparseIndex {
+    agent: ureq::Agent,
+    url: String,
+    /// Directory caching the index files of this registry, if any;
+    cache: Option<PathBuf>,
+}
+
+impl SparseIndex {
+    /// `url` may carry cargo's `sparse+` prefix; index files are cached in a directory of
+    /// `cache` per registry, if given;
+    pub fn new(url: &str, net: &CargoNetConfig, cache: Option<&Path>) -> Result<Self, Error> {
+        let agent = http::agent_builder(Duration::from_secs(30), net)?;
+
+        let url = url.trim_start_matches("sparse+").trim_end_matches('/');
+        Ok(Self {
+            agent: agent.build(),
+            cache: cache.map(|dir| dir.join(cache_key(url))),
+            url: url.to_string(),
+        })
+    }
+
+    /// Returns the versions of the crate listed by the index, none if it was never published;
+    pub fn versions(&self, name: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .index_file(name)?
+            .map(|contents| parse_versions(&contents))
+            .unwrap_or_default())
+    }
+
+    /// Returns the index file of the crate, revalidating the cached copy if there is one, or
+    /// None if the crate was never published;
+    fn index_file(&self, name: &str) -> Result<Option<String>, Error> {
+        let path = index_path(name);
+        let url = format!("{}/{}", self.url, path);
+        let cache_file = self
+            .cache
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", path)));
+        let cached = cache_file
+            .as_ref()
+            .and_then(|file| read_to_string(file).ok())
+            .and_then(|contents| serde_json::from_str::<CachedFile>(&contents).ok());
+
+        let mut attempt = 1;
+        let response = loop {
+            // The index is served through a CDN, which may cache a file for a while;
+            let mut request = self.agent.get(&url).set("Cache-Control", "no-cache");
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.set("If-None-Match", etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.set("If-Modified-Since", last_modified);
+                }
+            }
+
+            match request.call() {
+                Ok(response) => break response,
+                Err(ureq::Error::Status(404, _)) | Err(ureq::Error::Status(410, _)) => {
+                    return Ok(None)
+                }
+                // Wait as long as a rate-limited index asks, or else back off exponentially;
+                Err(ureq::Error::Status(status, response))
+                    if (status == 429 || status == 503) && attempt <= RATE_LIMIT_RETRIES =>
+                {
+                    let wait = retry_after(response.header("Retry-After"))
+                        .unwrap_or_else(|| Duration::from_secs(1 << (attempt - 1)))
+                        .min(MAX_RETRY_AFTER);
+                    eprintln!(
+                        "The registry index answered {} for {}; retrying in {}s",
+                        status,
+                        name,
+                        wait.as_secs()
+                    );
+                    sleep(wait);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(Error::msg(format!(
+                        "Failed to query the registry index at {}: {}",
+                        url, e
+                    )))
+                }
+            }
+        };
+
+        if let (304, Some(cached)) = (response.status(), cached) {
+            return Ok(Some(cached.contents));
+        }
+
+        let cached = CachedFile {
+            etag: response.header("ETag").map(String::from),
+            last_modified: response.header("Last-Modified").map(String::from),
+            contents: response.into_string()?,
+        };
+        // A file without validators cannot be revalidated, so it is not worth caching;
+        if let Some(file) =
+            cache_file.filter(|_| cached.etag.is_some() || cached.last_modified.is_some())
+        {
+            if let Err(e) = write_cache(&file, &cached) {
+                eprintln!(
+                    "warning: failed to cache the registry index file {:?}: {}",
+                    file.display(),
+                    e
+                );
+            }
+        }
+
+        Ok(Some(cached.contents))
+    }
+
+    /// Poll the index every `interval` until it lists `version` of the crate, failing after
+    /// `timeout`; failed queries, e.g. on a network error, are retried until then;
+    pub fn wait_for(
+        &self,
+        name: &str,
+        version: &str,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let error = match self.versions(name) {
+                Ok(versions) if versions.iter().any(|listed| listed == version) => return Ok(()),
+                Ok(_) => None,
+                Err(e) => {
+                    eprintln!("warning: {}; retrying", e);
+                    Some(e)
+                }
+            };
+            if Instant::now() + interval > deadline {
+                return Err(Error::msg(format!(
+                    "{} {} did not appear in the registry index {} within {}s{}; raise --index-timeout if the registry is slow",
+                    name,
+                    version,
+                    self.url,
+                    timeout.as_secs(),
+                    error.map(|e| format!(", last query failed: {}", e)).unwrap_or_default()
+                )));
+            }
+            sleep(interval);
+        }
+    }
+}
+
+/// Returns the name of the cache directory of the registry at `url`;
+fn cache_key(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url.as_bytes()))[..16].to_string()
+}
+
+/// Replace the cached index file atomically, so that concurrent runs never read a partial one;
+fn write_cache(file: &Path, cached: &CachedFile) -> Result<(), Error> {
+    if let Some(dir) = file.parent() {
+        create_dir_all(dir)?;
+    }
+    let temp = file.with_extension(format!("tmp.{}", std::process::id()));
+    write(&temp, serde_json::to_string(cached)?)?;
+    rename(&temp, file)?;
+    Ok(())
+}
+
+/// Returns the wait asked for by a `Retry-After` header in seconds; HTTP dates are not
+/// supported;
+fn retry_after(header: Option<&str>) -> Option<Duration> {
+    header
+        .and_then(|secs| secs.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Returns the versions of an index file, skipping lines that do not parse;
+fn parse_versions(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<IndexEntry>(line).ok())
+        .map(|entry| entry.vers)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(super::index_path("a"), "1/a");
+        assert_eq!(super::index_path("io"), "2/io");
+        assert_eq!(super::index_path("Syn"), "3/s/syn");
+        assert_eq!(super::index_path("serde_json"), "se/rd/serde_json");
+
+        let contents = "{\"name\":\"foo\",\"vers\":\"0.1.0\",\"deps\":[],\"cksum\":\"00\",\"features\":{},\"yanked\":false}\n{\"name\":\"foo\",\"vers\":\"0.2.0\",\"deps\":[],\"cksum\":\"00\",\"features\":{},\"yanked\":false}\n";
+        assert_eq!(super::parse_versions(contents), vec!["0.1.0", "0.2.0"]);
+    }
+
+    #[test]
+    fn test_cache() -> Result<(), Box<dyn std::error::Error>> {
+        use super::{retry_after, write_cache, CachedFile};
+        use std::time::Duration;
+
+        assert_eq!(retry_after(Some(" 3")), Some(Duration::from_secs(3)));
+        assert_eq!(retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+        assert_eq!(retry_after(None), None);
+
+        // Registries get separate directories;
+        assert_ne!(
+            super::cache_key("https://index.crates.io"),
+            super::cache_key("https://my-registry.example.com/index")
+        );
+
+        let dir = TempDir::new("index")?;
+        let file = dir.path().join("se/rd/serde.json");
+        let cached = CachedFile {
+            etag: Some(String::from("\"abc\"")),
+            last_modified: None,
+            contents: String::from("{\"vers\":\"1.0.0\"}\n"),
+        };
+        write_cache(&file, &cached)?;
+        let read: CachedFile = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+        assert_eq!(read, cached);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+use crate::baseline::{BaselineEntry, BASELINE_FILE};
+use crate::ci;
+use crate::i18n::tr;
+use crate::manager::{
+    BelowFloor, IncompleteCrate, MetadataRegression, MissingChangelog, OutdatedCrate,
+    ReleaseTrainViolation, UnchangedBump, VersionJump, VersionMismatch, Waiver,
+};
+use crate::notes::describe_template_error;
+use anyhow::Error;
+use cargo_cvm::schema::SCHEMA_VERSION;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use tera::{Context, Tera};
+
+/// Format of the report printed by a check run;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    /// GitLab Code Quality report;
+    GitLab,
+    /// JUnit XML with a test case per crate, e.g. for Jenkins;
+    JUnit,
+    /// Render the report through a user-provided Tera template;
+    Template,
+}
+
+impl TryInto<OutputFormat> for &str {
+    type Error = Error;
+    fn try_into(self) -> Result<OutputFormat, Error> {
+        let format = match self {
+            "human" => OutputFormat::Human,
+            "json" => OutputFormat::Json,
+            "gitlab" => OutputFormat::GitLab,
+            "junit" => OutputFormat::JUnit,
+            _ => return Err(Error::msg(format!("Invalid output format: {:?}", self))),
+        };
+
+        Ok(format)
+    }
+}
+
+/// Result of a check run, printed at the end of the run for machine-readable formats;
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Version of the report format, see `cargo_cvm::schema`;
+    pub schema_version: SchemaVersion,
+    pub outdated: Vec<OutdatedCrate>,
+    /// Crates bumped by more than the release train of the target branch allows;
+    pub violations: Vec<ReleaseTrainViolation>,
+    /// Bumps skipping versions, rejected by `--check --strict`;
+    pub jumps: Vec<VersionJump>,
+    /// Crates bumped although their sources did not change, reported as warnings by `--check`;
+    pub unchanged_bumps: Vec<UnchangedBump>,
+    /// Status of each target branch the crates were compared against;
+    pub targets: Vec<TargetStatus>,
+    /// Versions changed by `--fix` or `--force`, in an order the crates can be published in;
+    pub bumps: Vec<Bump>,
+    /// Crates skipped because they opted out with `frozen = true` in `[package.metadata.cvm]`;
+    pub frozen: Vec<FrozenCrate>,
+    /// Crates that omit `package.version`, which Cargo defaults to `0.0.0` and never publishes;
+    pub unversioned: Vec<UnversionedCrate>,
+    /// Guarded package fields that were removed or changed compared to a target branch;
+    pub metadata: Vec<MetadataRegression>,
+    /// New crates that do not set the fields required by `new-crate-fields` in `.cvm.toml`;
+    pub incomplete: Vec<IncompleteCrate>,
+    /// Bumped crates whose changelog has no entry for the new version, see `[changelog]`;
+    pub changelog: Vec<MissingChangelog>,
+    /// Outdated crates exempted from the check by `[skip cvm]` or a `Cvm-Waive` trailer;
+    pub waivers: Vec<Waiver>,
+    /// Crates whose version is below their floor in `.cvm.toml`;
+    pub below_floor: Vec<BelowFloor>,
+    /// Members whose version differs from the unified version, see `--unified`;
+    pub mismatched: Vec<VersionMismatch>,
+    /// Path dependencies without a version, which `cargo publish` rejects;
+    pub path_deps: Vec<PathDependency>,
+    /// Manifests of crates that are new compared to the target branch, relative to the
+    /// repository root;
+    pub added: Vec<PathBuf>,
+    /// Manifests of crates that were deleted compared to the first target branch;
+    pub removed: Vec<PathBuf>,
+    /// Manifests of members outside the sparse-checkout cone, evaluated from git trees only;
+    pub sparse: Vec<PathBuf>,
+    /// Manifests of crates that are neither outdated nor new;
+    pub up_to_date: Vec<PathBuf>,
+    /// Findings above that `--check` tolerates because they are recorded in the baseline;
+    pub tolerated: Vec<BaselineEntry>,
+    /// Crate name of each manifest above, since package and directory names often differ;
+    pub crates: BTreeMap<PathBuf, String>,
+}
+
+/// Serializes as the current `schema::SCHEMA_VERSION`;
+#[derive(Debug, Default)]
+pub struct SchemaVersion;
+
+impl Serialize for SchemaVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(SCHEMA_VERSION)
+    }
+}
+
+/// A crate whose version is frozen, e.g. an archived crate kept for history;
+#[derive(Debug, Serialize)]
+pub struct FrozenCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+}
+
+impl std::fmt::Display for FrozenCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version of {} is frozen, skipping workspace Cargo.toml file: {:?}",
+            self.krate,
+            self.cargo_toml.display()
+        )
+    }
+}
+
+/// A crate that omits `package.version`, so that it is not publishable;
+#[derive(Debug, Serialize)]
+pub struct UnversionedCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    /// Whether `require-version` in `.cvm.toml` makes the omitted version an error;
+    pub required: bool,
+}
+
+impl std::fmt::Display for UnversionedCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.required {
+            write!(
+                f,
+                "{} omits `package.version` in {:?}, but `require-version` is set in .cvm.toml",
+                self.krate,
+                self.cargo_toml.display()
+            )
+        } else {
+            write!(
+                f,
+                "version of {} is omitted (0.0.0, not publishable), skipping workspace Cargo.toml file: {:?}",
+                self.krate,
+                self.cargo_toml.display()
+            )
+        }
+    }
+}
+
+/// A path dependency without a version, e.g. `foo = { path = "../foo" }`;
+#[derive(Debug, Serialize)]
+pub struct PathDependency {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    /// Name of the package depended on;
+    pub dependency: String,
+    /// Dependency table declaring it, e.g. `build-dependencies`;
+    pub table: String,
+    pub path: String,
+    /// Whether `--fix-path-deps` added the current version of the dependency;
+    pub fixed: bool,
+}
+
+impl std::fmt::Display for PathDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} depends on {} by path {:?} in [{}] without a version, which `cargo publish` rejects; add its version or run with --fix-path-deps: {:?}",
+            self.krate,
+            self.dependency,
+            self.path,
+            self.table,
+            self.cargo_toml.display()
+        )
+    }
+}
+
+/// A version change applied by the run;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bump {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: String,
+    pub new_version: String,
+    /// Why the version was changed; i.e. `outdated` or `forced`;
+    pub reason: String,
+}
+
+/// Number of outdated crates compared to a single target branch;
+#[derive(Debug, Serialize)]
+pub struct TargetStatus {
+    pub target: String,
+    pub outdated: usize,
+}
+
+impl Report {
+    /// Returns the waiver of an outdated crate, if any;
+    pub fn waiver(&self, krate: &OutdatedCrate) -> Option<&Waiver> {
+        self.waivers
+            .iter()
+            .find(|waiver| waiver.cargo_toml == krate.cargo_toml)
+    }
+
+    /// Returns the crate name of the manifest followed by the manifest, or only the manifest if
+    /// its name is not known, e.g. `foo (crates/foo-rs/Cargo.toml)`;
+    pub fn label(&self, manifest: &Path) -> String {
+        match self.crates.get(manifest) {
+            Some(name) => format!("{} ({})", name, manifest.display()),
+            None => manifest.display().to_string(),
+        }
+    }
+
+    /// Print the crates of a check grouped by status: outdated crates first, then added,
+    /// removed, frozen, and unversioned crates, then the up-to-date crates, which are only
+    /// counted unless `show_ok` is set;
+    pub fn print_grouped(&self, show_ok: bool) {
+        if !self.outdated.is_empty() {
+            eprintln!(
+                "{}",
+                tr("outdated-heading", &[("count", &self.outdated.len())])
+            );
+            for krate in self.outdated.iter() {
+                let message = krate.describe().replace('\n', "\n  ");
+                if self.tolerated.contains(&BaselineEntry::outdated(krate)) {
+                    eprintln!(
+                        "  {}\n      {}",
+                        message,
+                        tr("tolerated-by", &[("file", &BASELINE_FILE)])
+                    );
+                } else if let Some(waiver) = self.waiver(krate) {
+                    eprintln!("  {}\n      {}", message, waiver);
+                } else {
+                    eprintln!("  {}", message);
+                }
+            }
+        }
+
+        if !self.metadata.is_empty() {
+            eprintln!(
+                "{}",
+                tr("metadata-heading", &[("count", &self.metadata.len())])
+            );
+            self.metadata
+                .iter()
+                .for_each(|regression| eprintln!("  {}", regression));
+        }
+
+        if !self.incomplete.is_empty() {
+            eprintln!(
+                "{}",
+                tr("incomplete-heading", &[("count", &self.incomplete.len())])
+            );
+            self.incomplete
+                .iter()
+                .for_each(|incomplete| eprintln!("  {}", incomplete));
+        }
+
+        if !self.changelog.is_empty() {
+            eprintln!(
+                "{}",
+                tr("changelog-heading", &[("count", &self.changelog.len())])
+            );
+            self.changelog
+                .iter()
+                .for_each(|missing| eprintln!("  {}", missing));
+        }
+
+        if !self.below_floor.is_empty() {
+            eprintln!(
+                "{}",
+                tr("below-floor-heading", &[("count", &self.below_floor.len())])
+            );
+            self.below_floor
+                .iter()
+                .for_each(|below| eprintln!("  {}", below));
+        }
+
+        if !self.mismatched.is_empty() {
+            eprintln!(
+                "{}",
+                tr("mismatched-heading", &[("count", &self.mismatched.len())])
+            );
+            self.mismatched
+                .iter()
+                .for_each(|mismatch| eprintln!("  {}", mismatch));
+        }
+
+        let path_deps = self
+            .path_deps
+            .iter()
+            .filter(|dep| !dep.fixed)
+            .collect::<Vec<&PathDependency>>();
+        if !path_deps.is_empty() {
+            eprintln!(
+                "{}",
+                tr("path-deps-heading", &[("count", &path_deps.len())])
+            );
+            path_deps.iter().for_each(|dep| eprintln!("  {}", dep));
+        }
+
+        let groups = [
+            ("added-heading", self.added.clone()),
+            ("removed-heading", self.removed.clone()),
+            ("sparse-heading", self.sparse.clone()),
+            (
+                "frozen-heading",
+                self.frozen
+                    .iter()
+                    .map(|krate| krate.cargo_toml.clone())
+                    .collect(),
+            ),
+            (
+                "unversioned-heading",
+                self.unversioned
+                    .iter()
+                    .map(|krate| krate.cargo_toml.clone())
+                    .collect(),
+            ),
+        ];
+        for (heading, manifests) in groups.iter() {
+            if !manifests.is_empty() {
+                eprintln!("{}", tr(heading, &[("count", &manifests.len())]));
+                manifests
+                    .iter()
+                    .for_each(|manifest| eprintln!("  {}", self.label(manifest)));
+            }
+        }
+
+        if show_ok {
+            eprintln!(
+                "{}",
+                tr("up-to-date-heading", &[("count", &self.up_to_date.len())])
+            );
+            self.up_to_date
+                .iter()
+                .for_each(|manifest| eprintln!("  {}", self.label(manifest)));
+        } else if !self.up_to_date.is_empty() {
+            eprintln!(
+                "{}",
+                tr("up-to-date-count", &[("count", &self.up_to_date.len())])
+            );
+        }
+    }
+
+    /// Print the report; `manifests` are the manifests of every checked crate, relative to the
+    /// repository root `root`, as are the manifests of the findings;
+    pub fn print(
+        &self,
+        format: OutputFormat,
+        template: Option<&str>,
+        root: &Path,
+        manifests: &[PathBuf],
+    ) -> Result<(), Error> {
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+        } else if format == OutputFormat::GitLab {
+            println!("{}", ci::code_quality(self, root));
+        } else if format == OutputFormat::JUnit {
+            print!("{}", ci::junit(self, root, manifests));
+        } else if let (OutputFormat::Template, Some(template)) = (format, template) {
+            let context = Context::from_serialize(self)?;
+            let rendered = Tera::one_off(template, &context, false).map_err(|e| {
+                Error::msg(format!(
+                    "Failed to render the template: {}",
+                    describe_template_error(&e)
+                ))
+            })?;
+            print!("{}", rendered);
+        } else if self.targets.len() > 1 {
+            // Summarize each target branch when checking several at once;
+            for status in self.targets.iter() {
+                if status.outdated == 0 {
+                    println!("{}", tr("target-up-to-date", &[("target", &status.target)]));
+                } else {
+                    println!(
+                        "{}",
+                        tr(
+                            "target-outdated",
+                            &[("target", &status.target), ("count", &status.outdated)]
+                        )
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bump, Report};
+    use crate::manager::{ChangedFile, LastChange, OutdatedCrate, SemVer, Version};
+    use cargo_cvm::schema::{self, SCHEMA_VERSION};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_report_matches_schema() -> Result<(), anyhow::Error> {
+        let mut report = Report::default();
+        report.outdated.push(OutdatedCrate {
+            krate: String::from("foo"),
+            version: Version::parse("0.1.0", false)?,
+            cargo_toml: PathBuf::from("crates/foo/Cargo.toml"),
+            changed_files: vec![ChangedFile {
+                path: PathBuf::from("src/lib.rs"),
+                rule: String::from("src/**"),
+                semver: SemVer::Minor,
+            }],
+            suggested_version: Version::parse("0.2.0", false)?,
+            suggested_semver: SemVer::Minor,
+            reason: String::from("default level"),
+            target: String::from("origin/master"),
+            last_change: Some(LastChange {
+                commit: String::from("1a2b3c4"),
+                author: String::from("Jane Doe"),
+                subject: String::from("feat: add parser option"),
+            }),
+            owners: vec![String::from("@org/parser-team")],
+        });
+        report.bumps.push(Bump {
+            krate: String::from("foo"),
+            cargo_toml: PathBuf::from("crates/foo/Cargo.toml"),
+            old_version: String::from("0.1.0"),
+            new_version: String::from("0.2.0"),
+            reason: String::from("outdated"),
+        });
+
+        // Fields missing from the schema are lost in the round trip;
+        let json = serde_json::to_value(&report)?;
+        let parsed: schema::Report = serde_json::from_value(json.clone())?;
+        assert_eq!(serde_json::to_value(&parsed)?, json);
+
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+        assert_eq!(parsed.outdated[0].suggested_version, "0.2.0");
+        assert_eq!(
+            parsed.outdated[0].changed_files[0].semver,
+            schema::SemVer::Minor
+        );
+        assert_eq!(
+            parsed.outdated[0]
+                .last_change
+                .as_ref()
+                .map(|change| change.author.as_str()),
+            Some("Jane Doe")
+        );
+        assert_eq!(parsed.bumps[0].krate, "foo");
+        assert_eq!(parsed.outdated[0].krate, "foo");
+
+        let manifest = PathBuf::from("crates/foo-rs/Cargo.toml");
+        assert_eq!(report.label(&manifest), "crates/foo-rs/Cargo.toml");
+        report.crates.insert(manifest.clone(), String::from("foo"));
+        assert_eq!(report.label(&manifest), "foo (crates/foo-rs/Cargo.toml)");
+        Ok(())
+    }
+}
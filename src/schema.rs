@@ -0,0 +1,238 @@
+//! Schema of the JSON report printed by `cargo cvm --output json`;
+//!
+//! Every report carries `schema_version`. Fields are only added within a schema version;
+//! renaming or removing a field, or changing its type, increments it, so parsers can refuse
+//! reports of a version they do not know. Fields added since the first release of a schema
+//! version are `#[serde(default)]`, so that its earlier reports, which lack them, still parse.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Version of the JSON report format printed by this release;
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Report of a run, see `Report` of the binary for the meaning of each field;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub outdated: Vec<OutdatedCrate>,
+    pub violations: Vec<ReleaseTrainViolation>,
+    pub jumps: Vec<VersionJump>,
+    pub unchanged_bumps: Vec<VersionChange>,
+    pub targets: Vec<TargetStatus>,
+    pub bumps: Vec<Bump>,
+    pub frozen: Vec<FrozenCrate>,
+    pub unversioned: Vec<UnversionedCrate>,
+    #[serde(default)]
+    pub metadata: Vec<MetadataRegression>,
+    #[serde(default)]
+    pub incomplete: Vec<IncompleteCrate>,
+    #[serde(default)]
+    pub changelog: Vec<MissingChangelog>,
+    #[serde(default)]
+    pub waivers: Vec<Waiver>,
+    #[serde(default)]
+    pub below_floor: Vec<BelowFloor>,
+    #[serde(default)]
+    pub mismatched: Vec<VersionMismatch>,
+    #[serde(default)]
+    pub path_deps: Vec<PathDependency>,
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    #[serde(default)]
+    pub sparse: Vec<PathBuf>,
+    pub up_to_date: Vec<PathBuf>,
+    pub tolerated: Vec<BaselineEntry>,
+    #[serde(default)]
+    pub crates: BTreeMap<PathBuf, String>,
+}
+
+/// Level of a version bump;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemVer {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// A crate whose sources changed without a version bump;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutdatedCrate {
+    #[serde(rename = "crate", default)]
+    pub krate: String,
+    pub version: String,
+    pub cargo_toml: PathBuf,
+    pub changed_files: Vec<ChangedFile>,
+    pub suggested_version: String,
+    pub suggested_semver: SemVer,
+    pub reason: String,
+    pub target: String,
+    #[serde(default)]
+    pub last_change: Option<LastChange>,
+    #[serde(default)]
+    pub owners: Vec<String>,
+}
+
+/// The most recent commit that changed the sources of an outdated crate;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastChange {
+    /// Short id of the commit;
+    pub commit: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// A changed file relative to the crate directory, and the rule that made it require a bump;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: PathBuf,
+    pub rule: String,
+    pub semver: SemVer,
+}
+
+/// A bump by more than the release train of the target branch allows;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseTrainViolation {
+    #[serde(rename = "crate", default)]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: String,
+    pub new_version: String,
+    pub semver: SemVer,
+    pub allowed: SemVer,
+    pub rule: String,
+    pub target: String,
+}
+
+/// A bump skipping versions, rejected by `--check --strict`;
+pub type VersionJump = VersionChange;
+
+/// A version change of a crate compared to a target branch;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionChange {
+    #[serde(rename = "crate", default)]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: String,
+    pub new_version: String,
+    pub target: String,
+}
+
+/// Number of outdated crates compared to a single target branch;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetStatus {
+    pub target: String,
+    pub outdated: usize,
+}
+
+/// A version change applied by `--fix` or `--force`;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bump {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub old_version: String,
+    pub new_version: String,
+    pub reason: String,
+}
+
+/// A crate skipped because its version is frozen;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrozenCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+}
+
+/// A crate that omits `package.version`;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnversionedCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub required: bool,
+}
+
+/// A guarded package field that was removed or changed compared to a target branch;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataRegression {
+    #[serde(rename = "crate", default)]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: Option<String>,
+    pub target: String,
+}
+
+/// A new crate that does not set the fields required of its first release;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncompleteCrate {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub missing: Vec<String>,
+}
+
+/// A bumped crate whose changelog has no entry for the new version;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingChangelog {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub changelog: PathBuf,
+    pub version: String,
+    pub target: String,
+}
+
+/// An outdated crate exempted from the check by a commit message;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waiver {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub commit: String,
+    pub reason: String,
+}
+
+/// A crate whose version is below its floor in `.cvm.toml`;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BelowFloor {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub version: String,
+    pub floor: String,
+}
+
+/// A member whose version differs from the unified version;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionMismatch {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub version: String,
+    pub unified: String,
+}
+
+/// A path dependency without a version, which `cargo publish` rejects;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathDependency {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub cargo_toml: PathBuf,
+    pub dependency: String,
+    pub table: String,
+    pub path: String,
+    pub fixed: bool,
+}
+
+/// A finding tolerated by the baseline;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub check: String,
+    pub cargo_toml: PathBuf,
+    pub version: String,
+}
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+
+/// The `IdentityFile` entries of an OpenSSH client configuration that apply to `host`, in the
+/// order ssh tries them;
+///
+/// Only `Host` blocks are evaluated; `Match` blocks are skipped.
+pub fn identity_files(config: &str, host: &str, home: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    // Entries before the first `Host` line apply to every host;
+    let mut applies = true;
+
+    for line in config.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+            Some(i) => (
+                &line[..i],
+                line[i..].trim_start_matches(|c: char| c.is_whitespace() || c == '='),
+            ),
+            None => continue,
+        };
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => applies = host_matches(value, host),
+            "match" => applies = false,
+            "identityfile" if applies => {
+                files.push(expand(value.trim_matches('"'), host, home));
+            }
+            _ => {}
+        }
+    }
+
+    files
+}
+
+/// Returns true if the whitespace separated `Host` patterns match `host`, i.e. any pattern
+/// matches and no negated pattern does;
+fn host_matches(patterns: &str, host: &str) -> bool {
+    let mut matched = false;
+
+    for pattern in patterns.split_whitespace() {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, host) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, host),
+        }
+    }
+
+    matched
+}
+
+/// Match `text` against a pattern of `*` and `?` wildcards, case-insensitively as ssh does;
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase().chars().collect::<Vec<char>>();
+    let text = text.to_ascii_lowercase().chars().collect::<Vec<char>>();
+
+    // matches[i][j] is true if pattern[..i] matches text[..j];
+    let mut matches = vec![vec![false; text.len() + 1]; pattern.len() + 1];
+    matches[0][0] = true;
+
+    for i in 1..=pattern.len() {
+        for j in 0..=text.len() {
+            matches[i][j] = match pattern[i - 1] {
+                '*' => matches[i - 1][j] || (j > 0 && matches[i][j - 1]),
+                '?' => j > 0 && matches[i - 1][j - 1],
+                c => j > 0 && matches[i - 1][j - 1] && text[j - 1] == c,
+            };
+        }
+    }
+
+    matches[pattern.len()][text.len()]
+}
+
+/// Expand `~` and the `%d` (home directory) and `%h` (host) tokens of an `IdentityFile` path;
+fn expand(path: &str, host: &str, home: &Path) -> PathBuf {
+    let home = home.display().to_string();
+    let path = path.replace("%d", &home).replace("%h", host);
+
+    match path.strip_prefix("~/") {
+        Some(rest) => Path::new(&home).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_identity_files() {
+        let config = r#"
+IdentityFile ~/.ssh/default
+
+Host github.com !gist.github.com
+    IdentityFile ~/.ssh/github
+
+Host *.example.com
+    User git
+    IdentityFile=%d/.ssh/%h
+
+Match host github.com
+    IdentityFile ~/.ssh/never
+"#;
+        let home = Path::new("/home/me");
+
+        assert_eq!(
+            super::identity_files(config, "github.com", home),
+            vec![
+                PathBuf::from("/home/me/.ssh/default"),
+                PathBuf::from("/home/me/.ssh/github"),
+            ]
+        );
+        assert_eq!(
+            super::identity_files(config, "git.example.com", home),
+            vec![
+                PathBuf::from("/home/me/.ssh/default"),
+                PathBuf::from("/home/me/.ssh/git.example.com"),
+            ]
+        );
+        assert_eq!(
+            super::identity_files(config, "gist.github.com", home),
+            vec![PathBuf::from("/home/me/.ssh/default")]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, remove_file, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the file inside the `.git` directory recording the most recent fix run;
+pub const STATE_FILE: &str = "cvm-fix-state.toml";
+
+/// Manifest edits applied by the most recent `--fix` or `--force` run, used by
+/// `cargo cvm undo` to restore the previous versions;
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FixState {
+    /// Commit created by `--commit`, if any;
+    pub commit: Option<String>,
+    pub manifests: Vec<ManifestEdit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEdit {
+    /// Name of the package;
+    pub name: String,
+    pub path: PathBuf,
+    pub old_version: String,
+    pub new_version: String,
+    /// Contents of the manifest before it was rewritten;
+    pub original: String,
+}
+
+impl FixState {
+    pub fn load(git_dir: &Path) -> Result<Option<Self>, Error> {
+        let path = git_dir.join(STATE_FILE);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(toml::from_str(&read_to_string(&path)?)?))
+    }
+
+    pub fn save(&self, git_dir: &Path) -> Result<(), Error> {
+        let mut file = File::create(git_dir.join(STATE_FILE))?;
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn clear(git_dir: &Path) -> Result<(), Error> {
+        let path = git_dir.join(STATE_FILE);
+
+        if path.exists() {
+            remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use crate::manager::Version;
+use git2::Oid;
+use serde::Serialize;
+
+/// A tag marking the release of a version of a crate;
+#[derive(Debug, Clone)]
+pub struct ReleaseTag {
+    pub name: String,
+    pub version: Version,
+    pub commit: Oid,
+}
+
+/// The last release of a crate, and the commits that changed it since, see
+/// `cargo cvm last-release`;
+#[derive(Debug, Serialize)]
+pub struct LastRelease {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub version: String,
+    pub tag: String,
+    /// Short id of the tagged commit;
+    pub commit: String,
+    /// UTC time of the tagged commit in RFC 3339 format;
+    pub date: String,
+    /// Number of commits since the tag that changed the crate;
+    pub commits_since: usize,
+}
+
+impl std::fmt::Display for LastRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} released as {} at {} on {}\n{} commit(s) changed it since",
+            self.krate, self.version, self.tag, self.commit, self.date, self.commits_since
+        )
+    }
+}
+
+/// Returns the tag prefixes of the crate `name` unless set by `tag-prefix` in
+/// `[package.metadata.cvm]`: `<name>-v`, as written by release tools such as cargo-release for
+/// workspace members, and also `v` for the package at the root of the repository;
+pub fn default_prefixes(name: &str, root: bool) -> Vec<String> {
+    let mut prefixes = vec![format!("{}-v", name)];
+    if root {
+        prefixes.push(String::from("v"));
+    }
+    prefixes
+}
+
+/// Returns the version of the tag if it is one of the prefixes followed by a version, e.g.
+/// `0.9.1` for `foo_0.9.1` with the prefix `foo_`;
+pub fn tag_version(tag: &str, prefixes: &[String]) -> Option<Version> {
+    prefixes
+        .iter()
+        .filter_map(|prefix| tag.strip_prefix(prefix.as_str()))
+        .find_map(|version| Version::parse(version, false).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_prefixes, tag_version};
+
+    #[test]
+    fn test_tag_version() {
+        let version =
+            |tag: &str, prefixes: &[String]| tag_version(tag, prefixes).map(|v| v.to_string());
+
+        let prefixes = default_prefixes("foo", false);
+        assert_eq!(version("foo-v0.9.1", &prefixes).as_deref(), Some("0.9.1"));
+        assert_eq!(version("v0.9.1", &prefixes), None);
+        assert_eq!(version("foo-bar-v0.9.1", &prefixes), None);
+        assert_eq!(
+            version("v1.0.0", &default_prefixes("foo", true)).as_deref(),
+            Some("1.0.0")
+        );
+
+        let prefixes = vec![String::from("rust-v"), String::from("foo_")];
+        assert_eq!(version("rust-v0.9.1", &prefixes).as_deref(), Some("0.9.1"));
+        assert_eq!(version("foo_0.9.1", &prefixes).as_deref(), Some("0.9.1"));
+        assert_eq!(version("foo_latest", &prefixes), None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of directories created by this test run, which keeps their names unique between
+/// tests running in parallel;
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Directory of a test under the system's temporary directory, removed with its contents when
+/// dropped, so also when the test fails;
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory whose name starts with `cvm-{name}`, unique to this test run
+    /// and to each call;
+    pub fn new(name: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "cvm-{}-{}-{}",
+            name,
+            std::process::id(),
+            CREATED.fetch_add(1, Ordering::SeqCst)
+        ));
+        // Left over by a run that was killed, with the same process id;
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)?;
+
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
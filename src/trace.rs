@@ -0,0 +1,199 @@
+//! Timing of the phases of a run, see `--trace-file`;
+//!
+//! The few spans of a run are written by hand rather than with `tracing` and `tracing-chrome`,
+//! which would add a subscriber stack to every build for a diagnostic flag, and whose spans end
+//! in destructors that `std::process::exit` skips.
+
+use anyhow::Error;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Trace file of the run, see `--trace-file`;
+static TRACE: OnceLock<Trace> = OnceLock::new();
+
+/// Number of the next thread recording a span, as the thread id of the trace;
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(1);
+
+/// Number of the next span, to find it among the open spans;
+static NEXT_SPAN: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    static THREAD: usize = NEXT_THREAD.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Spans written in the Trace Event Format of Chrome, as loaded by `chrome://tracing` and
+/// Perfetto; each span is written once it ends, so that the file is usable even if the run
+/// exits early, since the closing `]` of the array is optional;
+struct Trace {
+    start: Instant,
+    file: Mutex<File>,
+    /// Spans not ended yet, by number, which `finish` ends, e.g. `run` when the run exits
+    /// from within it;
+    open: Mutex<BTreeMap<usize, OpenSpan>>,
+    /// Whether the array is closed, after which spans are no longer written;
+    finished: AtomicBool,
+}
+
+/// A span recorded but not ended yet;
+struct OpenSpan {
+    name: &'static str,
+    detail: String,
+    start: Instant,
+    thread: usize,
+}
+
+/// Phase of the run, ending when dropped;
+#[must_use]
+pub struct Span {
+    /// Number of the span among the open spans, none if the run is not traced;
+    id: Option<usize>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let (Some(trace), Some(id)) = (TRACE.get(), self.id) {
+            trace.end(id);
+        }
+    }
+}
+
+impl Trace {
+    /// Write the span `id` as ending now, unless it already ended;
+    fn end(&self, id: usize) {
+        let span = match self.open.lock() {
+            Ok(mut open) => open.remove(&id),
+            Err(_) => None,
+        };
+        let span = match span {
+            Some(span) => span,
+            None => return,
+        };
+
+        let event = serde_json::json!({
+            "name": span.name,
+            "cat": "cvm",
+            "ph": "X",
+            "ts": span.start.duration_since(self.start).as_micros() as u64,
+            "dur": span.start.elapsed().as_micros() as u64,
+            "pid": std::process::id(),
+            "tid": span.thread,
+            "args": { "detail": span.detail },
+        });
+        self.write(&format!("{},\n", event));
+    }
+
+    /// Append to the file; a failed write loses the span rather than fail the run;
+    fn write(&self, contents: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            if !self.finished.load(Ordering::SeqCst) {
+                let _ = file.write_all(contents.as_bytes());
+            }
+        }
+    }
+}
+
+/// Start writing the spans of the run to `path`;
+pub fn init(path: &Path) -> Result<(), Error> {
+    let mut file = File::create(path).map_err(|e| {
+        Error::msg(format!(
+            "Failed to create the trace file {:?}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    file.write_all(b"[\n")?;
+
+    let _ = TRACE.set(Trace {
+        start: Instant::now(),
+        file: Mutex::new(file),
+        open: Mutex::new(BTreeMap::new()),
+        finished: AtomicBool::new(false),
+    });
+    Ok(())
+}
+
+/// Start a span of the phase `name`, e.g. `fetch`, with `detail` naming what it works on, e.g.
+/// the branch; without `--trace-file`, spans are not recorded;
+pub fn span(name: &'static str, detail: impl Into<String>) -> Span {
+    let trace = match TRACE.get() {
+        Some(trace) => trace,
+        None => return Span { id: None },
+    };
+
+    let id = NEXT_SPAN.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut open) = trace.open.lock() {
+        open.insert(
+            id,
+            OpenSpan {
+                name,
+                detail: detail.into(),
+                start: Instant::now(),
+                thread: THREAD.with(|thread| *thread),
+            },
+        );
+    }
+    Span { id: Some(id) }
+}
+
+/// Close the array of spans, ending the spans still open, e.g. before `std::process::exit`,
+/// which skips the destructors ending them;
+pub fn finish() {
+    if let Some(trace) = TRACE.get() {
+        let open = match trace.open.lock() {
+            Ok(open) => open.keys().rev().copied().collect::<Vec<usize>>(),
+            Err(_) => Vec::new(),
+        };
+        for id in open {
+            trace.end(id);
+        }
+
+        let name = serde_json::json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": std::process::id(),
+            "args": { "name": "cargo cvm" },
+        });
+        trace.write(&format!("{}\n]\n", name));
+        trace.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{finish, init, span};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_trace() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new("trace")?;
+        let path = dir.path().join("trace.json");
+        init(&path)?;
+        {
+            let _outer = span("test-outer", "master");
+            let _inner = span("test-inner", "master");
+        }
+        // Spans still open when the run exits end with it;
+        let open = span("test-open", "master");
+        finish();
+        drop(open);
+
+        let events: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        let event = |name: &str| events.iter().find(|event| event["name"] == name).cloned();
+        let (outer, inner) = (event("test-outer").unwrap(), event("test-inner").unwrap());
+        assert_eq!(outer["ph"], "X");
+        assert_eq!(outer["args"]["detail"], "master");
+        // Nested spans end first, within their parent;
+        assert!(inner["ts"].as_u64() >= outer["ts"].as_u64());
+        assert!(inner["dur"].as_u64() <= outer["dur"].as_u64());
+        assert!(event("process_name").is_some());
+        assert!(event("test-open").is_some());
+
+        Ok(())
+    }
+}
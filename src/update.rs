@@ -0,0 +1,163 @@
+use crate::cargo_config::CargoNetConfig;
+use crate::http;
+use crate::manager::Version;
+use anyhow::Error;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+use std::fs::{rename, write};
+use std::io::Read;
+use std::time::Duration;
+
+/// GitHub API endpoint of the latest release;
+pub const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/infinyon/cargo-cvm/releases/latest";
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// Name of the release artifact for the current platform, e.g. `cargo-cvm-x86_64-linux`;
+/// its checksum is published as the same name with a `.sha256` suffix;
+pub fn artifact_name() -> String {
+    format!(
+        "cargo-cvm-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+fn agent() -> Result<ureq::Agent, Error> {
+    let net = CargoNetConfig::load(&std::env::current_dir()?)?;
+    Ok(http::agent_builder(Duration::from_secs(60), &net)?.build())
+}
+
+fn get(agent: &ureq::Agent, url: &str) -> Result<ureq::Response, Error> {
+    let mut request = agent.get(url);
+
+    // Authenticate to avoid the rate limit of anonymous API requests, e.g. in CI;
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+
+    request
+        .call()
+        .map_err(|e| Error::msg(format!("Failed to reach GitHub: {}", e)))
+}
+
+fn download(agent: &ureq::Agent, url: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    get(agent, url)?.into_reader().read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns the expected SHA-256 digest from a checksum file in `sha256sum` format;
+pub fn parse_checksum(contents: &str) -> Option<String> {
+    contents
+        .split_whitespace()
+        .next()
+        .filter(|digest| digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|digest| digest.to_ascii_lowercase())
+}
+
+/// Replace the running binary with the latest release if it is newer, checking the integrity of
+/// the download against its checksum; the checksum comes from the same release, so it catches
+/// corrupted downloads but does not authenticate the release;
+pub fn self_update(dry_run: bool) -> Result<(), Error> {
+    let agent = agent()?;
+    let release: Release = get(&agent, LATEST_RELEASE_URL)?.into_json()?;
+
+    let current: Version = env!("CARGO_PKG_VERSION").to_string().try_into()?;
+    let latest: Version = release
+        .tag_name
+        .trim_start_matches('v')
+        .to_string()
+        .try_into()?;
+
+    if latest <= current {
+        println!("cargo-cvm {} is up to date", current);
+        return Ok(());
+    }
+
+    println!("cargo-cvm {} is available (installed: {})", latest, current);
+    if dry_run {
+        return Ok(());
+    }
+
+    let name = artifact_name();
+    let find = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| {
+                Error::msg(format!(
+                    "Release {} has no artifact {:?}; install it with `cargo install cargo-cvm` instead",
+                    release.tag_name, name
+                ))
+            })
+    };
+    let binary = find(&name)?;
+    let checksum = find(&format!("{}.sha256", name))?;
+
+    let expected = parse_checksum(&String::from_utf8_lossy(&download(
+        &agent,
+        &checksum.browser_download_url,
+    )?))
+    .ok_or_else(|| Error::msg(format!("Invalid checksum file {:?}", checksum.name)))?;
+    let bytes = download(&agent, &binary.browser_download_url)?;
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+
+    if actual != expected {
+        return Err(Error::msg(format!(
+            "Checksum mismatch for {:?}: expected {}, got {}",
+            name, expected, actual
+        )));
+    }
+
+    // Write next to the current binary, then rename over it, which also works while it runs;
+    let current_exe = std::env::current_exe()?;
+    let new_exe = current_exe.with_extension("new");
+    write(&new_exe, &bytes)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new_exe, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows cannot replace a running binary, but can move it aside;
+    if cfg!(windows) {
+        rename(&current_exe, current_exe.with_extension("old"))?;
+    }
+    rename(&new_exe, &current_exe)?;
+
+    println!(
+        "Updated {:?} to cargo-cvm {}",
+        current_exe.display(),
+        latest
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse_checksum() {
+        let digest = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        assert_eq!(
+            super::parse_checksum(&format!("{}  cargo-cvm-x86_64-linux\n", digest)),
+            Some(digest.to_ascii_lowercase())
+        );
+        assert_eq!(super::parse_checksum("not a checksum"), None);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::i18n::tr;
+use anyhow::Error;
+use std::fmt;
+
+/// Exit code of runs failed by a warning with `--strict`;
+pub const STRICT_EXIT_CODE: i32 = 4;
+
+/// Warning turned into an error by `--strict`;
+#[derive(Debug)]
+pub struct StrictWarning(pub String);
+
+impl fmt::Display for StrictWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", tr("strict-warning", &[("message", &self.0)]))
+    }
+}
+
+impl std::error::Error for StrictWarning {}
+
+/// Print `message` as a warning, or fail with it if `strict` is set;
+pub fn warn(strict: bool, message: impl Into<String>) -> Result<(), Error> {
+    let message = message.into();
+
+    if strict {
+        return Err(StrictWarning(message).into());
+    }
+
+    eprintln!("{}", tr("warning", &[("message", &message)]));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warn() {
+        assert!(warn(false, "dirty working directory").is_ok());
+
+        let e = warn(true, "dirty working directory").unwrap_err();
+        assert!(e.downcast_ref::<StrictWarning>().is_some());
+        assert_eq!(
+            e.to_string(),
+            "dirty working directory (warnings are errors with --strict)"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::fields::{self, FieldValue};
+use crate::fs::FileSystem;
+use crate::manager::{manifest_version, Version};
+use anyhow::Error;
+use cargo_toml::Manifest;
+use git2::Oid;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use toml_edit::DocumentMut;
+
+/// Member paths and manifests of the workspace, built once per run and shared by all
+/// operations, so that each manifest is read and parsed at most once;
+///
+/// Manifests are loaded on first use, and replaced when the run edits them.
+pub struct WorkspaceModel {
+    /// Workspace root, i.e. the directory CVM runs in;
+    root: PathBuf,
+    /// Member directories relative to the root, e.g. `crates/foo`;
+    members: Vec<String>,
+    /// Root manifest, if any, which members may inherit fields from;
+    root_manifest: RefCell<Option<Manifest>>,
+    /// Member manifests on disk, by absolute manifest path;
+    manifests: RefCell<BTreeMap<PathBuf, Rc<MemberManifest>>>,
+    /// Manifests in git trees, by tree and manifest path relative to the repository root;
+    tree_manifests: RefCell<HashMap<(Oid, PathBuf), Option<Manifest>>>,
+    /// Where manifests are read from, shared with the `Manager` which writes them;
+    fs: Rc<dyn FileSystem>,
+}
+
+/// Manifest of a member as it is on disk;
+#[derive(Debug)]
+pub struct MemberManifest {
+    /// Absolute path of the manifest, for diagnostics;
+    pub path: PathBuf,
+    /// Contents as read, used for text edits that keep the formatting;
+    pub contents: String,
+    /// Editable document, e.g. to read `[package.metadata.cvm]`;
+    pub doc: DocumentMut,
+    /// Parsed manifest, with fields inherited from the workspace resolved;
+    pub manifest: Manifest,
+}
+
+impl MemberManifest {
+    /// Returns the package version, resolving a version inherited from the workspace; with
+    /// `lenient`, extra components such as `1.2.3.4` are accepted;
+    pub fn version(&self, lenient: bool) -> Result<Version, Error> {
+        manifest_version(&self.manifest, lenient)
+            .map_err(|e| Error::msg(format!("{} in {:?}", e, self.path.display())))
+    }
+
+    /// Returns true if the manifest omits the package version, which Cargo defaults to `0.0.0`
+    /// with `publish = false`;
+    pub fn omits_version(&self) -> bool {
+        self.manifest.package.is_some()
+            && fields::get(&self.doc, "version", false) == FieldValue::Missing
+    }
+
+    /// Returns true if the package version is inherited from `workspace.package.version`;
+    pub fn inherits_version(&self) -> bool {
+        fields::get(&self.doc, "version", false) == FieldValue::Inherited
+    }
+}
+
+impl WorkspaceModel {
+    pub fn new(root: PathBuf, members: Vec<String>, fs: Rc<dyn FileSystem>) -> Result<Self, Error> {
+        let cargo_toml = root.join("Cargo.toml");
+        let root_manifest = if fs.is_file(&cargo_toml) {
+            Some(fields::read_manifest(&*fs, &cargo_toml)?)
+        } else {
+            None
+        };
+
+        Self::with_root_manifest(root, members, root_manifest, fs)
+    }
+
+    /// Build the model with the contents of the root manifest, e.g. as read from a git tree;
+    pub fn with_root_manifest(
+        root: PathBuf,
+        members: Vec<String>,
+        root_manifest: Option<String>,
+        fs: Rc<dyn FileSystem>,
+    ) -> Result<Self, Error> {
+        let root_manifest = match root_manifest {
+            Some(contents) => Some(Manifest::from_str(&contents)?),
+            None => None,
+        };
+
+        Ok(Self {
+            root,
+            members,
+            root_manifest: RefCell::new(root_manifest),
+            manifests: RefCell::new(BTreeMap::new()),
+            tree_manifests: RefCell::new(HashMap::new()),
+            fs,
+        })
+    }
+
+    /// Workspace root, i.e. the directory CVM runs in;
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Member directories relative to the workspace root, e.g. `crates/foo`;
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    /// Returns the manifest of the member in `workspace`, reading it on first use;
+    pub fn member(&self, workspace: &Path) -> Result<Rc<MemberManifest>, Error> {
+        self.manifest(&workspace.join("Cargo.toml"))
+    }
+
+    /// Returns the member manifest at `cargo_toml`, reading it on first use;
+    pub fn manifest(&self, cargo_toml: &Path) -> Result<Rc<MemberManifest>, Error> {
+        let cargo_toml = self.root.join(cargo_toml);
+
+        if let Some(member) = self.manifests.borrow().get(&cargo_toml) {
+            return Ok(member.clone());
+        }
+
+        let contents = fields::read_manifest(&*self.fs, &cargo_toml)?;
+        self.update(&cargo_toml, contents)
+    }
+
+    /// Returns the package version of the member in `workspace`;
+    pub fn version(&self, workspace: &Path, lenient: bool) -> Result<Version, Error> {
+        self.member(workspace)?.version(lenient)
+    }
+
+    /// Returns `workspace.package.version` of the root manifest, which members may inherit;
+    pub fn workspace_version(&self, lenient: bool) -> Result<Option<Version>, Error> {
+        let root_manifest = self.root_manifest.borrow();
+        match root_manifest
+            .as_ref()
+            .and_then(|root| root.workspace.as_ref())
+            .and_then(|workspace| workspace.package.as_ref())
+            .and_then(|package| package.version.as_deref())
+        {
+            Some(version) => Ok(Some(Version::parse(version, lenient).map_err(|e| {
+                Error::msg(format!(
+                    "{} in `workspace.package.version` of {:?}",
+                    e,
+                    self.root.join("Cargo.toml").display()
+                ))
+            })?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace the manifest at `cargo_toml` with `contents`, e.g. after the run wrote them;
+    /// replacing the root manifest also resolves the inherited fields of the members again;
+    pub fn update(&self, cargo_toml: &Path, contents: String) -> Result<Rc<MemberManifest>, Error> {
+        let cargo_toml = self.root.join(cargo_toml);
+
+        if cargo_toml == self.root.join("Cargo.toml") {
+            self.root_manifest
+                .replace(Some(Manifest::from_str(&contents)?));
+            let members = self
+                .manifests
+                .borrow()
+                .iter()
+                .filter(|(path, _)| **path != cargo_toml)
+                .map(|(path, member)| (path.clone(), member.contents.clone()))
+                .collect::<Vec<_>>();
+            for (path, contents) in members {
+                self.complete(&path, contents)?;
+            }
+        }
+
+        self.complete(&cargo_toml, contents)
+    }
+
+    /// Parse the member manifest at the absolute `cargo_toml`, resolving the fields it
+    /// inherits from the root manifest, and cache it;
+    fn complete(&self, cargo_toml: &Path, contents: String) -> Result<Rc<MemberManifest>, Error> {
+        let cargo_toml = cargo_toml.to_path_buf();
+
+        let doc = contents.parse::<DocumentMut>().map_err(|e| {
+            Error::msg(format!(
+                "Invalid manifest {:?}: {}",
+                cargo_toml.display(),
+                e
+            ))
+        })?;
+        let mut manifest = Manifest::from_str(&contents)?;
+        manifest.complete_from_path_and_workspace(
+            &cargo_toml,
+            self.root_manifest
+                .borrow()
+                .as_ref()
+                .map(|root| (root, self.root.as_path())),
+        )?;
+
+        let member = Rc::new(MemberManifest {
+            path: cargo_toml.clone(),
+            contents,
+            doc,
+            manifest,
+        });
+        self.manifests
+            .borrow_mut()
+            .insert(cargo_toml, member.clone());
+
+        Ok(member)
+    }
+
+    /// Returns the manifest at `manifest_path` in the tree, parsing it with `load` on first use;
+    pub fn tree_manifest(
+        &self,
+        tree: Oid,
+        manifest_path: &Path,
+        load: impl FnOnce() -> Result<Option<Manifest>, Error>,
+    ) -> Result<Option<Manifest>, Error> {
+        let key = (tree, manifest_path.to_path_buf());
+
+        if let Some(manifest) = self.tree_manifests.borrow().get(&key) {
+            return Ok(manifest.clone());
+        }
+
+        let manifest = load()?;
+        self.tree_manifests
+            .borrow_mut()
+            .insert(key, manifest.clone());
+
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkspaceModel;
+    use crate::fs::{FileSystem, RecordingFs};
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_member_is_read_once() -> Result<(), Box<dyn std::error::Error>> {
+        let root = PathBuf::from("/cvm-workspace");
+        let fs = Rc::new(RecordingFs::default());
+        fs.write(
+            &root.join("foo/Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        )?;
+
+        let model = WorkspaceModel::new(root.clone(), vec![String::from("foo")], fs.clone())?;
+        assert_eq!(model.version(Path::new("foo"), false)?.to_string(), "0.1.0");
+
+        // Later reads use the cached manifest, and updates replace it;
+        fs.write(&root.join("foo/Cargo.toml"), "not a manifest")?;
+        assert_eq!(model.version(Path::new("foo"), false)?.to_string(), "0.1.0");
+        model.update(
+            Path::new("foo/Cargo.toml"),
+            String::from("[package]\nname = \"foo\"\nversion = \"0.2.0\"\n"),
+        )?;
+        assert_eq!(model.version(Path::new("foo"), false)?.to_string(), "0.2.0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_omitted_version() -> Result<(), Box<dyn std::error::Error>> {
+        let root = PathBuf::from("/cvm-unversioned");
+        let fs = Rc::new(RecordingFs::default());
+        fs.write(&root.join("foo/Cargo.toml"), "[package]\nname = \"foo\"\n")?;
+
+        let model = WorkspaceModel::new(root, vec![String::from("foo")], fs)?;
+        let member = model.member(Path::new("foo"))?;
+        assert!(member.omits_version());
+        assert_eq!(member.version(false)?.to_string(), "0.0.0");
+
+        Ok(())
+    }
+}